@@ -1,20 +1,97 @@
 CREATE TABLE IF NOT EXISTS warehouses (
     id INTEGER UNIQUE NOT NULL PRIMARY KEY,
-    name TEXT UNIQUE NOT NULL
+    name TEXT UNIQUE NOT NULL,
+    -- Unix timestamp, NULL unless archived (`deposito archive`)
+    archived_at INTEGER
 );
 
 CREATE TABLE IF NOT EXISTS items (
     id INTEGER UNIQUE NOT NULL PRIMARY KEY,
     warehouse_id INTEGER NOT NULL,
-    name TEXT UNIQUE NOT NULL,
+    name TEXT NOT NULL,
     description TEXT,
     quantity INTEGER NOT NULL,
+    unit TEXT,
+    -- Comma separated
+    tags TEXT,
+    -- Unix timestamp, NULL unless archived
+    archived_at INTEGER,
+    -- Names are unique within a warehouse, other warehouses may have items of the same name
+    UNIQUE (warehouse_id, name),
     FOREIGN KEY (warehouse_id) REFERENCES warehouses(id)
 );
 
 CREATE TABLE IF NOT EXISTS rules (
     id INTEGER UNIQUE NOT NULL PRIMARY KEY,
     item_id INTEGER UNIQUE NOT NULL,
-    gets_below_quantity INTEGER NOT NULL,
+    gets_below_quantity INTEGER,
+    expression TEXT,
+    severity TEXT NOT NULL DEFAULT 'critical' CHECK (severity IN ('info', 'warn', 'critical')),
+    CHECK (gets_below_quantity IS NOT NULL OR expression IS NOT NULL),
     FOREIGN KEY (item_id) REFERENCES items(id)
 );
+
+CREATE TABLE IF NOT EXISTS movements (
+    id INTEGER UNIQUE NOT NULL PRIMARY KEY,
+    item_id INTEGER NOT NULL,
+    delta INTEGER NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+    FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+);
+
+-- Frozen copies of all item quantities and rules (`deposito snapshot`). Items are kept by
+-- name rather than id, so that snapshots outlive them.
+CREATE TABLE IF NOT EXISTS snapshots (
+    id INTEGER UNIQUE NOT NULL PRIMARY KEY,
+    label TEXT UNIQUE NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER))
+);
+
+CREATE TABLE IF NOT EXISTS snapshot_items (
+    id INTEGER UNIQUE NOT NULL PRIMARY KEY,
+    snapshot_id INTEGER NOT NULL,
+    warehouse TEXT NOT NULL,
+    name TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    -- All NULL without a rule
+    gets_below_quantity INTEGER,
+    expression TEXT,
+    severity TEXT,
+    FOREIGN KEY (snapshot_id) REFERENCES snapshots(id) ON DELETE CASCADE
+);
+
+-- Stock lists new warehouses can start from (`deposito template`, `create --template`).
+-- The one named `default` is used for every new warehouse unless told otherwise.
+CREATE TABLE IF NOT EXISTS templates (
+    id INTEGER UNIQUE NOT NULL PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER))
+);
+
+CREATE TABLE IF NOT EXISTS template_items (
+    id INTEGER UNIQUE NOT NULL PRIMARY KEY,
+    template_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    quantity INTEGER NOT NULL,
+    unit TEXT,
+    tags TEXT,
+    -- All NULL without a rule
+    gets_below_quantity INTEGER,
+    expression TEXT,
+    severity TEXT,
+    UNIQUE (template_id, name),
+    FOREIGN KEY (template_id) REFERENCES templates(id) ON DELETE CASCADE
+);
+
+-- Undo journal (`deposito undo`): the rows a command changed, as they were before and
+-- after it, as JSON arrays (see `journal.rs`)
+CREATE TABLE IF NOT EXISTS journal (
+    id INTEGER UNIQUE NOT NULL PRIMARY KEY,
+    command TEXT NOT NULL,
+    rows_before TEXT NOT NULL,
+    rows_after TEXT NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+    -- Undone commands can be redone, until the next command is recorded
+    undone INTEGER NOT NULL DEFAULT 0 CHECK (undone IN (0, 1))
+);
@@ -1,7 +1,9 @@
 --DELETE FROM warehouses;
 --DELETE FROM items;
 --DELETE FROM rules;
+--DELETE FROM movements;
 
 DROP TABLE IF EXISTS warehouses;
 DROP TABLE IF EXISTS items;
 DROP TABLE IF EXISTS rules;
+DROP TABLE IF EXISTS movements;
@@ -84,3 +84,21 @@ UPDATE rules SET gets_below_quantity = $2 WHERE rules.item_id IN (SELECT items.i
 
 --- Remove rules
 DELETE FROM rules WHERE rules.item_id IN (SELECT items.id FROM items JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = $1 AND items.name IN (?));
+
+--- Record a movement (quantity change) of an item
+INSERT INTO movements (id, item_id, delta) VALUES (NULL, (SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE warehouses.name = $1 AND items.name = $2), $3);
+
+--- Select movements of items in a specific warehouse
+SELECT
+    i.name,
+    m.delta,
+    m.created_at
+FROM
+    movements m
+    JOIN items i ON i.id = m.item_id
+    JOIN warehouses w ON w.id = i.warehouse_id
+WHERE
+    w.name = "Fridge";
+
+--- Create an expression rule
+INSERT INTO rules (id, item_id, gets_below_quantity, expression, severity) VALUES (NULL, (SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE items.name = $2 AND warehouses.name = $1), NULL, $3, $4)
@@ -0,0 +1,63 @@
+use crate::raport;
+
+// Naming and rotation of the backups `deposito backup` takes, and the tables `deposito
+// restore` copies back.
+
+// Backups go here unless told otherwise, relative to the deposito directory
+pub const DIR_NAME: &str = "backups";
+pub const DEFAULT_KEEP: usize = 10;
+
+// Tables of the workspace with their columns, in an order that satisfies the foreign keys
+pub const TABLES: &[(&str, &str)] = &[
+    ("warehouses", "id, name, archived_at"),
+    ("items", "id, warehouse_id, name, description, quantity, unit, tags, archived_at"),
+    ("rules", "id, item_id, gets_below_quantity, expression, severity"),
+    ("movements", "id, item_id, delta, created_at"),
+    ("snapshots", "id, label, created_at"),
+    ("snapshot_items", "id, snapshot_id, warehouse, name, quantity, gets_below_quantity, expression, severity"),
+    ("journal", "id, command, rows_before, rows_after, created_at, undone"),
+    ("templates", "id, name, created_at"),
+    ("template_items", "id, template_id, name, description, quantity, unit, tags, gets_below_quantity, expression, severity"),
+];
+
+// Tables every version of the schema has, which make a database a deposito one. Older
+// ones may lack the rest, `restore` upgrades them first.
+pub const REQUIRED: &[&str] = &["warehouses", "items", "rules"];
+
+const PREFIX: &str = "deposito-";
+const EXTENSION: &str = ".db";
+
+// `deposito-20240610-140312.db`, with `-2`, `-3`, ... for backups taken within the same second
+pub fn file_name(timestamp: i64, attempt: u32) -> String {
+    let (year, month, day, hour, minute, second) = raport::civil(timestamp);
+    let stamp = format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, hour, minute, second);
+    match attempt {
+        0 | 1 => format!("{}{}{}", PREFIX, stamp, EXTENSION),
+        attempt => format!("{}{}-{}{}", PREFIX, stamp, attempt, EXTENSION),
+    }
+}
+
+// When a file named by `file_name` was taken, `None` for any other file
+fn taken_at(name: &str) -> Option<(&str, u32)> {
+    let stem = name.strip_prefix(PREFIX)?.strip_suffix(EXTENSION)?;
+    let (stamp, attempt) = match stem.get(15..) {
+        Some("") => (stem, 1),
+        Some(suffix) => (&stem[..15], suffix.strip_prefix('-')?.parse().ok()?),
+        None => return None,
+    };
+
+    let valid = stamp.char_indices().all(|(index, char)| if index == 8 { char == '-' } else { char.is_ascii_digit() });
+    valid.then_some((stamp, attempt))
+}
+
+// Backups among the file names beyond the newest `keep`, which rotation removes. Other
+// files are never touched, and `keep` of 0 keeps everything.
+pub fn expired(names: &[String], keep: usize) -> Vec<&str> {
+    if keep == 0 {
+        return Vec::new();
+    }
+
+    let mut backups: Vec<_> = names.iter().filter_map(|name| taken_at(name).map(|taken| (taken, name.as_str()))).collect();
+    backups.sort_by(|a, b| b.cmp(a));
+    backups.into_iter().skip(keep).map(|(_, name)| name).collect()
+}
@@ -0,0 +1,824 @@
+use thiserror::Error;
+use unicode_width::UnicodeWidthStr;
+use crate::commands::{COMMANDS, DESCRIPTION, GLOBAL_OPTIONS, SYNOPSIS};
+use crate::style;
+use crate::suggest;
+
+// Declarative description of the command line. `commands.rs` holds the actual
+// definitions, this module parses arguments against them and renders usage and help.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueKind {
+    Text,
+    Integer,
+    Choice(&'static [&'static str]),
+}
+
+pub struct OptionSpec {
+    pub long: &'static str,
+    pub short: Option<char>,
+    // Name and type of the value, `None` for flags
+    pub value: Option<(&'static str, ValueKind)>,
+    pub help: &'static str,
+}
+
+impl OptionSpec {
+    pub const fn flag(short: Option<char>, long: &'static str, help: &'static str) -> Self {
+        Self { long, short, value: None, help }
+    }
+
+    pub const fn value(short: Option<char>, long: &'static str, name: &'static str, kind: ValueKind, help: &'static str) -> Self {
+        Self { long, short, value: Some((name, kind)), help }
+    }
+
+    pub fn signature(&self) -> String {
+        let mut signature = match self.short {
+            Some(short) => format!("-{}, --{}", short, self.long),
+            None => format!("    --{}", self.long),
+        };
+        if let Some((name, _)) = self.value {
+            signature.push_str(&format!("=<{}>", name));
+        }
+        signature
+    }
+
+    // How the option is written in a usage line
+    fn usage(&self) -> String {
+        match (self.short, self.value) {
+            (Some(short), None) => format!("-{}", short),
+            (None, None) => format!("--{}", self.long),
+            (_, Some((name, _))) => format!("--{}=<{}>", self.long, name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Required,
+    // Optional trailing argument, with the default used when it's missing
+    Optional(Option<&'static str>),
+    // One or more values
+    Many,
+}
+
+// What shell completion suggests for an argument besides the values of its kind
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Suggest {
+    Nothing,
+    Warehouses,
+    // Items of the warehouse given in the form's warehouse argument
+    Items,
+    Commands,
+}
+
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub kind: ValueKind,
+    pub arity: Arity,
+    pub suggest: Suggest,
+}
+
+impl ArgSpec {
+    pub const fn required(name: &'static str) -> Self {
+        Self { name, kind: ValueKind::Text, arity: Arity::Required, suggest: Suggest::Nothing }
+    }
+
+    pub const fn optional(name: &'static str, default: Option<&'static str>) -> Self {
+        Self { name, kind: ValueKind::Text, arity: Arity::Optional(default), suggest: Suggest::Nothing }
+    }
+
+    pub const fn many(name: &'static str) -> Self {
+        Self { name, kind: ValueKind::Text, arity: Arity::Many, suggest: Suggest::Nothing }
+    }
+
+    pub const fn integer(mut self) -> Self {
+        self.kind = ValueKind::Integer;
+        self
+    }
+
+    pub const fn choice(mut self, choices: &'static [&'static str]) -> Self {
+        self.kind = ValueKind::Choice(choices);
+        self
+    }
+
+    pub const fn warehouse(mut self) -> Self {
+        self.suggest = Suggest::Warehouses;
+        self
+    }
+
+    pub const fn item(mut self) -> Self {
+        self.suggest = Suggest::Items;
+        self
+    }
+
+    pub const fn command(mut self) -> Self {
+        self.suggest = Suggest::Commands;
+        self
+    }
+
+    fn usage(&self) -> String {
+        match self.arity {
+            Arity::Required => format!("<{}>", self.name),
+            Arity::Optional(Some(default)) => format!("[<{}> | {}]", self.name, default),
+            Arity::Optional(None) => format!("[<{}>]", self.name),
+            Arity::Many => format!("<{}>...", self.name),
+        }
+    }
+}
+
+// One way of invoking a command. Forms are told apart by their mode options, e.g.
+// `rules -l` and `rules -c` take different arguments.
+pub struct Form {
+    pub modes: &'static [&'static str],
+    pub args: &'static [ArgSpec],
+}
+
+impl Form {
+    pub const fn new(modes: &'static [&'static str], args: &'static [ArgSpec]) -> Self {
+        Self { modes, args }
+    }
+}
+
+// Extra block of the help text, like the expression reference of `rules`
+pub struct Section {
+    pub title: &'static str,
+    pub entries: &'static [(&'static str, &'static str)],
+}
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub forms: &'static [Form],
+    pub options: &'static [OptionSpec],
+    pub examples: &'static [&'static str],
+    pub sections: &'static [Section],
+    pub notes: &'static [&'static str],
+    pub requires_workspace: bool,
+}
+
+impl CommandSpec {
+    pub const fn new(name: &'static str, summary: &'static str) -> Self {
+        Self {
+            name,
+            summary,
+            forms: &[],
+            options: &[],
+            examples: &[],
+            sections: &[],
+            notes: &[],
+            requires_workspace: true,
+        }
+    }
+
+    pub const fn forms(mut self, forms: &'static [Form]) -> Self {
+        self.forms = forms;
+        self
+    }
+
+    pub const fn options(mut self, options: &'static [OptionSpec]) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub const fn examples(mut self, examples: &'static [&'static str]) -> Self {
+        self.examples = examples;
+        self
+    }
+
+    pub const fn sections(mut self, sections: &'static [Section]) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    pub const fn notes(mut self, notes: &'static [&'static str]) -> Self {
+        self.notes = notes;
+        self
+    }
+
+    pub const fn without_workspace(mut self) -> Self {
+        self.requires_workspace = false;
+        self
+    }
+
+    pub fn find(name: &str) -> Option<&'static CommandSpec> {
+        COMMANDS.iter().find(|command| command.name == name)
+    }
+
+    pub fn option(&self, long: &str) -> Option<&'static OptionSpec> {
+        self.options.iter().chain(GLOBAL_OPTIONS.iter()).find(|option| option.long == long)
+    }
+
+    pub fn short_option(&self, short: char) -> Option<&'static OptionSpec> {
+        self.options.iter().chain(GLOBAL_OPTIONS.iter()).find(|option| option.short == Some(short))
+    }
+
+    pub fn mode_options(&self) -> Vec<&'static str> {
+        let mut modes: Vec<&'static str> = Vec::new();
+        for form in self.forms.iter() {
+            for mode in form.modes.iter() {
+                if !modes.contains(mode) { modes.push(mode); }
+            }
+        }
+        modes
+    }
+
+    pub fn usage(&self) -> Vec<String> {
+        let modes = self.mode_options();
+        let has_options = self.options.iter().any(|option| !modes.contains(&option.long));
+
+        self.forms.iter().map(|form| {
+            let mut usage = format!("deposito {}", self.name);
+            for mode in form.modes.iter() {
+                if let Some(option) = self.option(mode) {
+                    usage.push(' ');
+                    usage.push_str(&option.usage());
+                }
+            }
+            if has_options {
+                usage.push_str(" [options]");
+            }
+            for arg in form.args.iter() {
+                usage.push(' ');
+                usage.push_str(&arg.usage());
+            }
+            usage
+        }).collect()
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CliError {
+    #[error("Unknown command `{0}`.{}", suggest::did_you_mean(.1))]
+    UnknownCommand(String, Option<String>),
+
+    #[error("Unknown option `{0}`.{}", suggest::did_you_mean(.1))]
+    UnknownOption(String, Option<String>),
+
+    #[error("Option `--{0}` requires a value.")]
+    MissingValue(&'static str),
+
+    #[error("Option `--{0}` doesn't take a value.")]
+    UnexpectedValue(&'static str),
+
+    #[error("Invalid value `{value}` for `{name}`, expected {expected}.")]
+    InvalidValue { name: String, value: String, expected: String },
+
+    #[error("`{command}` requires one of the options {options}.")]
+    MissingMode { command: &'static str, options: String },
+
+    #[error("Options {0} can't be used together.")]
+    ConflictingModes(String),
+
+    #[error("`{command}` is missing the <{arg}> argument.")]
+    MissingArgument { command: &'static str, arg: &'static str },
+
+    #[error("`{command}` got too many arguments, `{extra}` is unexpected.")]
+    TooManyArguments { command: &'static str, extra: String },
+}
+
+impl CliError {
+    pub fn unknown_command(name: &str) -> Self {
+        let suggestion = suggest::closest(name, COMMANDS.iter().map(|command| command.name));
+        CliError::UnknownCommand(name.to_string(), suggestion.map(str::to_string))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    Integer(i64),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Text(text) => write!(formatter, "{}", text),
+            Value::Integer(integer) => write!(formatter, "{}", integer),
+        }
+    }
+}
+
+impl Value {
+    fn parse(name: String, value: &str, kind: ValueKind) -> Result<Value, CliError> {
+        match kind {
+            ValueKind::Text => Ok(Value::Text(value.to_string())),
+            ValueKind::Integer => {
+                match value.parse::<i64>() {
+                    Ok(integer) => Ok(Value::Integer(integer)),
+                    Err(_) => Err(CliError::InvalidValue { name, value: value.to_string(), expected: String::from("a whole number") }),
+                }
+            }
+            ValueKind::Choice(choices) => {
+                if choices.contains(&value) {
+                    return Ok(Value::Text(value.to_string()));
+                }
+                Err(CliError::InvalidValue { name, value: value.to_string(), expected: format!("one of {}", choices.join(", ")) })
+            }
+        }
+    }
+
+    fn as_text(&self) -> Option<&str> {
+        match self {
+            Value::Text(text) => Some(text),
+            Value::Integer(_) => None,
+        }
+    }
+
+    fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(integer) => Some(*integer),
+            Value::Text(_) => None,
+        }
+    }
+}
+
+pub struct Matches {
+    pub command: Option<&'static CommandSpec>,
+    pub form: Option<&'static Form>,
+    // `-h`/`--help` was given, nothing beyond the command name got validated
+    pub help: bool,
+    options: Vec<(&'static OptionSpec, Option<Value>)>,
+    args: Vec<(&'static ArgSpec, Vec<Value>)>,
+}
+
+impl Matches {
+    pub fn flag(&self, long: &str) -> bool {
+        self.options.iter().any(|(option, _)| option.long == long)
+    }
+
+    // Value of an option, the last occurrence wins
+    fn option_value(&self, long: &str) -> Option<&Value> {
+        self.options.iter().rev().find(|(option, _)| option.long == long).and_then(|(_, value)| value.as_ref())
+    }
+
+    pub fn text(&self, long: &str) -> Option<&str> {
+        self.option_value(long).and_then(Value::as_text)
+    }
+
+    pub fn integer(&self, long: &str) -> Option<i64> {
+        self.option_value(long).and_then(Value::as_integer)
+    }
+
+    fn arg_values(&self, name: &str) -> &[Value] {
+        match self.args.iter().find(|(arg, _)| arg.name == name) {
+            Some((_, values)) => values,
+            None => &[],
+        }
+    }
+
+    pub fn arg(&self, name: &str) -> Option<&str> {
+        self.arg_values(name).first().and_then(Value::as_text)
+    }
+
+    pub fn arg_integer(&self, name: &str) -> Option<i64> {
+        self.arg_values(name).first().and_then(Value::as_integer)
+    }
+
+    pub fn arg_list(&self, name: &str) -> Vec<String> {
+        self.arg_values(name).iter().filter_map(Value::as_text).map(str::to_string).collect()
+    }
+
+    pub fn is_mode(&self, mode: &str) -> bool {
+        self.form.is_some_and(|form| form.modes.contains(&mode))
+    }
+
+    // The command as it could have been typed (options first, defaults filled in), to
+    // show it back in the undo journal
+    pub fn command_line(&self) -> String {
+        let quote = |value: &Value| {
+            let value = value.to_string();
+            match shlex::try_quote(&value) {
+                Ok(quoted) => quoted.into_owned(),
+                Err(_) => value,
+            }
+        };
+
+        let mut words: Vec<String> = self.command.map(|command| command.name.to_string()).into_iter().collect();
+        for (option, value) in self.options.iter() {
+            words.push(match (option.short, value) {
+                (Some(short), None) => format!("-{}", short),
+                (None, None) => format!("--{}", option.long),
+                (_, Some(value)) => format!("--{}={}", option.long, quote(value)),
+            });
+        }
+        for (_, values) in self.args.iter() {
+            words.extend(values.iter().map(quote));
+        }
+        words.join(" ")
+    }
+}
+
+// Negative numbers are arguments, not clusters of short options
+pub fn is_option(arg: &str) -> bool {
+    arg.len() > 1 && arg.starts_with('-') && !arg[1..].starts_with(|char: char| char.is_ascii_digit())
+}
+
+pub fn parse(args: &[String]) -> Result<Matches, CliError> {
+    let mut matches = Matches { command: None, form: None, help: false, options: Vec::new(), args: Vec::new() };
+    let mut positional: Vec<String> = Vec::new();
+    let mut tokens = args.iter();
+    let mut terminated = false;
+
+    while let Some(token) = tokens.next() {
+        if terminated || !is_option(token) {
+            match matches.command {
+                Some(_) => { positional.push(token.clone()); }
+                None => {
+                    match CommandSpec::find(token) {
+                        Some(command) => { matches.command = Some(command); }
+                        None => { return Err(CliError::unknown_command(token)); }
+                    }
+                }
+            }
+            continue;
+        }
+
+        if token == "--" {
+            terminated = true;
+            continue;
+        }
+
+        // Options are looked up in the command (once known) and the global options
+        let command = matches.command;
+        let lookup_long = |long: &str| match command {
+            Some(command) => command.option(long),
+            None => GLOBAL_OPTIONS.iter().find(|option| option.long == long),
+        };
+        let lookup_short = |short: char| match command {
+            Some(command) => command.short_option(short),
+            None => GLOBAL_OPTIONS.iter().find(|option| option.short == Some(short)),
+        };
+
+        if let Some(long) = token.strip_prefix("--") {
+            let (name, inline) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+
+            let option = match lookup_long(name) {
+                Some(option) => option,
+                None => {
+                    let options = command.map(|command| command.options).unwrap_or_default().iter().chain(GLOBAL_OPTIONS.iter());
+                    let suggestion = suggest::closest(name, options.map(|option| option.long)).map(|long| format!("--{}", long));
+                    return Err(CliError::UnknownOption(format!("--{}", name), suggestion));
+                }
+            };
+
+            let value = match (option.value, inline) {
+                (None, None) => None,
+                (None, Some(_)) => { return Err(CliError::UnexpectedValue(option.long)); }
+                (Some((_, kind)), Some(value)) => Some(Value::parse(format!("--{}", option.long), &value, kind)?),
+                (Some((_, kind)), None) => {
+                    match tokens.next() {
+                        Some(value) => Some(Value::parse(format!("--{}", option.long), value, kind)?),
+                        None => { return Err(CliError::MissingValue(option.long)); }
+                    }
+                }
+            };
+
+            matches.options.push((option, value));
+            continue;
+        }
+
+        // Cluster of short options, `-ia` is `-i -a` and `-q6`, `-q=6` or `-q 6` all set `-q`
+        let cluster: Vec<char> = token[1..].chars().collect();
+        for (index, short) in cluster.iter().enumerate() {
+            let option = match lookup_short(*short) {
+                Some(option) => option,
+                None => { return Err(CliError::UnknownOption(format!("-{}", short), None)); }
+            };
+
+            let (_, kind) = match option.value {
+                Some(value) => value,
+                None => { matches.options.push((option, None)); continue; }
+            };
+
+            let rest: String = cluster[index+1..].iter().collect();
+            let rest = rest.strip_prefix('=').unwrap_or(&rest).to_string();
+            let value = if !rest.is_empty() {
+                rest
+            } else {
+                match tokens.next() {
+                    Some(value) => value.clone(),
+                    None => { return Err(CliError::MissingValue(option.long)); }
+                }
+            };
+
+            matches.options.push((option, Some(Value::parse(format!("-{}", short), &value, kind)?)));
+            break;
+        }
+    }
+
+    matches.help = matches.flag("help");
+    let command = match matches.command {
+        Some(command) if !matches.help => command,
+        _ => { return Ok(matches); }
+    };
+
+    let form = select_form(command, &matches)?;
+    matches.form = Some(form);
+    matches.args = assign_args(command, form, positional)?;
+
+    Ok(matches)
+}
+
+// Picks the most specific form whose mode options were all given
+fn select_form(command: &'static CommandSpec, matches: &Matches) -> Result<&'static Form, CliError> {
+    let candidates: Vec<&'static Form> = command.forms.iter()
+        .filter(|form| form.modes.iter().all(|mode| matches.flag(mode)))
+        .collect();
+
+    let most_specific = candidates.iter().map(|form| form.modes.len()).max();
+    let best: Vec<&'static Form> = candidates.into_iter().filter(|form| Some(form.modes.len()) == most_specific).collect();
+
+    let describe = |modes: &[&str]| -> String {
+        modes.iter().map(|mode| format!("`--{}`", mode)).collect::<Vec<_>>().join(", ")
+    };
+
+    let form = match best.as_slice() {
+        [form] => *form,
+        [] => {
+            // Value options such as `--expr` only refine a form, so don't offer them here
+            let modes: Vec<&str> = command.mode_options().into_iter()
+                .filter(|mode| command.option(mode).is_some_and(|option| option.value.is_none()))
+                .collect();
+            return Err(CliError::MissingMode { command: command.name, options: describe(&modes) });
+        }
+        forms => {
+            let modes: Vec<&str> = forms.iter().flat_map(|form| form.modes.iter().copied()).collect();
+            return Err(CliError::ConflictingModes(describe(&modes)));
+        }
+    };
+
+    // Mode options of other forms don't mean anything here, reject them instead of ignoring them
+    let stray: Vec<&str> = command.mode_options().into_iter()
+        .filter(|mode| matches.flag(mode) && !form.modes.contains(mode))
+        .collect();
+    if !stray.is_empty() {
+        let mut modes: Vec<&str> = form.modes.to_vec();
+        modes.extend(stray);
+        return Err(CliError::ConflictingModes(describe(&modes)));
+    }
+
+    Ok(form)
+}
+
+// Distributes positional arguments over the form's arguments. A `Many` argument takes
+// whatever is left over once the arguments before and after it are satisfied.
+fn assign_args(command: &'static CommandSpec, form: &'static Form, positional: Vec<String>) -> Result<Vec<(&'static ArgSpec, Vec<Value>)>, CliError> {
+    let specs = form.args;
+    let required_after = |index: usize| specs[index+1..].iter().filter(|spec| spec.arity == Arity::Required).count();
+
+    let mut assigned = Vec::with_capacity(specs.len());
+    let mut remaining = positional.into_iter().peekable();
+    let mut left = remaining.len();
+
+    for (index, spec) in specs.iter().enumerate() {
+        let take = match spec.arity {
+            Arity::Required => {
+                if left == 0 {
+                    return Err(CliError::MissingArgument { command: command.name, arg: spec.name });
+                }
+                1
+            }
+            Arity::Optional(_) => left.saturating_sub(required_after(index)).min(1),
+            Arity::Many => {
+                if left == 0 {
+                    return Err(CliError::MissingArgument { command: command.name, arg: spec.name });
+                }
+                // When short on arguments, the ones after it are reported missing instead
+                left.saturating_sub(required_after(index)).max(1)
+            }
+        };
+
+        let mut values = Vec::with_capacity(take);
+        for _ in 0..take {
+            if let Some(raw) = remaining.next() {
+                values.push(Value::parse(format!("<{}>", spec.name), &raw, spec.kind)?);
+            }
+        }
+        left -= take;
+
+        if let (Arity::Optional(Some(default)), true) = (spec.arity, values.is_empty()) {
+            values.push(Value::parse(format!("<{}>", spec.name), default, spec.kind)?);
+        }
+
+        assigned.push((spec, values));
+    }
+
+    if let Some(extra) = remaining.next() {
+        return Err(CliError::TooManyArguments { command: command.name, extra });
+    }
+
+    Ok(assigned)
+}
+
+pub fn print_aligned(entries: &[(String, &str)]) {
+    let width = entries.iter().map(|(term, _)| term.width()).max().unwrap_or_default();
+    for (term, description) in entries.iter() {
+        let padding = " ".repeat(width - term.width());
+        println!("{}", format!("\t{}{}  {}", term, padding, description).trim_end());
+    }
+}
+
+// Reports a parse error, followed by how the command is supposed to be used if it got
+// that far
+pub fn print_error(err: &CliError, args: &[String]) {
+    println!("{}", err);
+
+    let command = args.iter().take_while(|arg| *arg != "--").find_map(|arg| CommandSpec::find(arg));
+    if let (Some(command), false) = (command, matches!(err, CliError::UnknownCommand(..))) {
+        println!();
+        print_usage(command);
+        println!();
+        println!("For more information run `{}`", style::bold(format!("deposito {} -h", command.name)));
+    }
+}
+
+pub fn print_usage(command: &CommandSpec) {
+    println!("{}", style::bold("Usage:"));
+    for usage in command.usage() {
+        println!("\t{}", usage);
+    }
+}
+
+pub fn print_help(command: Option<&CommandSpec>) {
+    let command = match command {
+        Some(command) => command,
+        None => {
+            println!("{}", style::bold(format!("deposito - {}.", DESCRIPTION)));
+            println!();
+            println!("{}", style::bold("Usage:"));
+            println!("\t{}", SYNOPSIS);
+            println!();
+            println!("{}", style::bold("Global options:"));
+            let globals: Vec<(String, &str)> = GLOBAL_OPTIONS.iter().map(|option| (option.signature(), option.help)).collect();
+            print_aligned(&globals);
+            println!();
+            println!("{}", style::bold("Commands:"));
+            println!("\tFor more information about a command run the command with `{}`", style::bold("-h"));
+            println!();
+            for command in COMMANDS.iter() {
+                println!("\t{} - {}", command.name, command.summary);
+            }
+            return;
+        }
+    };
+
+    println!("{} {}.", style::bold(format!("`{}`", command.name)), command.summary);
+    println!();
+    print_usage(command);
+
+    if !command.examples.is_empty() {
+        println!();
+        println!("{}", style::bold("Example:"));
+        for example in command.examples.iter() {
+            println!("\t{}", example);
+        }
+    }
+
+    if !command.options.is_empty() {
+        println!();
+        println!("{}", style::bold("Options:"));
+        let options: Vec<(String, &str)> = command.options.iter().map(|option| (option.signature(), option.help)).collect();
+        print_aligned(&options);
+    }
+
+    for section in command.sections.iter() {
+        println!();
+        println!("{}", style::bold(format!("{}:", section.title)));
+        let entries: Vec<(String, &str)> = section.entries.iter().map(|(term, description)| (term.to_string(), *description)).collect();
+        print_aligned(&entries);
+    }
+
+    if !command.notes.is_empty() {
+        println!();
+        for note in command.notes.iter() {
+            println!("{}", note);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::FORMATS;
+
+    fn parse_line(line: &str) -> Result<Matches, CliError> {
+        parse(&shlex::split(line).expect("unbalanced quotes"))
+    }
+
+    #[test]
+    fn short_option_clusters() {
+        let matches = parse_line("remove -ia Fridge").expect("doesn't parse");
+        assert!(matches.flag("item") && matches.flag("all"));
+        assert!(matches.is_mode("all"));
+        assert_eq!(matches.arg("warehouse"), Some("Fridge"));
+
+        // A value option ends the cluster, taking the rest of it or else the next argument
+        for line in ["edit -q6 Fridge Egg", "edit -q=6 Fridge Egg", "edit -q 6 Fridge Egg"] {
+            let matches = parse_line(line).expect(line);
+            assert_eq!(matches.integer("quantity"), Some(6), "{}", line);
+            assert_eq!(matches.arg("item"), Some("Egg"), "{}", line);
+        }
+        assert_eq!(parse_line("edit -nq6 Fridge Egg").expect("doesn't parse").text("name"), Some("q6"));
+
+        assert_eq!(parse_line("remove -ix Fridge").err(), Some(CliError::UnknownOption(String::from("-x"), None)));
+        assert_eq!(parse_line("edit Fridge Egg -q").err(), Some(CliError::MissingValue("quantity")));
+    }
+
+    #[test]
+    fn long_options() {
+        for line in ["edit --name=Eggs Fridge Egg", "edit --name Eggs Fridge Egg", "edit Fridge Egg --name Eggs"] {
+            let matches = parse_line(line).expect(line);
+            assert_eq!(matches.text("name"), Some("Eggs"), "{}", line);
+            assert_eq!(matches.arg("warehouse"), Some("Fridge"), "{}", line);
+            assert_eq!(matches.arg("item"), Some("Egg"), "{}", line);
+        }
+
+        // The value goes up to the end of the word, the last occurrence wins
+        let matches = parse_line("rules -c \"--expr=quantity == 1\" --severity=warn --severity=info Fridge Egg").expect("doesn't parse");
+        assert_eq!(matches.text("expr"), Some("quantity == 1"));
+        assert_eq!(matches.text("severity"), Some("info"));
+
+        assert_eq!(parse_line("edit Fridge Egg --name").err(), Some(CliError::MissingValue("name")));
+        assert_eq!(parse_line("list --include-archived=yes Fridge").err(), Some(CliError::UnexpectedValue("include-archived")));
+        assert_eq!(
+            parse_line("list --include-archive Fridge").err(),
+            Some(CliError::UnknownOption(String::from("--include-archive"), Some(String::from("--include-archived")))),
+        );
+    }
+
+    #[test]
+    fn global_options() {
+        let matches = parse_line("--format=json list Fridge").expect("doesn't parse");
+        assert_eq!(matches.text("format"), Some("json"));
+        assert_eq!(parse_line("list --format json Fridge").expect("doesn't parse").text("format"), Some("json"));
+        assert!(parse_line("list Fridge -h").expect("doesn't parse").help);
+
+        // Help skips validating the arguments
+        assert!(parse_line("rules -h").expect("doesn't parse").form.is_none());
+        assert_eq!(
+            parse_line("list --format=yaml Fridge").err(),
+            Some(CliError::InvalidValue { name: String::from("--format"), value: String::from("yaml"), expected: format!("one of {}", FORMATS.join(", ")) }),
+        );
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let matches = parse_line("add -- Fridge -Egg 2").expect("doesn't parse");
+        assert_eq!(matches.arg("item"), Some("-Egg"));
+        assert_eq!(matches.arg_integer("quantity"), Some(2));
+
+        let matches = parse_line("edit -n Eggs -- Fridge --help").expect("doesn't parse");
+        assert!(!matches.help);
+        assert_eq!(matches.arg("item"), Some("--help"));
+        assert_eq!(parse_line("add Fridge -Egg").err(), Some(CliError::UnknownOption(String::from("-E"), None)));
+    }
+
+    #[test]
+    fn typed_and_required_arguments() {
+        let matches = parse_line("add Fridge Egg").expect("doesn't parse");
+        assert_eq!(matches.arg_integer("quantity"), Some(1));
+        assert_eq!(
+            parse_line("add Fridge Egg six").err(),
+            Some(CliError::InvalidValue { name: String::from("<quantity>"), value: String::from("six"), expected: String::from("a whole number") }),
+        );
+        assert_eq!(parse_line("add Fridge").err(), Some(CliError::MissingArgument { command: "add", arg: "item" }));
+        assert_eq!(parse_line("add Fridge Egg 2 3").err(), Some(CliError::TooManyArguments { command: "add", extra: String::from("3") }));
+
+        // A list takes what the arguments after it leave over
+        let matches = parse_line("rules -e Fridge Egg Milk 6").expect("doesn't parse");
+        assert_eq!(matches.arg_list("items"), vec!["Egg", "Milk"]);
+        assert_eq!(matches.arg_integer("quantity"), Some(6));
+        assert_eq!(parse_line("rules -e Fridge Egg").err(), Some(CliError::MissingArgument { command: "rules", arg: "quantity" }));
+        assert_eq!(parse_line("rules -r Fridge").err(), Some(CliError::MissingArgument { command: "rules", arg: "items" }));
+    }
+
+    #[test]
+    fn forms() {
+        assert!(parse_line("rules -c Fridge Egg 4").expect("doesn't parse").form.is_some_and(|form| form.modes == ["create"]));
+        let matches = parse_line("rules -c --expr=\"quantity < 4\" Fridge Egg").expect("doesn't parse");
+        assert!(matches.is_mode("create") && matches.is_mode("expr"));
+        assert_eq!(matches.arg_integer("quantity"), None);
+
+        assert_eq!(
+            parse_line("rules Fridge").err(),
+            Some(CliError::MissingMode { command: "rules", options: String::from("`--list`, `--create`, `--edit`, `--remove`") }),
+        );
+        assert_eq!(parse_line("rules -lc Fridge").err(), Some(CliError::ConflictingModes(String::from("`--list`, `--create`"))));
+        assert_eq!(parse_line("remove -wa Fridge").err(), Some(CliError::ConflictingModes(String::from("`--warehouse`, `--all`"))));
+    }
+
+    #[test]
+    fn negative_numbers_are_arguments() {
+        assert!(is_option("-a"));
+        assert!(is_option("--all"));
+        assert!(!is_option("-5"));
+        assert!(!is_option("-"));
+
+        assert_eq!(parse_line("add Fridge Egg -2").expect("doesn't parse").arg_integer("quantity"), Some(-2));
+        assert_eq!(parse_line("edit -q -3 Fridge Egg").expect("doesn't parse").integer("quantity"), Some(-3));
+        assert_eq!(parse_line("rules -e Fridge Egg -1").expect("doesn't parse").arg_integer("quantity"), Some(-1));
+    }
+}
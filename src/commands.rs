@@ -0,0 +1,486 @@
+use crate::cli::{ArgSpec, CommandSpec, Form, OptionSpec, Section, ValueKind};
+
+pub const DESCRIPTION: &str = "warehouse inventory management tool";
+pub const SYNOPSIS: &str = "deposito [command] [options] [<args>...]";
+
+pub const FORMATS: &[&str] = &["text", "json", "csv", "tsv", "html", "markdown"];
+pub const COLOR_CHOICES: &[&str] = &["auto", "always", "never"];
+pub const SEVERITIES: &[&str] = &["info", "warn", "critical"];
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+pub const IMPORT_FORMATS: &[&str] = &["csv", "json"];
+pub const IMPORT_MODES: &[&str] = &["add", "replace", "upsert"];
+pub const IMPORT_CONFLICTS: &[&str] = &["fail", "skip", "overwrite"];
+pub const SNAPSHOT_ACTIONS: &[&str] = &["create", "list", "remove"];
+pub const TEMPLATE_ACTIONS: &[&str] = &["create", "list", "remove"];
+
+// Commands that only exist in `deposito shell`
+pub const SHELL_COMMANDS: &[(&str, &str)] = &[
+    ("use <warehouse>", "Use the warehouse when a command's warehouse argument is left out"),
+    ("use", "Show the warehouse in use"),
+    ("use -", "Stop using the warehouse"),
+    ("help", "Show the help (`<command> -h` for help with a command)"),
+    ("exit, quit", "Leave the shell (or Ctrl-D)"),
+];
+
+// Keys of `deposito tui`
+pub const TUI_KEYS: &[(&str, &str)] = &[
+    ("Up/Down, j/k", "Select a warehouse or an item"),
+    ("Tab, Left/Right", "Switch between the warehouses and the items"),
+    ("+, -", "Add or take one of the selected item"),
+    ("e, Enter", "Edit the quantity of the item"),
+    ("r", "Rename the item"),
+    ("d", "Edit the description of the item"),
+    ("/", "Search the items by name and description (Esc shows all again)"),
+    ("q, Ctrl-C", "Quit"),
+];
+
+// Columns of `deposito import csv`
+pub const IMPORT_COLUMNS: &[(&str, &str)] = &[
+    ("name", "Name of the item (also item, product)"),
+    ("quantity", "Quantity, empty is 0 (also qty, count, amount, stock)"),
+    ("description", "Description (also desc, note, comment)"),
+    ("min", "Adds a rule alerting when the quantity gets below it (also min-rule, minimum)"),
+    ("unit", "Unit the quantity is in, e.g. kg (also units, uom)"),
+    ("tags", "Tags separated by `,`, `;` or `|` (also tag, labels, category)"),
+];
+
+pub static GLOBAL_OPTIONS: &[OptionSpec] = &[
+    OptionSpec::flag(Some('h'), "help", "Show help for the command"),
+    OptionSpec::value(None, "format", "format", ValueKind::Choice(FORMATS), "Output of `view`, `list`, `raport`, `rules -l`, `diff` and `export`: text, json, csv or tsv, `raport` also html or markdown"),
+    OptionSpec::value(None, "color", "when", ValueKind::Choice(COLOR_CHOICES), "Colored output: auto, always or never (default: auto, honours NO_COLOR)"),
+];
+
+pub static COMMANDS: &[CommandSpec] = &[
+    CommandSpec::new("init", "create a deposito directory in the current directory")
+        .forms(&[Form::new(&[], &[])])
+        .examples(&["deposito init"])
+        .without_workspace(),
+
+    CommandSpec::new("completions", "print a shell completion script")
+        .forms(&[Form::new(&[], &[ArgSpec::required("shell").choice(SHELLS)])])
+        .examples(&[
+            "source <(deposito completions bash)",
+            "deposito completions zsh > ~/.zfunc/_deposito",
+            "deposito completions fish > ~/.config/fish/completions/deposito.fish",
+        ])
+        .notes(&[
+            "Besides commands and options the scripts complete names of warehouses and items,",
+            "which they get from `deposito __complete` in the current directory.",
+        ])
+        .without_workspace(),
+
+    CommandSpec::new("man", "print the manual")
+        .forms(&[
+            Form::new(&[], &[ArgSpec::optional("command", None).command()]),
+            Form::new(&["markdown"], &[]),
+        ])
+        .options(&[
+            OptionSpec::flag(None, "markdown", "Print the reference of all commands as Markdown instead"),
+        ])
+        .examples(&[
+            "deposito man | man -l -",
+            "deposito man rules > deposito-rules.1",
+            "deposito man --markdown > docs/cli.md",
+        ])
+        .notes(&[
+            "Without a command the page covers all of them, like `deposito man --markdown`.",
+        ])
+        .without_workspace(),
+
+    CommandSpec::new("shell", "start an interactive shell")
+        .forms(&[Form::new(&[], &[])])
+        .examples(&[
+            "deposito shell",
+            "echo \"add Fridge Egg 6\" | deposito shell",
+        ])
+        .sections(&[Section { title: "Shell commands", entries: SHELL_COMMANDS }])
+        .notes(&[
+            "Commands are the same as on the command line, without the leading `deposito`, and the",
+            "database stays open between them. With a warehouse in use, commands can leave out",
+            "their warehouse argument: `use Fridge` and then `add Egg 6`. Tab completes commands",
+            "and names, the history is kept in `deposito/history`.",
+        ]),
+
+    CommandSpec::new("tui", "browse and edit the inventory in a full-screen interface")
+        .forms(&[Form::new(&[], &[ArgSpec::optional("warehouse", None).warehouse()])])
+        .examples(&[
+            "deposito tui",
+            "deposito tui Fridge",
+        ])
+        .sections(&[Section { title: "Keys", entries: TUI_KEYS }])
+        .notes(&[
+            "Items show their quantity and the status of their rule, CRITICAL ones in red, and",
+            "warehouses with CRITICAL items are marked with `!`. Changes are saved right away.",
+            "Without a terminal, keys are read from stdin and the last screen is printed instead.",
+        ]),
+
+    CommandSpec::new("batch", "run commands from a file or stdin")
+        .forms(&[Form::new(&[], &[ArgSpec::optional("file", Some("-"))])])
+        .options(&[
+            OptionSpec::flag(None, "atomic", "Run everything in one transaction, saving nothing if any line fails"),
+        ])
+        .examples(&[
+            "deposito batch delivery.txt",
+            "deposito batch --atomic delivery.txt",
+            "printf 'add Fridge Egg 6\\nadd Fridge Milk 2\\n' | deposito batch",
+        ])
+        .notes(&[
+            "Every line is a command as it would be typed after `deposito`, blank lines and lines",
+            "starting with `#` are skipped. Each command's output is preceded by its line number",
+            "and the lines that failed are listed at the end. With `--atomic`, no command runs",
+            "unless all of them are valid, and the first one to fail rolls back the whole batch.",
+            "`backup` and `restore` can't be part of an atomic batch.",
+        ]),
+
+    CommandSpec::new("export", "export the whole workspace")
+        .forms(&[Form::new(&[], &[ArgSpec::optional("file", Some("-"))])])
+        .examples(&[
+            "deposito export --format=json > backup.json",
+            "deposito export backup.json",
+        ])
+        .notes(&[
+            "Writes every warehouse, item, rule and movement as a JSON document, which",
+            "`deposito import json` reads back. Its layout is described in the README.",
+        ]),
+
+    CommandSpec::new("import", "import items into a warehouse, or a whole exported workspace")
+        .forms(&[Form::new(&[], &[
+            ArgSpec::required("format").choice(IMPORT_FORMATS),
+            ArgSpec::optional("warehouse", None).warehouse(),
+            ArgSpec::required("file"),
+        ])])
+        .options(&[
+            OptionSpec::value(None, "mode", "mode", ValueKind::Choice(IMPORT_MODES), "CSV: add (default), replace or upsert"),
+            OptionSpec::value(None, "columns", "columns", ValueKind::Text, "CSV: fields of the columns in order, separated by commas (`-` skips a column)"),
+            OptionSpec::value(None, "on-conflict", "policy", ValueKind::Choice(IMPORT_CONFLICTS), "JSON: what to do with existing items, fail (default), skip or overwrite"),
+            OptionSpec::flag(None, "dry-run", "Show what would change without importing anything"),
+        ])
+        .sections(&[Section { title: "Columns", entries: IMPORT_COLUMNS }])
+        .examples(&[
+            "deposito import csv Fridge inventory.csv",
+            "deposito import csv Fridge inventory.csv --mode=replace --dry-run",
+            "deposito import csv Pantry export.csv --columns=name,-,quantity,unit",
+            "deposito import json backup.json",
+            "deposito import json Fridge backup.json --on-conflict=overwrite",
+        ])
+        .notes(&[
+            "CSV files are imported into the <warehouse>. Columns are taken from the header, which",
+            "is detected, or given with `--columns`. Files without either have them in the order of",
+            "the Columns section. `,`, `;` and tab separate columns. With `--mode=add` the quantities",
+            "get added to existing items, like `deposito add`. `upsert` sets their quantity and",
+            "anything else the file has instead, and `replace` also removes items that aren't in",
+            "the file. A min doesn't replace a rule that is an expression, it's skipped and listed.",
+            "Nothing is imported if any line is invalid.",
+            "",
+            "JSON documents of `deposito export` are imported whole, or only their <warehouse>.",
+            "Missing warehouses are created. Items that already exist in their warehouse stop the",
+            "import unless `--on-conflict` skips them or overwrites them, along with their rule",
+            "and movements.",
+        ]),
+
+    CommandSpec::new("backup", "back up the database")
+        .forms(&[Form::new(&[], &[ArgSpec::optional("path", None)])])
+        .options(&[
+            OptionSpec::value(None, "keep", "count", ValueKind::Integer, "Backups to keep in the directory, 10 by default (0 keeps all of them)"),
+        ])
+        .examples(&[
+            "deposito backup",
+            "deposito backup --keep=30",
+            "deposito backup /mnt/usb/",
+            "deposito backup before-inventory.db",
+        ])
+        .notes(&[
+            "Takes a consistent copy of the database, also while other deposito processes",
+            "are using it. Without a <path> the copy goes to `deposito/backups`, named after",
+            "the current time, and older copies in there beyond the newest 10 (or --keep)",
+            "are removed. Another directory works the same way, any other <path> is the",
+            "file to write, which must not exist yet.",
+        ]),
+
+    CommandSpec::new("restore", "replace the database with a backup")
+        .forms(&[Form::new(&[], &[ArgSpec::required("file")])])
+        .examples(&[
+            "deposito restore deposito/backups/deposito-20240610-140312.db",
+        ])
+        .notes(&[
+            "Checks that <file> is an intact deposito database of this or an older version",
+            "before replacing the contents of the current one with it. The current contents",
+            "are backed up to `deposito/backups` first, so a restore can be undone with",
+            "another one.",
+        ]),
+
+    CommandSpec::new("snapshot", "take, list or remove snapshots of the inventory")
+        .forms(&[Form::new(&[], &[ArgSpec::required("action").choice(SNAPSHOT_ACTIONS), ArgSpec::optional("label", None)])])
+        .examples(&[
+            "deposito snapshot create 2024-06",
+            "deposito snapshot list",
+            "deposito snapshot remove 2024-06",
+        ])
+        .notes(&[
+            "A snapshot is a frozen copy of the quantities and rules of all items, kept in",
+            "the database, for comparing with `deposito diff` later.",
+        ]),
+
+    CommandSpec::new("diff", "show what changed since a snapshot")
+        .forms(&[Form::new(&[], &[ArgSpec::required("snapshot"), ArgSpec::optional("other", Some("now"))])])
+        .examples(&[
+            "deposito diff 2024-06",
+            "deposito diff 2024-05 2024-06",
+            "deposito diff 2024-06 --format=csv",
+        ])
+        .notes(&[
+            "Lists the items added and removed between the two snapshots (or the snapshot and",
+            "now), and those whose quantity or rule changed, per warehouse.",
+        ]),
+
+    CommandSpec::new("undo", "undo the latest changes")
+        .forms(&[
+            Form::new(&[], &[ArgSpec::optional("count", Some("1")).integer()]),
+            Form::new(&["list"], &[]),
+        ])
+        .options(&[
+            OptionSpec::flag(Some('l'), "list", "Show the recent commands that can be undone"),
+        ])
+        .examples(&[
+            "deposito undo",
+            "deposito undo 3",
+            "deposito undo --list",
+        ])
+        .notes(&[
+            "`add`, `edit`, `remove`, `create`, `rename`, `merge`, `archive`, `unarchive` and",
+            "`rules -c/-e/-r` are recorded in an undo journal, the latest 100 of them.",
+            "`undo <count>` undoes that many, newest first. A command can't be undone once what",
+            "it changed has been changed by something outside of the journal, such as `import`.",
+        ]),
+
+    CommandSpec::new("redo", "redo undone changes")
+        .forms(&[Form::new(&[], &[ArgSpec::optional("count", Some("1")).integer()])])
+        .examples(&[
+            "deposito redo",
+            "deposito redo 2",
+        ])
+        .notes(&[
+            "Redoes undone commands, oldest first, until another command is recorded.",
+        ]),
+
+    CommandSpec::new("create", "create a new warehouse")
+        .forms(&[Form::new(&[], &[ArgSpec::required("warehouse")])])
+        .options(&[
+            OptionSpec::value(None, "from", "warehouse", ValueKind::Text, "Copy the items and rules of an existing warehouse"),
+            OptionSpec::value(None, "template", "name", ValueKind::Text, "Start from the items and rules of a template"),
+            OptionSpec::flag(None, "zero", "Copy the items with a quantity of 0"),
+            OptionSpec::flag(None, "empty", "Start empty, without the `default` template"),
+        ])
+        .examples(&[
+            "deposito create Fridge",
+            "deposito create --from=Fridge --zero Cellar",
+            "deposito create --template=site Warsaw",
+        ])
+        .notes(&[
+            "New warehouses start with the `default` template when there is one (see",
+            "`deposito template`). Archived items aren't copied with `--from`.",
+        ]),
+
+    CommandSpec::new("template", "make, list or remove templates for new warehouses")
+        .forms(&[Form::new(&[], &[ArgSpec::required("action").choice(TEMPLATE_ACTIONS), ArgSpec::optional("name", None), ArgSpec::optional("warehouse", None).warehouse()])])
+        .examples(&[
+            "deposito template create site Fridge",
+            "deposito template list",
+            "deposito template remove site",
+        ])
+        .notes(&[
+            "A template is a copy of the items of a warehouse with their quantities and",
+            "rules, kept in the database for `deposito create --template`. The one named",
+            "`default` is used for every new warehouse created without `--from`,",
+            "`--template` or `--empty`. Changing the warehouse later doesn't change it.",
+        ]),
+
+    CommandSpec::new("rename", "rename a warehouse")
+        .forms(&[Form::new(&[], &[ArgSpec::required("old").warehouse(), ArgSpec::required("new")])])
+        .examples(&["deposito rename Fridge Kitchen"]),
+
+    CommandSpec::new("merge", "move all items of a warehouse into another one")
+        .forms(&[Form::new(&[], &[ArgSpec::required("source").warehouse(), ArgSpec::required("target").warehouse()])])
+        .options(&[
+            OptionSpec::value(None, "on-conflict", "policy", ValueKind::Choice(IMPORT_CONFLICTS), "Items with a different rule in both: fail (default), skip (keep the target's) or overwrite"),
+        ])
+        .examples(&[
+            "deposito merge Garage Basement",
+            "deposito merge Garage Basement --on-conflict=overwrite",
+        ])
+        .notes(&[
+            "Items of the same name are combined into one, adding up the quantities and keeping",
+            "the movements of both. When only one of them has a rule it is kept, different",
+            "rules are a conflict. The source warehouse is removed afterwards. All of it",
+            "happens at once, or nothing does.",
+        ]),
+
+    CommandSpec::new("view", "list available warehouses")
+        .forms(&[Form::new(&[], &[])])
+        .options(&[
+            OptionSpec::flag(None, "include-archived", "Show archived warehouses as well"),
+        ])
+        .examples(&["deposito view", "deposito view --include-archived"]),
+
+    CommandSpec::new("list", "list items in the specified warehouse")
+        .forms(&[Form::new(&[], &[ArgSpec::required("warehouse").warehouse()])])
+        .options(&[
+            OptionSpec::flag(None, "include-archived", "Show archived items, or list an archived warehouse"),
+        ])
+        .examples(&["deposito list Fridge", "deposito list Garage --include-archived"]),
+
+    CommandSpec::new("add", "add a new item to a warehouse")
+        .forms(&[Form::new(&[], &[
+            ArgSpec::required("warehouse").warehouse(),
+            ArgSpec::required("item").item(),
+            ArgSpec::optional("quantity", Some("1")).integer(),
+        ])])
+        .examples(&["deposito add Fridge Egg 8"]),
+
+    CommandSpec::new("edit", "edit an existing item in a warehouse")
+        .forms(&[Form::new(&[], &[ArgSpec::required("warehouse").warehouse(), ArgSpec::required("item").item()])])
+        .options(&[
+            OptionSpec::value(Some('n'), "name", "name", ValueKind::Text, "New name"),
+            OptionSpec::value(Some('d'), "description", "desc", ValueKind::Text, "New description"),
+            OptionSpec::value(Some('q'), "quantity", "quan", ValueKind::Integer, "New quantity"),
+        ])
+        .examples(&["deposito edit --name=Egg -q 6 --description=\"Just a regular egg\" Fridge Eg"]),
+
+    CommandSpec::new("remove", "remove a warehouse or an item")
+        .forms(&[
+            Form::new(&["warehouse"], &[ArgSpec::many("warehouses").warehouse()]),
+            Form::new(&["item"], &[ArgSpec::required("warehouse").warehouse(), ArgSpec::many("items").item()]),
+            Form::new(&["item", "all"], &[ArgSpec::required("warehouse").warehouse()]),
+        ])
+        .options(&[
+            OptionSpec::flag(Some('w'), "warehouse", "Remove a warehouse"),
+            OptionSpec::flag(Some('i'), "item", "Remove an item"),
+            OptionSpec::flag(Some('a'), "all", "Remove all (items only)"),
+            OptionSpec::flag(Some('y'), "yes", "Remove without asking"),
+            OptionSpec::flag(None, "dry-run", "Only show what would be removed"),
+            OptionSpec::flag(None, "purge", "Remove even what isn't archived"),
+        ])
+        .examples(&[
+            "deposito remove -w Fridge",
+            "deposito remove -i Fridge Egg",
+            "deposito remove -ia Fridge",
+            "deposito remove -ia Fridge --dry-run",
+            "deposito remove -w Garage --yes",
+            "deposito remove -i Fridge Milk --purge",
+        ])
+        .notes(&[
+            "Only archived warehouses and items are removed (see `deposito archive`), unless",
+            "--purge is given. -ia removes the archived items of the warehouse, or all of them",
+            "with --purge. Items go along with their rules and movements.",
+            "",
+            "What would be removed is shown first and has to be confirmed, unless --yes is",
+            "given. Without a terminal to ask on (e.g. in scripts), nothing is removed without",
+            "--yes.",
+        ]),
+
+    CommandSpec::new("archive", "archive a warehouse or an item")
+        .forms(&[
+            Form::new(&["warehouse"], &[ArgSpec::many("warehouses").warehouse()]),
+            Form::new(&["item"], &[ArgSpec::required("warehouse").warehouse(), ArgSpec::many("items").item()]),
+        ])
+        .options(&[
+            OptionSpec::flag(Some('w'), "warehouse", "Archive a warehouse"),
+            OptionSpec::flag(Some('i'), "item", "Archive an item"),
+        ])
+        .examples(&[
+            "deposito archive -w Garage",
+            "deposito archive -i Fridge Egg Milk",
+        ])
+        .notes(&[
+            "Archived warehouses and items are kept with their rules and movements, but hidden",
+            "from `view`, `list` and `raport` unless they're given --include-archived. Only",
+            "archived ones can be removed without --purge.",
+        ]),
+
+    CommandSpec::new("unarchive", "bring back an archived warehouse or item")
+        .forms(&[
+            Form::new(&["warehouse"], &[ArgSpec::many("warehouses").warehouse()]),
+            Form::new(&["item"], &[ArgSpec::required("warehouse").warehouse(), ArgSpec::many("items").item()]),
+        ])
+        .options(&[
+            OptionSpec::flag(Some('w'), "warehouse", "Unarchive a warehouse"),
+            OptionSpec::flag(Some('i'), "item", "Unarchive an item"),
+        ])
+        .examples(&[
+            "deposito unarchive -w Garage",
+            "deposito unarchive -i Fridge Egg",
+        ]),
+
+    CommandSpec::new("rules", "manage rules in a warehouse")
+        .forms(&[
+            Form::new(&["list"], &[ArgSpec::required("warehouse").warehouse()]),
+            Form::new(&["create"], &[ArgSpec::required("warehouse").warehouse(), ArgSpec::required("item").item(), ArgSpec::required("quantity").integer()]),
+            Form::new(&["create", "expr"], &[ArgSpec::required("warehouse").warehouse(), ArgSpec::required("item").item()]),
+            Form::new(&["edit"], &[ArgSpec::required("warehouse").warehouse(), ArgSpec::many("items").item(), ArgSpec::required("quantity").integer()]),
+            Form::new(&["remove"], &[ArgSpec::required("warehouse").warehouse(), ArgSpec::many("items").item()]),
+        ])
+        .options(&[
+            OptionSpec::flag(Some('l'), "list", "Show rules"),
+            OptionSpec::flag(Some('c'), "create", "Create a new rule"),
+            OptionSpec::flag(Some('e'), "edit", "Edit an existing rule"),
+            OptionSpec::flag(Some('r'), "remove", "Remove a rule"),
+            OptionSpec::value(None, "expr", "expression", ValueKind::Text, "Condition to use instead of a quantity (with --create)"),
+            OptionSpec::value(None, "severity", "level", ValueKind::Choice(SEVERITIES), "info, warn or critical (default: critical)"),
+            OptionSpec::flag(Some('y'), "yes", "Remove without asking (with --remove)"),
+            OptionSpec::flag(None, "dry-run", "Only show what would be removed (with --remove)"),
+        ])
+        .examples(&[
+            "deposito rules -l Fridge",
+            "deposito rules -c Fridge Egg 1",
+            "deposito rules -c --expr=\"Egg + Eggs_boxed * 6 < 12\" Fridge Egg",
+            "deposito rules -c --expr=\"quantity < 0.1 * consumption(30) / 30\" Fridge Milk",
+            "deposito rules -c --expr=\"days_idle > 90\" --severity=warn Garage Drill",
+            "deposito rules -e Fridge Egg Milk Dough 6",
+            "deposito rules -r Fridge Milk Dough",
+        ])
+        .sections(&[Section {
+            title: "Expressions",
+            entries: &[
+                ("quantity", "Quantity of the rule's item"),
+                ("Egg, `Human Figures`", "Quantity of another item in the warehouse"),
+                ("consumption(<days>)", "How much of the rule's item was used in the last <days> days"),
+                ("days_idle", "Days since the rule's item quantity last changed"),
+                ("min(a, b), max(a, b), abs(a)", ""),
+                ("+ - * /  < <= > >= == !=  and or not  ( )", ""),
+            ],
+        }]),
+
+    CommandSpec::new("raport", "create a raport")
+        .forms(&[
+            Form::new(&[], &[ArgSpec::required("warehouse").warehouse()]),
+            Form::new(&["all"], &[]),
+        ])
+        .options(&[
+            OptionSpec::flag(Some('a'), "all", "Raport all warehouses"),
+            OptionSpec::value(None, "horizon", "period", ValueKind::Text, "Flag items that will become critical within the period (e.g. 14d, 2w)"),
+            OptionSpec::value(None, "output", "file", ValueKind::Text, "Write the raport to a spreadsheet (.xlsx or .ods) or a PDF (.pdf)"),
+            OptionSpec::flag(None, "stamp", "Add when and in which workspace the raport was made"),
+            OptionSpec::flag(None, "include-archived", "Raport archived warehouses and items as well"),
+        ])
+        .examples(&[
+            "deposito raport Fridge",
+            "deposito raport -a",
+            "deposito raport -a --horizon=14d",
+            "deposito raport -a --output=raport.xlsx",
+            "deposito raport -a --output=raport.pdf",
+            "deposito raport -a --format=html --stamp > raport.html",
+            "deposito raport Fridge --format=markdown",
+        ])
+        .notes(&[
+            "Daily consumption is estimated from the outflows of the last 30 days and used to",
+            "estimate when each item becomes critical and when it runs out.",
+            "",
+            "Spreadsheets start with a summary sheet counting the statuses in each warehouse,",
+            "followed by a sheet per warehouse listing the item, quantity, threshold and status",
+            "of every rule. CRITICAL rows are highlighted through conditional formatting.",
+            "PDFs have a title page with the summary and when and where they were made, followed",
+            "by a section per warehouse with the tables of the text output, and numbered pages.",
+            "`--format=html` makes a self-contained page and `--format=markdown` GitHub flavoured",
+            "Markdown, both with the tables of the text output.",
+        ]),
+];
@@ -0,0 +1,235 @@
+use crate::cli::{self, Arity, CommandSpec, Form, OptionSpec, Suggest, ValueKind};
+use crate::commands::{COMMANDS, GLOBAL_OPTIONS};
+
+// Shell completion. The scripts below don't know anything about deposito's commands,
+// they hand the words typed so far to `deposito __complete -- <words>...` and offer
+// whatever it prints (one candidate per line). Candidates for the last word are
+// worked out from the command definitions, names come from the database.
+//
+// `deposito __complete warehouses` and `deposito __complete items <warehouse>` print
+// the names directly.
+
+pub enum Completion {
+    Words(Vec<String>),
+    Warehouses,
+    Items(String),
+}
+
+pub struct Request {
+    pub completion: Completion,
+    // Partial word under the cursor, candidates have to start with it
+    pub current: String,
+}
+
+pub fn request(args: &[String]) -> Option<Request> {
+    let (kind, rest) = args.split_first()?;
+    match kind.as_str() {
+        "--" => {
+            let (current, previous) = match rest.split_last() {
+                Some((current, previous)) => (current.clone(), previous),
+                None => (String::new(), rest),
+            };
+            Some(Request { completion: analyze(previous, &current), current })
+        }
+        "warehouses" => Some(Request { completion: Completion::Warehouses, current: String::new() }),
+        "items" => Some(Request { completion: Completion::Items(rest.first()?.clone()), current: String::new() }),
+        _ => None,
+    }
+}
+
+fn lookup_long(command: Option<&'static CommandSpec>, long: &str) -> Option<&'static OptionSpec> {
+    match command {
+        Some(command) => command.option(long),
+        None => GLOBAL_OPTIONS.iter().find(|option| option.long == long),
+    }
+}
+
+fn lookup_short(command: Option<&'static CommandSpec>, short: char) -> Option<&'static OptionSpec> {
+    match command {
+        Some(command) => command.short_option(short),
+        None => GLOBAL_OPTIONS.iter().find(|option| option.short == Some(short)),
+    }
+}
+
+fn choices(kind: ValueKind) -> Vec<String> {
+    match kind {
+        ValueKind::Choice(choices) => choices.iter().map(|choice| choice.to_string()).collect(),
+        ValueKind::Text | ValueKind::Integer => Vec::new(),
+    }
+}
+
+// Walks the words the same way `cli::parse` does, but never fails: unknown options are
+// skipped and the form is picked from whatever mode options were given so far
+pub fn analyze(previous: &[String], current: &str) -> Completion {
+    let mut command: Option<&'static CommandSpec> = None;
+    let mut positional: Vec<&str> = Vec::new();
+    let mut given: Vec<&'static str> = Vec::new();
+    let mut pending: Option<&'static OptionSpec> = None;
+    let mut terminated = false;
+
+    for word in previous.iter() {
+        if pending.take().is_some() {
+            continue;
+        }
+
+        if terminated || !cli::is_option(word) {
+            match command {
+                Some(_) => { positional.push(word); }
+                None => {
+                    command = CommandSpec::find(word);
+                    if command.is_none() { return Completion::Words(Vec::new()); }
+                }
+            }
+            continue;
+        }
+
+        if word == "--" {
+            terminated = true;
+            continue;
+        }
+
+        if let Some(long) = word.strip_prefix("--") {
+            let (name, inline) = match long.split_once('=') {
+                Some((name, _)) => (name, true),
+                None => (long, false),
+            };
+            if let Some(option) = lookup_long(command, name) {
+                given.push(option.long);
+                if option.value.is_some() && !inline { pending = Some(option); }
+            }
+            continue;
+        }
+
+        let cluster: Vec<char> = word[1..].chars().collect();
+        for (index, short) in cluster.iter().enumerate() {
+            if let Some(option) = lookup_short(command, *short) {
+                given.push(option.long);
+                if option.value.is_some() {
+                    if index == cluster.len() - 1 { pending = Some(option); }
+                    break;
+                }
+            }
+        }
+    }
+
+    // Value of an option given as a separate word
+    if let Some(option) = pending {
+        return match option.value {
+            Some((_, kind)) => Completion::Words(choices(kind)),
+            None => Completion::Words(Vec::new()),
+        };
+    }
+
+    if !terminated && current.starts_with('-') {
+        if let Some((name, _)) = current.trim_start_matches('-').split_once('=') {
+            let values = match lookup_long(command, name).and_then(|option| option.value) {
+                Some((_, kind)) => choices(kind),
+                None => Vec::new(),
+            };
+            return Completion::Words(values.into_iter().map(|value| format!("--{}={}", name, value)).collect());
+        }
+
+        let options = match command {
+            Some(command) => command.options.iter().chain(GLOBAL_OPTIONS.iter()).collect::<Vec<_>>(),
+            None => GLOBAL_OPTIONS.iter().collect(),
+        };
+        return Completion::Words(options.iter().map(|option| format!("--{}", option.long)).collect());
+    }
+
+    let command = match command {
+        Some(command) => command,
+        None => { return Completion::Words(COMMANDS.iter().map(|command| command.name.to_string()).collect()); }
+    };
+
+    let form = command.forms.iter()
+        .filter(|form| form.modes.iter().all(|mode| given.contains(mode)))
+        .max_by_key(|form| form.modes.len());
+    let form = match form {
+        Some(form) => form,
+        None => { return Completion::Words(Vec::new()); }
+    };
+
+    let spec = match argument_at(form, positional.len()) {
+        Some(spec) => spec,
+        None => { return Completion::Words(Vec::new()); }
+    };
+
+    match spec.suggest {
+        Suggest::Warehouses => Completion::Warehouses,
+        Suggest::Items => {
+            let warehouse = form.args.iter().position(|arg| arg.suggest == Suggest::Warehouses)
+                .and_then(|index| positional.get(index));
+            match warehouse {
+                Some(warehouse) => Completion::Items(warehouse.to_string()),
+                None => Completion::Words(Vec::new()),
+            }
+        }
+        Suggest::Commands => Completion::Words(COMMANDS.iter().map(|command| command.name.to_string()).collect()),
+        Suggest::Nothing => Completion::Words(choices(spec.kind)),
+    }
+}
+
+// Argument the n-th positional word belongs to. A `Many` argument is assumed to take
+// every word from its position onwards.
+fn argument_at(form: &'static Form, index: usize) -> Option<&'static cli::ArgSpec> {
+    form.args.iter().enumerate()
+        .find(|(position, spec)| *position == index || (spec.arity == Arity::Many && index >= *position))
+        .map(|(_, spec)| spec)
+}
+
+pub fn script(shell: &str) -> Option<&'static str> {
+    match shell {
+        "bash" => Some(BASH),
+        "zsh" => Some(ZSH),
+        "fish" => Some(FISH),
+        _ => None,
+    }
+}
+
+const BASH: &str = r#"# bash completion for deposito
+_deposito() {
+    local IFS=$'\n'
+    local words=() word index
+
+    # bash splits `--option=value` on `=`, join it back for deposito
+    for ((index = 1; index <= COMP_CWORD; index++)); do
+        word="${COMP_WORDS[index]}"
+        if [[ ${#words[@]} -gt 0 && ( "$word" == "=" || "${words[-1]}" == *= ) ]]; then
+            words[-1]+="$word"
+        else
+            words+=("$word")
+        fi
+    done
+
+    # Only the part after the last word break gets replaced
+    local current="${COMP_WORDS[COMP_CWORD]}"
+    local prefix="${words[-1]%"$current"}"
+
+    COMPREPLY=()
+    local candidate
+    for candidate in $(deposito __complete -- "${words[@]}" 2>/dev/null); do
+        COMPREPLY+=("$(printf '%q' "${candidate#"$prefix"}")")
+    done
+}
+complete -F _deposito deposito
+"#;
+
+const ZSH: &str = r#"#compdef deposito
+# zsh completion for deposito
+_deposito() {
+    local -a candidates
+    candidates=("${(@f)$(deposito __complete -- "${(@Q)words[2,CURRENT]}" 2>/dev/null)}")
+    candidates=(${candidates:#})
+    compadd -a candidates
+}
+
+if [ "$funcstack[1]" = "_deposito" ]; then
+    _deposito "$@"
+else
+    compdef _deposito deposito
+fi
+"#;
+
+const FISH: &str = r#"# fish completion for deposito
+complete -c deposito -f -a '(deposito __complete -- (commandline -opc)[2..-1] (commandline -ct))'
+"#;
@@ -0,0 +1,403 @@
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use unicode_width::UnicodeWidthChar;
+
+// Minimal line editor for `deposito shell`: cursor movement, history and tab completion
+// on a terminal, plain line reading otherwise (e.g. when commands are piped in).
+//
+//   Left/Right, Ctrl-B/Ctrl-F    move by a character
+//   Home/End, Ctrl-A/Ctrl-E      move to the start/end of the line
+//   Up/Down, Ctrl-P/Ctrl-N       walk the history
+//   Backspace, Delete            delete a character
+//   Ctrl-U, Ctrl-K, Ctrl-W       delete to the start, to the end, the previous word
+//   Ctrl-L                       clear the screen
+//   Ctrl-C                       discard the line
+//   Ctrl-D                       exit on an empty line
+//   Tab                          complete
+
+const HISTORY_LIMIT: usize = 1000;
+
+pub enum ReadLine {
+    Line(String),
+    Interrupted,
+    Eof,
+}
+
+// Result of completing the word that ends at the cursor: `line[start..cursor]` is
+// replaced with one of the candidates (already quoted as needed)
+pub struct Completions {
+    pub start: usize,
+    pub candidates: Vec<String>,
+}
+
+pub struct Editor {
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+    interactive: bool,
+    keys: KeyReader<TerminalInput>,
+}
+
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Escape,
+    Control(char),
+    Unknown,
+}
+
+impl Editor {
+    pub fn new(history_path: Option<PathBuf>) -> Self {
+        let history = match &history_path {
+            Some(path) => std::fs::read_to_string(path).map(|history| history.lines().map(str::to_string).collect()).unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        let interactive = cfg!(unix) && io::stdin().is_terminal() && io::stdout().is_terminal();
+        Self { history, history_path, interactive, keys: KeyReader::new(TerminalInput, true) }
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+
+    pub fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() || self.history.last().is_some_and(|last| last == line) {
+            return;
+        }
+
+        self.history.push(line.to_string());
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.drain(..self.history.len() - HISTORY_LIMIT);
+        }
+
+        if let Some(path) = &self.history_path {
+            // Losing the history isn't worth interrupting the session for
+            let _ = std::fs::write(path, self.history.join("\n") + "\n");
+        }
+    }
+
+    pub fn read_line(&mut self, prompt: &str, complete: &dyn Fn(&str) -> Completions) -> io::Result<ReadLine> {
+        if !self.interactive {
+            let mut line = String::new();
+            return match io::stdin().lock().read_line(&mut line)? {
+                0 => Ok(ReadLine::Eof),
+                _ => Ok(ReadLine::Line(line.trim_end_matches(['\n', '\r']).to_string())),
+            };
+        }
+
+        let _raw = RawMode::enable()?;
+        let mut line = LineState { prompt, buffer: Vec::new(), cursor: 0 };
+        // Position in the history while walking it, and the line that was being typed
+        let mut history_index = self.history.len();
+        let mut typed: Vec<char> = Vec::new();
+
+        line.redraw()?;
+        loop {
+            match self.keys.read_key()? {
+                Key::Enter => {
+                    write!(io::stdout(), "\r\n")?;
+                    return Ok(ReadLine::Line(line.buffer.iter().collect()));
+                }
+                Key::Control('c') => {
+                    write!(io::stdout(), "^C\r\n")?;
+                    return Ok(ReadLine::Interrupted);
+                }
+                Key::Control('d') if line.buffer.is_empty() => {
+                    write!(io::stdout(), "\r\n")?;
+                    return Ok(ReadLine::Eof);
+                }
+                Key::Control('d') | Key::Delete => {
+                    if line.cursor < line.buffer.len() { line.buffer.remove(line.cursor); }
+                }
+                Key::Char(char) => {
+                    line.buffer.insert(line.cursor, char);
+                    line.cursor += 1;
+                }
+                Key::Backspace | Key::Control('h') => {
+                    if line.cursor > 0 {
+                        line.cursor -= 1;
+                        line.buffer.remove(line.cursor);
+                    }
+                }
+                Key::Left | Key::Control('b') => { line.cursor = line.cursor.saturating_sub(1); }
+                Key::Right | Key::Control('f') => { line.cursor = (line.cursor + 1).min(line.buffer.len()); }
+                Key::Home | Key::Control('a') => { line.cursor = 0; }
+                Key::End | Key::Control('e') => { line.cursor = line.buffer.len(); }
+                Key::Control('u') => {
+                    line.buffer.drain(..line.cursor);
+                    line.cursor = 0;
+                }
+                Key::Control('k') => { line.buffer.truncate(line.cursor); }
+                Key::Control('w') => {
+                    let mut start = line.cursor;
+                    while start > 0 && line.buffer[start-1] == ' ' { start -= 1; }
+                    while start > 0 && line.buffer[start-1] != ' ' { start -= 1; }
+                    line.buffer.drain(start..line.cursor);
+                    line.cursor = start;
+                }
+                Key::Control('l') => { write!(io::stdout(), "\x1b[H\x1b[2J")?; }
+                Key::Up | Key::Control('p') => {
+                    if history_index > 0 {
+                        if history_index == self.history.len() { typed = line.buffer.clone(); }
+                        history_index -= 1;
+                        line.set(self.history[history_index].chars().collect());
+                    }
+                }
+                Key::Down | Key::Control('n') => {
+                    if history_index < self.history.len() {
+                        history_index += 1;
+                        match self.history.get(history_index) {
+                            Some(entry) => { line.set(entry.chars().collect()); }
+                            None => { line.set(typed.clone()); }
+                        }
+                    }
+                }
+                Key::Tab => { line.complete(complete)?; }
+                Key::Control(_) | Key::Escape | Key::Unknown => { }
+            }
+            line.redraw()?;
+        }
+    }
+}
+
+struct LineState<'a> {
+    prompt: &'a str,
+    buffer: Vec<char>,
+    cursor: usize,
+}
+
+impl LineState<'_> {
+    fn set(&mut self, buffer: Vec<char>) {
+        self.cursor = buffer.len();
+        self.buffer = buffer;
+    }
+
+    fn redraw(&self) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        let text: String = self.buffer.iter().collect();
+        write!(stdout, "\r{}{}\x1b[K", self.prompt, text)?;
+
+        let after: usize = self.buffer[self.cursor..].iter().map(|char| char.width().unwrap_or(0)).sum();
+        if after > 0 {
+            write!(stdout, "\x1b[{}D", after)?;
+        }
+        stdout.flush()
+    }
+
+    fn complete(&mut self, complete: &dyn Fn(&str) -> Completions) -> io::Result<()> {
+        let before: String = self.buffer[..self.cursor].iter().collect();
+        let completions = complete(&before);
+        let start = before[..completions.start.min(before.len())].chars().count();
+
+        let replacement = match completions.candidates.as_slice() {
+            [] => { return Ok(()); }
+            [candidate] if candidate.ends_with('=') => candidate.clone(),
+            [candidate] => format!("{} ", candidate),
+            candidates => {
+                let prefix = common_prefix(candidates);
+                if prefix.chars().count() <= self.cursor - start {
+                    // Nothing more to fill in, show the options instead
+                    let mut stdout = io::stdout();
+                    write!(stdout, "\r\n{}\r\n", candidates.join("  "))?;
+                    return Ok(());
+                }
+                prefix
+            }
+        };
+
+        let replacement: Vec<char> = replacement.chars().collect();
+        let length = replacement.len();
+        self.buffer.splice(start..self.cursor, replacement);
+        self.cursor = start + length;
+        Ok(())
+    }
+}
+
+fn common_prefix(candidates: &[String]) -> String {
+    let mut prefix: Vec<char> = candidates[0].chars().collect();
+    for candidate in candidates[1..].iter() {
+        let shared = prefix.iter().zip(candidate.chars()).take_while(|(a, b)| **a == *b).count();
+        prefix.truncate(shared);
+    }
+    prefix.into_iter().collect()
+}
+
+// Decodes keys from the bytes a terminal sends for them (or anything sending the same
+// bytes, like the input of the headless TUI backend)
+pub struct KeyReader<R> {
+    input: R,
+    peeked: Option<u8>,
+    // On a terminal the rest of an escape sequence arrives right away, so a lone ESC is
+    // told apart by waiting for it briefly. Other input is read ahead instead.
+    terminal: bool,
+}
+
+impl<R: Read> KeyReader<R> {
+    pub fn new(input: R, terminal: bool) -> Self {
+        Self { input, peeked: None, terminal }
+    }
+
+    fn read_byte(&mut self) -> io::Result<u8> {
+        if let Some(byte) = self.peeked.take() {
+            return Ok(byte);
+        }
+
+        let mut byte = [0u8; 1];
+        self.input.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    // Byte following an ESC, `None` when the ESC was pressed on its own
+    fn read_escaped_byte(&mut self) -> io::Result<Option<u8>> {
+        if self.terminal && self.peeked.is_none() && !input_pending() {
+            return Ok(None);
+        }
+
+        match self.read_byte() {
+            Ok(byte) => Ok(Some(byte)),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn read_key(&mut self) -> io::Result<Key> {
+        let byte = self.read_byte()?;
+        let key = match byte {
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            0x7f => Key::Backspace,
+            0x1b => self.read_escape()?,
+            0x01..=0x1a => Key::Control((b'a' + byte - 1) as char),
+            0x00..=0x1f => Key::Unknown,
+            _ => {
+                // Rest of a multi-byte UTF-8 character
+                let length = match byte {
+                    0xf0..=0xff => 4,
+                    0xe0..=0xef => 3,
+                    0xc0..=0xdf => 2,
+                    _ => 1,
+                };
+                let mut bytes = vec![byte];
+                for _ in 1..length {
+                    bytes.push(self.read_byte()?);
+                }
+                match std::str::from_utf8(&bytes).ok().and_then(|text| text.chars().next()) {
+                    Some(char) => Key::Char(char),
+                    None => Key::Unknown,
+                }
+            }
+        };
+        Ok(key)
+    }
+
+    // `ESC [ ...` and `ESC O ...` sequences of the keys the editor knows about
+    fn read_escape(&mut self) -> io::Result<Key> {
+        match self.read_escaped_byte()? {
+            Some(b'[' | b'O') => { }
+            Some(byte) => {
+                self.peeked = Some(byte);
+                return Ok(Key::Escape);
+            }
+            None => { return Ok(Key::Escape); }
+        }
+
+        let key = match self.read_byte()? {
+            b'A' => Key::Up,
+            b'B' => Key::Down,
+            b'C' => Key::Right,
+            b'D' => Key::Left,
+            b'H' => Key::Home,
+            b'F' => Key::End,
+            digit @ b'0'..=b'9' => {
+                let mut code = vec![digit];
+                loop {
+                    match self.read_byte()? {
+                        b'~' => { break; }
+                        byte if code.len() < 8 => { code.push(byte); }
+                        _ => { return Ok(Key::Unknown); }
+                    }
+                }
+                match code.as_slice() {
+                    b"1" | b"7" => Key::Home,
+                    b"4" | b"8" => Key::End,
+                    b"3" => Key::Delete,
+                    _ => Key::Unknown,
+                }
+            }
+            _ => Key::Unknown,
+        };
+        Ok(key)
+    }
+}
+
+// Stdin without the buffering of `io::stdin()`, which would take the rest of an escape
+// sequence in before `input_pending` gets to see it
+pub struct TerminalInput;
+
+impl Read for TerminalInput {
+    #[cfg(unix)]
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        Ok(rustix::io::read(io::stdin(), buffer)?)
+    }
+
+    #[cfg(not(unix))]
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        io::stdin().read(buffer)
+    }
+}
+
+// Whether more input arrives on stdin within a moment
+#[cfg(unix)]
+fn input_pending() -> bool {
+    use rustix::event::{poll, PollFd, PollFlags, Timespec};
+
+    let stdin = io::stdin();
+    let mut fds = [PollFd::new(&stdin, PollFlags::IN)];
+    let timeout = Timespec { tv_sec: 0, tv_nsec: 50_000_000 };
+    matches!(poll(&mut fds, Some(&timeout)), Ok(count) if count > 0)
+}
+
+#[cfg(not(unix))]
+fn input_pending() -> bool {
+    true
+}
+
+// Puts the terminal in raw mode for as long as it's alive
+pub struct RawMode {
+    #[cfg(unix)]
+    original: rustix::termios::Termios,
+}
+
+impl RawMode {
+    #[cfg(unix)]
+    pub fn enable() -> io::Result<Self> {
+        use rustix::termios::{tcgetattr, tcsetattr, OptionalActions};
+
+        let original = tcgetattr(io::stdin())?;
+        let mut raw = original.clone();
+        raw.make_raw();
+        tcsetattr(io::stdin(), OptionalActions::Flush, &raw)?;
+        Ok(Self { original })
+    }
+
+    #[cfg(not(unix))]
+    pub fn enable() -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "line editing isn't supported on this platform"))
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        let _ = rustix::termios::tcsetattr(io::stdin(), rustix::termios::OptionalActions::Now, &self.original);
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+use crate::expr::{Expr, Severity};
+
+// The JSON document of `deposito export` and `deposito import json`, holding the whole
+// workspace. It's described in the README, keep the two in sync.
+//
+// `version` is bumped whenever a change would make older versions of deposito misread a
+// document. Documents of newer versions are refused, older ones keep being read. Adding
+// optional fields (with a default) doesn't need a new version.
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub struct Document {
+    pub version: u32,
+    // Unix timestamp, informational only
+    #[serde(default)]
+    pub exported_at: Option<i64>,
+    #[serde(default)]
+    pub warehouses: Vec<Warehouse>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Warehouse {
+    pub name: String,
+    #[serde(default)]
+    pub items: Vec<Item>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Item {
+    pub name: String,
+    #[serde(default)]
+    pub quantity: i64,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub rule: Option<Rule>,
+    // Oldest first. The quantity is stored as is, it doesn't have to add up to them.
+    #[serde(default)]
+    pub movements: Vec<Movement>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(default)]
+    pub gets_below_quantity: Option<i64>,
+    #[serde(default)]
+    pub expression: Option<String>,
+    #[serde(default = "default_severity")]
+    pub severity: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Movement {
+    pub delta: i64,
+    // Unix timestamp
+    pub created_at: i64,
+}
+
+fn default_severity() -> String {
+    Severity::Critical.as_str().to_string()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Conflict {
+    // Nothing is imported if any item already exists
+    #[default]
+    Fail,
+    // Existing items are left as they are
+    Skip,
+    // Existing items are replaced, along with their rule and movements
+    Overwrite,
+}
+
+impl FromStr for Conflict {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "fail" => Ok(Conflict::Fail),
+            "skip" => Ok(Conflict::Skip),
+            "overwrite" => Ok(Conflict::Overwrite),
+            _ => Err(format!("Unknown conflict policy `{}` (expected fail, skip or overwrite).", value)),
+        }
+    }
+}
+
+// Reads and checks a document. Every problem is reported, and the document only gets
+// imported without any.
+pub fn read(input: &str) -> Result<Document, Vec<String>> {
+    // The version is checked first, a newer document may not even deserialize
+    let value: serde_json::Value = serde_json::from_str(input).map_err(|err| vec![format!("Unable to read the document. Reason: {}", err)])?;
+    match value.get("version").and_then(serde_json::Value::as_u64) {
+        Some(version) if version > VERSION as u64 => {
+            return Err(vec![format!("The document is of version {}, which is newer than this deposito supports ({}).", version, VERSION)]);
+        }
+        Some(_) => {}
+        None => { return Err(vec![String::from("The document has no `version`, it wasn't made by `deposito export`.")]); }
+    }
+
+    let document: Document = serde_json::from_value(value).map_err(|err| vec![format!("Unable to read the document. Reason: {}", err)])?;
+
+    let mut errors = Vec::new();
+    let mut warehouses = HashSet::new();
+    for warehouse in document.warehouses.iter() {
+        if warehouse.name.trim().is_empty() {
+            errors.push(String::from("A warehouse has no name."));
+        } else if !warehouses.insert(warehouse.name.as_str()) {
+            errors.push(format!("The `{}` warehouse is in the document more than once.", warehouse.name));
+        }
+
+        // Item names are unique within a warehouse
+        let mut items = HashSet::new();
+        for item in warehouse.items.iter() {
+            if item.name.trim().is_empty() {
+                errors.push(format!("An item in the `{}` warehouse has no name.", warehouse.name));
+                continue;
+            }
+            if !items.insert(item.name.as_str()) {
+                errors.push(format!("`{}` is in the `{}` warehouse more than once.", item.name, warehouse.name));
+            }
+
+            if let Some(rule) = item.rule.as_ref() {
+                if let Err(err) = rule.severity.parse::<Severity>() {
+                    errors.push(format!("`{}`: {}", item.name, err));
+                }
+                match (rule.gets_below_quantity, rule.expression.as_deref()) {
+                    (None, None) => { errors.push(format!("The rule of `{}` has neither `gets_below_quantity` nor `expression`.", item.name)); }
+                    (_, Some(expression)) => {
+                        if let Err(err) = Expr::parse(expression) {
+                            errors.push(format!("The rule of `{}` has an invalid expression `{}`. Reason: {}", item.name, expression, err));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(document),
+        false => Err(errors),
+    }
+}
+
+// What importing a document does to a workspace
+#[derive(Default)]
+pub struct Plan<'a> {
+    // Warehouses of the document that don't exist yet, in document order
+    pub new_warehouses: Vec<&'a str>,
+    // Items to insert, with their warehouse
+    pub new_items: Vec<(&'a str, &'a Item)>,
+    // Existing items replaced by the ones of the document (`Conflict::Overwrite`)
+    pub replaced: Vec<(&'a str, &'a Item)>,
+    // Existing items left alone (`Conflict::Skip`), with the warehouse they are in
+    pub skipped: Vec<(&'a str, String)>,
+}
+
+// `existing` holds the warehouse and item names of the workspace. Only fails with
+// `Conflict::Fail`, listing every item that already exists.
+pub fn plan<'a>(document: &'a Document, warehouses: &HashSet<String>, existing: &HashSet<(String, String)>, conflict: Conflict) -> Result<Plan<'a>, Vec<String>> {
+    let mut plan = Plan::default();
+    let mut errors = Vec::new();
+
+    for warehouse in document.warehouses.iter() {
+        if !warehouses.contains(&warehouse.name) {
+            plan.new_warehouses.push(&warehouse.name);
+        }
+
+        for item in warehouse.items.iter() {
+            match (existing.contains(&(warehouse.name.clone(), item.name.clone())), conflict) {
+                (false, _) => { plan.new_items.push((&warehouse.name, item)); }
+                (true, Conflict::Fail) => { errors.push(format!("`{}` already exists in the `{}` warehouse.", item.name, warehouse.name)); }
+                (true, Conflict::Skip) => { plan.skipped.push((&item.name, warehouse.name.clone())); }
+                (true, Conflict::Overwrite) => { plan.replaced.push((&warehouse.name, item)); }
+            }
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(plan),
+        false => Err(errors),
+    }
+}
+
+// Tags are stored comma separated (see `import::normalize_tags`)
+pub fn tags_to_list(tags: Option<&str>) -> Vec<String> {
+    tags.unwrap_or_default().split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(str::to_string).collect()
+}
+
+pub fn tags_from_list(tags: &[String]) -> Option<String> {
+    let tags: Vec<&str> = tags.iter().map(|tag| tag.trim()).filter(|tag| !tag.is_empty()).collect();
+    match tags.is_empty() {
+        true => None,
+        false => Some(tags.join(", ")),
+    }
+}
@@ -0,0 +1,787 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+// Hard limits so that a rule can never make the raport hang or blow the stack
+const MAX_LENGTH: usize = 512;
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ExprError {
+    #[error("Expression is too long (at most {0} characters are allowed).")]
+    TooLong(usize),
+
+    #[error("Expression is nested too deeply (at most {0} levels are allowed).")]
+    TooDeep(usize),
+
+    #[error("Unexpected character `{0}` at position {1}.")]
+    UnexpectedChar(char, usize),
+
+    #[error("Unterminated item name starting at position {0}.")]
+    UnterminatedName(usize),
+
+    #[error("Invalid number `{0}`.")]
+    InvalidNumber(String),
+
+    #[error("Unexpected `{0}` at position {1}.")]
+    UnexpectedToken(String, usize),
+
+    #[error("Unexpected end of the expression.")]
+    UnexpectedEnd,
+
+    #[error("Unknown function `{0}`.")]
+    UnknownFunction(String),
+
+    #[error("Function `{0}` takes {1} argument(s), {2} given.")]
+    WrongArity(String, usize, usize),
+
+    #[error("`{0}` expects a number, not a condition.")]
+    ExpectedNumber(String),
+
+    #[error("`{0}` expects a condition, not a number.")]
+    ExpectedCondition(String),
+
+    #[error("`consumption` expects a number of days above 0 (e.g. `consumption(30)`).")]
+    ExpectedDays,
+
+    #[error("A rule expression must be a condition (e.g. `quantity < 4`).")]
+    NotACondition,
+
+    #[error("Unknown item `{0}`.")]
+    UnknownItem(String),
+
+    #[error("Division by zero.")]
+    DivisionByZero,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warn,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warn" | "warning" => Ok(Severity::Warn),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(format!("Unknown severity `{}` (expected info, warn or critical).", value)),
+        }
+    }
+}
+
+// Everything an expression is allowed to know about the world. The raport provides
+// an implementation backed by the items and movements of a single warehouse.
+pub trait Context {
+    // Quantity of the item the rule belongs to (fractional when forecasting)
+    fn quantity(&self) -> f64;
+
+    // Quantity of another item in the same warehouse
+    fn item_quantity(&self, name: &str) -> Option<f64>;
+
+    // Total outflow of the rule's item during the last `days` days
+    fn consumption(&self, days: f64) -> f64;
+
+    // Days since the rule's item last changed quantity (infinite if it never did)
+    fn days_idle(&self) -> f64;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Consumption,
+    Min,
+    Max,
+    Abs,
+}
+
+impl Function {
+    fn lookup(name: &str) -> Option<Self> {
+        match name {
+            "consumption" => Some(Function::Consumption),
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            "abs" => Some(Function::Abs),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Function::Consumption => "consumption",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Abs => "abs",
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Function::Consumption | Function::Abs => 1,
+            Function::Min | Function::Max => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Bool(bool),
+    Quantity,
+    DaysIdle,
+    Item(String),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Number,
+    Condition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Number(f64),
+    Bool(bool),
+}
+
+impl Expr {
+    // Parses and type checks a rule expression. The result is guaranteed to be a condition.
+    pub fn parse(source: &str) -> Result<Expr, ExprError> {
+        if source.chars().count() > MAX_LENGTH {
+            return Err(ExprError::TooLong(MAX_LENGTH));
+        }
+
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, position: 0, depth: 0 };
+        let expr = parser.parse_or()?;
+
+        if let Some((token, position)) = parser.tokens.get(parser.position) {
+            return Err(ExprError::UnexpectedToken(token.to_string(), *position));
+        }
+
+        if expr.kind()? != Kind::Condition {
+            return Err(ExprError::NotACondition);
+        }
+
+        Ok(expr)
+    }
+
+    // Names of all the items referenced by the expression
+    pub fn items(&self) -> HashSet<&str> {
+        let mut items = HashSet::new();
+        self.collect_items(&mut items);
+        items
+    }
+
+    fn collect_items<'a>(&'a self, items: &mut HashSet<&'a str>) {
+        match self {
+            Expr::Item(name) => { items.insert(name); }
+            Expr::Neg(expr) | Expr::Not(expr) => { expr.collect_items(items); }
+            Expr::Binary(_, lhs, rhs) => { lhs.collect_items(items); rhs.collect_items(items); }
+            Expr::Call(_, args) => { for arg in args { arg.collect_items(items); } }
+            Expr::Number(_) | Expr::Bool(_) | Expr::Quantity | Expr::DaysIdle => { }
+        }
+    }
+
+    fn kind(&self) -> Result<Kind, ExprError> {
+        let expect = |expr: &Expr, kind: Kind, what: &str| -> Result<(), ExprError> {
+            match (expr.kind()?, kind) {
+                (Kind::Number, Kind::Condition) => Err(ExprError::ExpectedCondition(what.to_string())),
+                (Kind::Condition, Kind::Number) => Err(ExprError::ExpectedNumber(what.to_string())),
+                _ => Ok(()),
+            }
+        };
+
+        match self {
+            Expr::Number(_) | Expr::Quantity | Expr::DaysIdle | Expr::Item(_) => Ok(Kind::Number),
+            Expr::Bool(_) => Ok(Kind::Condition),
+            Expr::Neg(expr) => { expect(expr, Kind::Number, "-")?; Ok(Kind::Number) }
+            Expr::Not(expr) => { expect(expr, Kind::Condition, "not")?; Ok(Kind::Condition) }
+            Expr::Binary(op, lhs, rhs) => {
+                let (operands, result) = match op {
+                    BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => (Kind::Number, Kind::Number),
+                    BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => (Kind::Number, Kind::Condition),
+                    BinaryOp::And | BinaryOp::Or => (Kind::Condition, Kind::Condition),
+                    BinaryOp::Eq | BinaryOp::Ne => {
+                        if lhs.kind()? != rhs.kind()? {
+                            return Err(ExprError::ExpectedNumber(op.symbol().to_string()));
+                        }
+                        return Ok(Kind::Condition);
+                    }
+                };
+
+                expect(lhs, operands, op.symbol())?;
+                expect(rhs, operands, op.symbol())?;
+                Ok(result)
+            }
+            Expr::Call(function, args) => {
+                for arg in args {
+                    expect(arg, Kind::Number, function.name())?;
+                }
+
+                // The days are a window into the past, so only a literal above 0 makes sense
+                if *function == Function::Consumption && !matches!(args.first(), Some(Expr::Number(days)) if *days > 0.0) {
+                    return Err(ExprError::ExpectedDays);
+                }
+                Ok(Kind::Number)
+            }
+        }
+    }
+
+    // Evaluates a (parsed, therefore well typed) condition
+    pub fn evaluate(&self, context: &dyn Context) -> Result<bool, ExprError> {
+        match self.value(context)? {
+            Value::Bool(value) => Ok(value),
+            Value::Number(_) => Err(ExprError::NotACondition),
+        }
+    }
+
+    fn number(&self, context: &dyn Context) -> Result<f64, ExprError> {
+        match self.value(context)? {
+            Value::Number(value) => Ok(value),
+            Value::Bool(_) => Err(ExprError::NotACondition),
+        }
+    }
+
+    fn value(&self, context: &dyn Context) -> Result<Value, ExprError> {
+        let value = match self {
+            Expr::Number(value) => Value::Number(*value),
+            Expr::Bool(value) => Value::Bool(*value),
+            Expr::Quantity => Value::Number(context.quantity()),
+            Expr::DaysIdle => Value::Number(context.days_idle()),
+            Expr::Item(name) => {
+                match context.item_quantity(name) {
+                    Some(quantity) => Value::Number(quantity),
+                    None => { return Err(ExprError::UnknownItem(name.clone())); }
+                }
+            }
+            Expr::Neg(expr) => Value::Number(-expr.number(context)?),
+            Expr::Not(expr) => Value::Bool(!expr.evaluate(context)?),
+            Expr::Binary(BinaryOp::And, lhs, rhs) => Value::Bool(lhs.evaluate(context)? && rhs.evaluate(context)?),
+            Expr::Binary(BinaryOp::Or, lhs, rhs) => Value::Bool(lhs.evaluate(context)? || rhs.evaluate(context)?),
+            Expr::Binary(op @ (BinaryOp::Eq | BinaryOp::Ne), lhs, rhs) => {
+                let equal = lhs.value(context)? == rhs.value(context)?;
+                Value::Bool(if *op == BinaryOp::Eq { equal } else { !equal })
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.number(context)?;
+                let rhs = rhs.number(context)?;
+                match op {
+                    BinaryOp::Add => Value::Number(lhs + rhs),
+                    BinaryOp::Sub => Value::Number(lhs - rhs),
+                    BinaryOp::Mul => Value::Number(lhs * rhs),
+                    BinaryOp::Div => {
+                        if rhs == 0.0 { return Err(ExprError::DivisionByZero); }
+                        Value::Number(lhs / rhs)
+                    }
+                    BinaryOp::Lt => Value::Bool(lhs < rhs),
+                    BinaryOp::Le => Value::Bool(lhs <= rhs),
+                    BinaryOp::Gt => Value::Bool(lhs > rhs),
+                    BinaryOp::Ge => Value::Bool(lhs >= rhs),
+                    BinaryOp::Eq | BinaryOp::Ne | BinaryOp::And | BinaryOp::Or => unreachable!(),
+                }
+            }
+            Expr::Call(function, args) => {
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args {
+                    values.push(arg.number(context)?);
+                }
+
+                Value::Number(match function {
+                    Function::Consumption => context.consumption(values[0]),
+                    Function::Min => values[0].min(values[1]),
+                    Function::Max => values[0].max(values[1]),
+                    Function::Abs => values[0].abs(),
+                })
+            }
+        };
+
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Name(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{}", value),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Name(name) => write!(f, "`{}`", name),
+            Token::Op(op) => write!(f, "{}", op),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ExprError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let start = index;
+        let char = chars[index];
+
+        if char.is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        // Item names containing spaces or symbols are written in backticks, e.g. `Human Figures`
+        if char == '`' {
+            index += 1;
+            while index < chars.len() && chars[index] != '`' { index += 1; }
+            if index == chars.len() {
+                return Err(ExprError::UnterminatedName(start));
+            }
+
+            tokens.push((Token::Name(chars[start+1..index].iter().collect()), start));
+            index += 1;
+            continue;
+        }
+
+        if char.is_ascii_digit() || (char == '.' && chars.get(index+1).is_some_and(|next| next.is_ascii_digit())) {
+            while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '.') { index += 1; }
+
+            let literal: String = chars[start..index].iter().collect();
+            match literal.parse::<f64>() {
+                Ok(value) => { tokens.push((Token::Number(value), start)); }
+                Err(_) => { return Err(ExprError::InvalidNumber(literal)); }
+            }
+            continue;
+        }
+
+        if char.is_alphabetic() || char == '_' {
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') { index += 1; }
+            tokens.push((Token::Ident(chars[start..index].iter().collect()), start));
+            continue;
+        }
+
+        let next = chars.get(index+1).copied();
+        let (token, length) = match (char, next) {
+            ('<', Some('=')) => (Token::Op("<="), 2),
+            ('>', Some('=')) => (Token::Op(">="), 2),
+            ('=', Some('=')) => (Token::Op("=="), 2),
+            ('!', Some('=')) => (Token::Op("!="), 2),
+            ('&', Some('&')) => (Token::Op("and"), 2),
+            ('|', Some('|')) => (Token::Op("or"), 2),
+            ('<', _) => (Token::Op("<"), 1),
+            ('>', _) => (Token::Op(">"), 1),
+            ('!', _) => (Token::Op("not"), 1),
+            ('+', _) => (Token::Op("+"), 1),
+            ('-', _) => (Token::Op("-"), 1),
+            ('*', _) => (Token::Op("*"), 1),
+            ('/', _) => (Token::Op("/"), 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            (',', _) => (Token::Comma, 1),
+            _ => { return Err(ExprError::UnexpectedChar(char, start)); }
+        };
+
+        tokens.push((token, start));
+        index += length;
+    }
+
+    // Word operators are normalized to their symbolic counterparts
+    for (token, _) in tokens.iter_mut() {
+        if let Token::Ident(name) = token {
+            match name.as_str() {
+                "and" => { *token = Token::Op("and"); }
+                "or" => { *token = Token::Op("or"); }
+                "not" => { *token = Token::Op("not"); }
+                _ => { }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<(Token, usize), ExprError> {
+        match self.tokens.get(self.position) {
+            Some(token) => { self.position += 1; Ok(token.clone()) }
+            None => Err(ExprError::UnexpectedEnd),
+        }
+    }
+
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        if let Some(Token::Op(op)) = self.peek() {
+            if ops.contains(op) {
+                let op = *op;
+                self.position += 1;
+                return Some(op);
+            }
+        }
+        None
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ExprError> {
+        let (token, position) = self.next()?;
+        if token != expected {
+            return Err(ExprError::UnexpectedToken(token.to_string(), position));
+        }
+        Ok(())
+    }
+
+    fn descend(&mut self) -> Result<(), ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ExprError::TooDeep(MAX_DEPTH));
+        }
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ExprError> {
+        let mut lhs = self.parse_and()?;
+        while self.eat_op(&["or"]).is_some() {
+            let rhs = self.parse_and()?;
+            lhs = Expr::Binary(BinaryOp::Or, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ExprError> {
+        let mut lhs = self.parse_not()?;
+        while self.eat_op(&["and"]).is_some() {
+            let rhs = self.parse_not()?;
+            lhs = Expr::Binary(BinaryOp::And, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ExprError> {
+        if self.eat_op(&["not"]).is_some() {
+            self.descend()?;
+            let expr = self.parse_not()?;
+            self.depth -= 1;
+            return Ok(Expr::Not(Box::new(expr)));
+        }
+        self.parse_comparison()
+    }
+
+    // Comparisons don't chain, `1 < 2 < 3` is rejected
+    fn parse_comparison(&mut self) -> Result<Expr, ExprError> {
+        let lhs = self.parse_sum()?;
+        let op = match self.eat_op(&["<", "<=", ">", ">=", "==", "!="]) {
+            Some("<") => BinaryOp::Lt,
+            Some("<=") => BinaryOp::Le,
+            Some(">") => BinaryOp::Gt,
+            Some(">=") => BinaryOp::Ge,
+            Some("==") => BinaryOp::Eq,
+            Some(_) => BinaryOp::Ne,
+            None => { return Ok(lhs); }
+        };
+        let rhs = self.parse_sum()?;
+        Ok(Expr::Binary(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr, ExprError> {
+        let mut lhs = self.parse_product()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            let rhs = self.parse_product()?;
+            let op = if op == "+" { BinaryOp::Add } else { BinaryOp::Sub };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_product(&mut self) -> Result<Expr, ExprError> {
+        let mut lhs = self.parse_unary()?;
+        while let Some(op) = self.eat_op(&["*", "/"]) {
+            let rhs = self.parse_unary()?;
+            let op = if op == "*" { BinaryOp::Mul } else { BinaryOp::Div };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ExprError> {
+        if self.eat_op(&["-"]).is_some() {
+            self.descend()?;
+            let expr = self.parse_unary()?;
+            self.depth -= 1;
+            return Ok(Expr::Neg(Box::new(expr)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ExprError> {
+        let (token, position) = self.next()?;
+        match token {
+            Token::Number(value) => Ok(Expr::Number(value)),
+            Token::Name(name) => Ok(Expr::Item(name)),
+            Token::LParen => {
+                self.descend()?;
+                let expr = self.parse_or()?;
+                self.depth -= 1;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Token::Ident(name) => {
+                if self.peek() == Some(&Token::LParen) {
+                    return self.parse_call(name);
+                }
+
+                Ok(match name.as_str() {
+                    "true" => Expr::Bool(true),
+                    "false" => Expr::Bool(false),
+                    "quantity" => Expr::Quantity,
+                    "days_idle" => Expr::DaysIdle,
+                    _ => Expr::Item(name),
+                })
+            }
+            _ => Err(ExprError::UnexpectedToken(token.to_string(), position)),
+        }
+    }
+
+    fn parse_call(&mut self, name: String) -> Result<Expr, ExprError> {
+        let function = match Function::lookup(&name) {
+            Some(function) => function,
+            None => { return Err(ExprError::UnknownFunction(name)); }
+        };
+
+        self.expect(Token::LParen)?;
+        self.descend()?;
+
+        let mut args = Vec::new();
+        if self.peek() != Some(&Token::RParen) {
+            loop {
+                args.push(self.parse_or()?);
+                if self.peek() != Some(&Token::Comma) { break; }
+                self.position += 1;
+            }
+        }
+
+        self.depth -= 1;
+        self.expect(Token::RParen)?;
+
+        if args.len() != function.arity() {
+            return Err(ExprError::WrongArity(name, function.arity(), args.len()));
+        }
+
+        Ok(Expr::Call(function, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A warehouse with Egg, Eggs_boxed and `Human Figures`, the rule being Egg's
+    struct Fixture {
+        quantity: f64,
+        eggs_boxed: f64,
+        daily_consumption: f64,
+        days_idle: f64,
+    }
+
+    impl Context for Fixture {
+        fn quantity(&self) -> f64 {
+            self.quantity
+        }
+
+        fn item_quantity(&self, name: &str) -> Option<f64> {
+            match name {
+                "Egg" => Some(self.quantity),
+                "Eggs_boxed" => Some(self.eggs_boxed),
+                "Human Figures" => Some(3.0),
+                _ => None,
+            }
+        }
+
+        fn consumption(&self, days: f64) -> f64 {
+            self.daily_consumption * days
+        }
+
+        fn days_idle(&self) -> f64 {
+            self.days_idle
+        }
+    }
+
+    const FIXTURE: Fixture = Fixture { quantity: 5.0, eggs_boxed: 1.0, daily_consumption: 2.0, days_idle: 10.0 };
+
+    fn evaluate(source: &str, context: &Fixture) -> Result<bool, ExprError> {
+        Expr::parse(source)?.evaluate(context)
+    }
+
+    #[test]
+    fn precedence() {
+        for source in [
+            "1 + 2 * 3 == 7",
+            "(1 + 2) * 3 == 9",
+            "10 - 4 - 3 == 3",
+            "12 / 3 / 2 == 2",
+            "-2 * 3 == -6",
+            "--2 == 2",
+            "true or false and false",
+            "not false and true",
+            "not (true and false)",
+            "(1 < 2) == (3 < 4)",
+            "quantity + 1 > 5 && !(quantity > 5)",
+        ] {
+            assert_eq!(evaluate(source, &FIXTURE), Ok(true), "{}", source);
+        }
+
+        assert_eq!(evaluate("(true or false) and false", &FIXTURE), Ok(false));
+        assert_eq!(evaluate("1 < 2 < 3", &FIXTURE), Err(ExprError::UnexpectedToken(String::from("<"), 6)));
+    }
+
+    #[test]
+    fn limits() {
+        let longest = format!("{:<width$}", "quantity < 1", width = MAX_LENGTH);
+        assert!(Expr::parse(&longest).is_ok());
+        assert_eq!(Expr::parse(&format!("{} ", longest)), Err(ExprError::TooLong(MAX_LENGTH)));
+
+        let nested = |depth: usize| format!("{}true{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Expr::parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(Expr::parse(&nested(MAX_DEPTH + 1)), Err(ExprError::TooDeep(MAX_DEPTH)));
+        assert_eq!(Expr::parse(&format!("{}true", "not ".repeat(MAX_DEPTH + 1))), Err(ExprError::TooDeep(MAX_DEPTH)));
+        assert_eq!(Expr::parse(&format!("{}1 < 2", "-".repeat(MAX_DEPTH + 1))), Err(ExprError::TooDeep(MAX_DEPTH)));
+        assert_eq!(Expr::parse(&format!("{}1{} < 2", "abs(".repeat(MAX_DEPTH + 1), ")".repeat(MAX_DEPTH + 1))), Err(ExprError::TooDeep(MAX_DEPTH)));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(evaluate("quantity / 0 < 1", &FIXTURE), Err(ExprError::DivisionByZero));
+        assert_eq!(evaluate("quantity / (Egg - quantity) < 1", &FIXTURE), Err(ExprError::DivisionByZero));
+        // Only evaluated operands count
+        assert_eq!(evaluate("true or quantity / 0 < 1", &FIXTURE), Ok(true));
+    }
+
+    #[test]
+    fn unknown_functions_and_items() {
+        assert_eq!(Expr::parse("average(30) < 1"), Err(ExprError::UnknownFunction(String::from("average"))));
+        assert_eq!(Expr::parse("min(1) < 1"), Err(ExprError::WrongArity(String::from("min"), 2, 1)));
+        assert_eq!(Expr::parse("consumption(1 < 2) < 1"), Err(ExprError::ExpectedNumber(String::from("consumption"))));
+        assert_eq!(Expr::parse("consumption(-5) > 1"), Err(ExprError::ExpectedDays));
+        assert_eq!(Expr::parse("consumption(0) > 1"), Err(ExprError::ExpectedDays));
+        assert_eq!(Expr::parse("consumption(days_idle) > 1"), Err(ExprError::ExpectedDays));
+        assert!(Expr::parse("consumption(0.5) > 1").is_ok());
+
+        // Items are only known when evaluating, `rules -c` checks them against the warehouse
+        let expr = Expr::parse("Eggs_boxed + Milk < 4").expect("doesn't parse");
+        assert_eq!(expr.items(), HashSet::from(["Eggs_boxed", "Milk"]));
+        assert_eq!(expr.evaluate(&FIXTURE), Err(ExprError::UnknownItem(String::from("Milk"))));
+    }
+
+    #[test]
+    fn backticked_names() {
+        let expr = Expr::parse("`Human Figures` * 2 > quantity").expect("doesn't parse");
+        assert_eq!(expr.items(), HashSet::from(["Human Figures"]));
+        assert_eq!(expr.evaluate(&FIXTURE), Ok(true));
+        assert_eq!(evaluate("`Egg` == Egg", &FIXTURE), Ok(true));
+        // Keywords in backticks are items
+        assert_eq!(Expr::parse("`quantity` < 1").map(|expr| expr.items().contains("quantity")), Ok(true));
+        assert_eq!(Expr::parse("`Human Figures < 3"), Err(ExprError::UnterminatedName(0)));
+    }
+
+    #[test]
+    fn conditions_and_numbers() {
+        assert_eq!(Expr::parse("quantity + 1"), Err(ExprError::NotACondition));
+        assert_eq!(Expr::parse("quantity and true"), Err(ExprError::ExpectedCondition(String::from("and"))));
+        assert_eq!(Expr::parse("(1 < 2) + 1 < 3"), Err(ExprError::ExpectedNumber(String::from("+"))));
+        assert_eq!(Expr::parse("quantity < 1 ?"), Err(ExprError::UnexpectedChar('?', 13)));
+        assert_eq!(Expr::parse("1.2.3 < 1"), Err(ExprError::InvalidNumber(String::from("1.2.3"))));
+        assert_eq!(Expr::parse("quantity <"), Err(ExprError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn example_rules() {
+        // Below 10% of the average daily consumption over 30 days
+        let low = "quantity < 0.1 * consumption(30) / 30";
+        assert_eq!(evaluate(low, &FIXTURE), Ok(false));
+        assert_eq!(evaluate(low, &Fixture { quantity: 0.1, ..FIXTURE }), Ok(true));
+
+        // The sum of Egg + Eggs_boxed * 6 < 12
+        let boxed = "Egg + Eggs_boxed * 6 < 12";
+        assert_eq!(evaluate(boxed, &FIXTURE), Ok(true));
+        assert_eq!(evaluate(boxed, &Fixture { eggs_boxed: 2.0, ..FIXTURE }), Ok(false));
+
+        // No movement in 90 days
+        let idle = "days_idle > 90";
+        assert_eq!(evaluate(idle, &FIXTURE), Ok(false));
+        assert_eq!(evaluate(idle, &Fixture { days_idle: f64::INFINITY, ..FIXTURE }), Ok(true));
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// Reading items from CSV for `deposito import csv` and working out what importing them
+// changes in a warehouse. Applying the changes is up to `Deposito::import_csv`.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Name,
+    Quantity,
+    Description,
+    Min,
+    Unit,
+    Tags,
+}
+
+impl Field {
+    // Order of the columns when a file has no header and no `--columns`
+    const ALL: [Field; 6] = [Field::Name, Field::Quantity, Field::Description, Field::Min, Field::Unit, Field::Tags];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Field::Name => "name",
+            Field::Quantity => "quantity",
+            Field::Description => "description",
+            Field::Min => "min",
+            Field::Unit => "unit",
+            Field::Tags => "tags",
+        }
+    }
+
+    // Headers recognised for the field, compared without case, spaces, `-` and `_`
+    fn headers(&self) -> &'static [&'static str] {
+        match self {
+            Field::Name => &["name", "item", "itemname", "product", "article"],
+            Field::Quantity => &["quantity", "qty", "count", "amount", "stock"],
+            Field::Description => &["description", "desc", "note", "notes", "comment"],
+            Field::Min => &["min", "minrule", "minimum", "minquantity", "reorderlevel"],
+            Field::Unit => &["unit", "units", "uom"],
+            Field::Tags => &["tags", "tag", "labels", "category"],
+        }
+    }
+
+    fn from_header(header: &str) -> Option<Field> {
+        let header: String = header.chars()
+            .filter(|char| !matches!(char, ' ' | '-' | '_'))
+            .flat_map(char::to_lowercase)
+            .collect();
+        Field::ALL.into_iter().find(|field| field.headers().contains(&header.as_str()))
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match Field::from_header(value) {
+            Some(field) => Ok(field),
+            None => {
+                let fields: Vec<&str> = Field::ALL.iter().map(Field::as_str).collect();
+                Err(format!("Unknown column `{}` (expected {} or `-` to skip it).", value, fields.join(", ")))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Mode {
+    // Like `deposito add`: new items are added, existing ones only get the quantity added
+    #[default]
+    Add,
+    // The warehouse ends up with exactly the items of the file
+    Replace,
+    // New items are added, existing ones take the quantity and whatever else the file has
+    Upsert,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "add" => Ok(Mode::Add),
+            "replace" => Ok(Mode::Replace),
+            "upsert" => Ok(Mode::Upsert),
+            _ => Err(format!("Unknown import mode `{}` (expected add, replace or upsert).", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub name: String,
+    pub quantity: i64,
+    pub description: Option<String>,
+    // Quantity of a `Can't go below` rule
+    pub min: Option<i64>,
+    pub unit: Option<String>,
+    pub tags: Option<String>,
+    // Condition of the item's rule when it's an expression, which a minimum doesn't replace
+    pub expression: Option<String>,
+}
+
+pub enum Change {
+    New(Item),
+    Update { before: Item, after: Item },
+    Remove(Item),
+    Unchanged,
+}
+
+impl Change {
+    // Fields that differ, for the diff
+    pub fn changed_fields(before: &Item, after: &Item) -> Vec<&'static str> {
+        let mut fields = Vec::new();
+        if before.description != after.description { fields.push("description"); }
+        if before.min != after.min { fields.push("min"); }
+        if before.unit != after.unit { fields.push("unit"); }
+        if before.tags != after.tags { fields.push("tags"); }
+        fields
+    }
+}
+
+// Picks whichever of `,`, `;` and tab the first line has the most of, so that files
+// exported by spreadsheets set to other locales work as well
+fn detect_delimiter(input: &str) -> u8 {
+    let first_line = input.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+    [b',', b';', b'\t'].into_iter()
+        .max_by_key(|delimiter| first_line.bytes().filter(|byte| byte == delimiter).count())
+        .filter(|delimiter| first_line.as_bytes().contains(delimiter))
+        .unwrap_or(b',')
+}
+
+// Multiple tags in a cell may be separated by `,`, `;` or `|`, they're stored comma separated
+fn normalize_tags(tags: &str) -> Option<String> {
+    let mut normalized: Vec<&str> = Vec::new();
+    for tag in tags.split([',', ';', '|']).map(str::trim).filter(|tag| !tag.is_empty()) {
+        if !normalized.contains(&tag) { normalized.push(tag); }
+    }
+
+    match normalized.is_empty() {
+        true => None,
+        false => Some(normalized.join(", ")),
+    }
+}
+
+fn parse_number(text: &str, what: &str, line: u64) -> Result<Option<i64>, String> {
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    match text.parse::<i64>() {
+        Ok(number) if number >= 0 => Ok(Some(number)),
+        _ => Err(format!("Line {}: invalid {} `{}`, expected a whole number that isn't negative.", line, what, text)),
+    }
+}
+
+// Reads the items of a CSV file. Columns are mapped by `columns` (a comma separated list of
+// fields, `-` skipping a column) or else by the header. A first row is taken for a header
+// when it names at least one field and its quantity isn't a number. Files without one are
+// expected to have the columns in the order of `Field::ALL`. With `columns`, any first row
+// with something other than a number for the quantity is a header.
+//
+// Every problem is reported with its line, and the file only gets imported without any.
+pub fn read(input: &str, columns: Option<&str>) -> Result<Vec<Item>, Vec<String>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .delimiter(detect_delimiter(input))
+        .from_reader(input.as_bytes());
+
+    let mut records = Vec::new();
+    for record in reader.records() {
+        match record {
+            Ok(record) => {
+                let line = record.position().map(|position| position.line()).unwrap_or_default();
+                let cells: Vec<String> = record.iter().map(str::to_string).collect();
+                if cells.iter().any(|cell| !cell.is_empty()) {
+                    records.push((line, cells));
+                }
+            }
+            Err(err) => { return Err(vec![format!("Unable to read the file. Reason: {}", err)]); }
+        }
+    }
+
+    let first = match records.first() {
+        Some((_, cells)) => cells.clone(),
+        None => { return Ok(Vec::new()); }
+    };
+
+    let from_header: Vec<Option<Field>> = first.iter().map(|cell| Field::from_header(cell)).collect();
+    let mapping: Vec<Option<Field>> = match columns {
+        Some(columns) => {
+            let mut mapping = Vec::new();
+            for column in columns.split(',').map(str::trim) {
+                match column {
+                    "" | "-" => { mapping.push(None); }
+                    column => { mapping.push(Some(column.parse::<Field>().map_err(|err| vec![err])?)); }
+                }
+            }
+            mapping
+        }
+        None if from_header.iter().any(Option::is_some) => from_header.clone(),
+        None => Field::ALL.iter().copied().map(Some).collect(),
+    };
+
+    for field in Field::ALL.iter() {
+        if mapping.iter().filter(|mapped| **mapped == Some(*field)).count() > 1 {
+            return Err(vec![format!("More than one column holds the {}.", field.as_str())]);
+        }
+    }
+    let position = |field: Field| mapping.iter().position(|mapped| *mapped == Some(field));
+    if position(Field::Name).is_none() {
+        return Err(vec![String::from("None of the columns holds the item names, use `--columns` to say which one does.")]);
+    }
+
+    let quantity_is_number = position(Field::Quantity)
+        .and_then(|index| first.get(index))
+        .is_none_or(|cell| cell.is_empty() || cell.parse::<i64>().is_ok());
+    // Headers given `--columns` don't have to name anything
+    let has_header = (columns.is_some() || from_header.iter().any(Option::is_some)) && !quantity_is_number;
+
+    let cell = |cells: &[String], field: Field| -> String {
+        position(field).and_then(|index| cells.get(index)).cloned().unwrap_or_default()
+    };
+
+    let mut rows: Vec<Item> = Vec::new();
+    let mut lines_by_name: HashMap<String, u64> = HashMap::new();
+    let mut errors = Vec::new();
+    for (line, cells) in records.into_iter().skip(if has_header { 1 } else { 0 }) {
+        let name = cell(&cells, Field::Name);
+        if name.is_empty() {
+            errors.push(format!("Line {}: the item name is missing.", line));
+            continue;
+        }
+        if let Some(previous) = lines_by_name.insert(name.clone(), line) {
+            errors.push(format!("Line {}: `{}` is already on line {}.", line, name, previous));
+            continue;
+        }
+
+        let (quantity, min) = match (parse_number(&cell(&cells, Field::Quantity), "quantity", line), parse_number(&cell(&cells, Field::Min), "min", line)) {
+            (Ok(quantity), Ok(min)) => (quantity.unwrap_or_default(), min),
+            (Err(err), _) | (_, Err(err)) => { errors.push(err); continue; }
+        };
+
+        let optional = |text: String| if text.is_empty() { None } else { Some(text) };
+        rows.push(Item {
+            name,
+            quantity,
+            description: optional(cell(&cells, Field::Description)),
+            min,
+            unit: optional(cell(&cells, Field::Unit)),
+            tags: normalize_tags(&cell(&cells, Field::Tags)),
+            expression: None,
+        });
+    }
+
+    match errors.is_empty() {
+        true => Ok(rows),
+        false => Err(errors),
+    }
+}
+
+// Rows whose minimum gets skipped since the existing item's rule is an expression, with
+// that expression. Adding never touches the rules of existing items.
+pub fn kept_rules(rows: &[Item], existing: &[Item], mode: Mode) -> Vec<(String, String)> {
+    if mode == Mode::Add {
+        return Vec::new();
+    }
+
+    rows.iter()
+        .filter(|item| item.min.is_some())
+        .filter_map(|item| {
+            let expression = existing.iter().find(|before| before.name == item.name)?.expression.clone()?;
+            Some((item.name.clone(), expression))
+        })
+        .collect()
+}
+
+// What importing the rows does to a warehouse holding the `existing` items
+pub fn plan(rows: Vec<Item>, existing: Vec<Item>, mode: Mode) -> Vec<Change> {
+    let mut existing: HashMap<String, Item> = existing.into_iter().map(|item| (item.name.clone(), item)).collect();
+    let mut changes = Vec::new();
+
+    for item in rows {
+        let before = match existing.remove(&item.name) {
+            Some(before) => before,
+            None => { changes.push(Change::New(item)); continue; }
+        };
+
+        let after = match mode {
+            Mode::Add => Item { quantity: before.quantity + item.quantity, ..before.clone() },
+            Mode::Replace | Mode::Upsert => Item {
+                name: item.name,
+                quantity: item.quantity,
+                description: item.description.or(before.description.clone()),
+                min: match before.expression { Some(_) => before.min, None => item.min.or(before.min) },
+                unit: item.unit.or(before.unit.clone()),
+                tags: item.tags.or(before.tags.clone()),
+                expression: before.expression.clone(),
+            },
+        };
+
+        if after == before {
+            changes.push(Change::Unchanged);
+        } else {
+            changes.push(Change::Update { before, after });
+        }
+    }
+
+    if mode == Mode::Replace {
+        let mut removed: Vec<Item> = existing.into_values().collect();
+        removed.sort_by(|a, b| a.name.cmp(&b.name));
+        changes.extend(removed.into_iter().map(Change::Remove));
+    }
+
+    changes
+}
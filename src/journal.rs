@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+
+// The undo journal (`deposito undo` and `redo`). Commands that change the inventory are
+// recorded as the rows they changed, as they were before and after, ids included. Undoing
+// puts the rows from before back in place of those from after, redoing does the opposite.
+
+// Entries kept, older ones are dropped as new ones are recorded
+pub const LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "table", rename_all = "snake_case")]
+pub enum Row {
+    Warehouse {
+        id: i64,
+        name: String,
+        // Entries recorded before archiving existed lack it
+        #[serde(default)]
+        archived_at: Option<i64>,
+    },
+    Item {
+        id: i64,
+        warehouse_id: i64,
+        name: String,
+        description: Option<String>,
+        quantity: i64,
+        unit: Option<String>,
+        tags: Option<String>,
+        #[serde(default)]
+        archived_at: Option<i64>,
+    },
+    Rule { id: i64, item_id: i64, gets_below_quantity: Option<i64>, expression: Option<String>, severity: String },
+    Movement { id: i64, item_id: i64, delta: i64, created_at: i64 },
+}
+
+impl Row {
+    pub fn table(&self) -> &'static str {
+        match self {
+            Row::Warehouse { .. } => "warehouses",
+            Row::Item { .. } => "items",
+            Row::Rule { .. } => "rules",
+            Row::Movement { .. } => "movements",
+        }
+    }
+
+    pub fn id(&self) -> i64 {
+        match self {
+            Row::Warehouse { id, .. } | Row::Item { id, .. } | Row::Rule { id, .. } | Row::Movement { id, .. } => *id,
+        }
+    }
+
+    // Rows referenced by the foreign keys of others come first
+    fn depth(&self) -> u8 {
+        match self {
+            Row::Warehouse { .. } => 0,
+            Row::Item { .. } => 1,
+            Row::Rule { .. } | Row::Movement { .. } => 2,
+        }
+    }
+
+    fn same(&self, other: &Row) -> bool {
+        self.table() == other.table() && self.id() == other.id()
+    }
+}
+
+// The rows that differ between the two captures, the ones from before and the ones from
+// after, so that a command touching one item of a large warehouse records just that item
+pub fn changes(before: Vec<Row>, after: Vec<Row>) -> (Vec<Row>, Vec<Row>) {
+    let unchanged: HashSet<Row> = {
+        let after: HashSet<&Row> = after.iter().collect();
+        before.iter().filter(|row| after.contains(row)).cloned().collect()
+    };
+
+    let before = before.into_iter().filter(|row| !unchanged.contains(row)).collect();
+    let after = after.into_iter().filter(|row| !unchanged.contains(row)).collect();
+    (before, after)
+}
+
+// Statements turning the rows `from` (as they are now) into the rows `to`, in an order the
+// foreign keys allow: new parents are inserted before their children, and children are
+// deleted before their parents
+pub struct Steps<'a> {
+    pub inserts: Vec<&'a Row>,
+    pub updates: Vec<&'a Row>,
+    pub deletes: Vec<&'a Row>,
+}
+
+pub fn steps<'a>(from: &'a [Row], to: &'a [Row]) -> Steps<'a> {
+    let mut inserts: Vec<&Row> = to.iter().filter(|row| !from.iter().any(|other| row.same(other))).collect();
+    let updates: Vec<&Row> = to.iter().filter(|row| from.iter().any(|other| row.same(other))).collect();
+    let mut deletes: Vec<&Row> = from.iter().filter(|row| !to.iter().any(|other| row.same(other))).collect();
+
+    inserts.sort_by_key(|row| row.depth());
+    deletes.sort_by_key(|row| std::cmp::Reverse(row.depth()));
+    Steps { inserts, updates, deletes }
+}
@@ -1,8 +1,67 @@
 use thiserror::Error;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-
-const DIR_NAME: &'static str = "deposito"; 
-const DATABASE_URL: &'static str = "sqlite://deposito/deposito.db"; 
+use std::sync::atomic::{AtomicUsize, Ordering};
+use expr::{Expr, Severity};
+use raport::{Movement, RaportEntry, RaportFile, Rule, Status, WarehouseState};
+use spreadsheet::{Highlight, Value, Workbook};
+use render::Stamp;
+use pdf::TitlePage;
+use output::{DiffRecord, Format, ItemRecord, RuleRecord, WarehouseRecord};
+use style::{Color, ColorChoice};
+use table::{Cell, Column, Table};
+use cli::{CliError, CommandSpec, Matches};
+use complete::Completion;
+use editor::{Editor, ReadLine};
+use shell::Names;
+use tui::{Action, App, ItemRow, WarehouseRow};
+use import::Change;
+use journal::Row;
+
+mod backup;
+mod cli;
+mod commands;
+mod complete;
+mod editor;
+mod export;
+mod expr;
+mod import;
+mod journal;
+mod manual;
+mod merge;
+mod output;
+mod pdf;
+mod raport;
+mod render;
+mod shell;
+mod snapshot;
+mod spreadsheet;
+mod style;
+mod suggest;
+mod table;
+mod tui;
+mod zip;
+
+const DIR_NAME: &str = "deposito"; 
+const DATABASE_URL: &str = "sqlite://deposito/deposito.db"; 
+const HISTORY_FILE: &str = "history";
+const SCHEMA: &str = include_str!("../deposito/deposito.sql");
+// Kept in `PRAGMA user_version` and bumped with every change to the schema, databases
+// from before it was kept are at 0
+const SCHEMA_VERSION: i64 = 6;
+// Template new warehouses start with, unless `create` is told otherwise
+const DEFAULT_TEMPLATE: &str = "default";
+
+// Errors reported by commands so far, so that `batch` can tell which of its lines failed
+static ERRORS: AtomicUsize = AtomicUsize::new(0);
+
+// `println!` for errors
+macro_rules! report_error {
+    ($($arg:tt)*) => {{
+        ERRORS.fetch_add(1, Ordering::Relaxed);
+        println!($($arg)*);
+    }};
+}
 
 #[derive(Debug, Error)]
 enum DepositoError {
@@ -13,9 +72,11 @@ enum DepositoError {
     Sqlx(sqlx::Error),
 }
 
+#[derive(Default)]
 struct Deposito {
     db_pool: Option<sqlx::SqlitePool>,
     default_warehouse: Option<String>,
+    format: Format,
 }
 
 enum RTItemOption {
@@ -28,11 +89,22 @@ enum RemoveTypes {
     Item(RTItemOption)
 }
 
+// How a destructive command goes ahead: after asking, right away (`--yes`), or not at
+// all, only showing what it would delete (`--dry-run`)
+#[derive(Clone, Copy, PartialEq)]
+enum Confirm {
+    Ask,
+    Yes,
+    DryRun,
+}
+
 impl Deposito {
+    #[allow(dead_code)]
     pub fn new(default_warehouse: String) -> Self {
         Self {
             db_pool: Default::default(),
             default_warehouse: Some(default_warehouse),
+            format: Default::default(),
         }
     }
 
@@ -41,143 +113,120 @@ impl Deposito {
             return Err(DepositoError::NoDepositoDir(current_directory.into()));
         }
 
-        match sqlx::SqlitePool::connect(DATABASE_URL).await {
-            Ok(pool) => { self.db_pool = Some(pool); Ok(()) }
+        let pool = match sqlx::SqlitePool::connect(DATABASE_URL).await {
+            Ok(pool) => { pool }
             Err(err) => { return Err(DepositoError::Sqlx(err)); }
+        };
+
+        match Deposito::upgrade(&pool).await {
+            Ok(_) => { self.db_pool = Some(pool); Ok(()) }
+            Err(err) => { Err(DepositoError::Sqlx(err)) }
+        }
+    }
+
+    // Adds columns and tables that were added to the schema later to workspaces created
+    // before that, and records the schema version
+    async fn upgrade(pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
+        let added = [("items", "unit", "TEXT"), ("items", "tags", "TEXT"), ("warehouses", "archived_at", "INTEGER"), ("items", "archived_at", "INTEGER")];
+        for (table, column, definition) in added {
+            let columns: Vec<String> = sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}');", table))
+                .fetch_all(pool)
+                .await?;
+
+            if !columns.iter().any(|name| name == column) {
+                sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, definition))
+                    .execute(pool)
+                    .await?;
+            }
+        }
+
+        // Item names used to be unique across all warehouses, rather than within each
+        let global_names: i64 = sqlx::query_scalar(r#"
+            SELECT COUNT(*) FROM pragma_index_list('items') list
+            WHERE list."unique" AND (SELECT group_concat(name) FROM pragma_index_info(list.name)) = 'name';"#)
+            .fetch_one(pool)
+            .await?;
+        if global_names > 0 {
+            Deposito::rebuild(pool, "items").await?;
+        }
+
+        // Rules used to be thresholds only, required, without an expression or a severity
+        let rule_columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info('rules');").fetch_all(pool).await?;
+        if !rule_columns.iter().any(|name| name == "expression") {
+            Deposito::rebuild(pool, "rules").await?;
+        }
+
+        // Tables added since are created by the schema itself, it only creates missing ones
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version;").fetch_one(pool).await?;
+        if version < SCHEMA_VERSION {
+            sqlx::raw_sql(SCHEMA).execute(pool).await?;
+            sqlx::query(&format!("PRAGMA user_version = {};", SCHEMA_VERSION)).execute(pool).await?;
+        }
+        Ok(())
+    }
+
+    // Recreates a table by the schema, for columns and constraints SQLite can't alter,
+    // keeping the rows and their ids. Columns the old table lacks get their defaults.
+    // Foreign keys are off meanwhile so that dropping the old table doesn't take the rows
+    // referring to it along, and the legacy renaming keeps those rows referring to the
+    // table's name rather than following the old table.
+    async fn rebuild(pool: &sqlx::SqlitePool, table: &str) -> Result<(), sqlx::Error> {
+        let old_columns: Vec<String> = sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}');", table))
+            .fetch_all(pool)
+            .await?;
+        let columns = backup::TABLES.iter().find(|(name, _)| *name == table).map(|(_, columns)| *columns).unwrap_or_default()
+            .split(", ")
+            .filter(|column| old_columns.iter().any(|old_column| old_column == column))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut connection = pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF; PRAGMA legacy_alter_table = ON;").execute(&mut *connection).await?;
+
+        let rebuild_result = Deposito::in_savepoint(&mut connection, "rebuild", async |connection| {
+            sqlx::query(&format!("ALTER TABLE {0} RENAME TO {0}_old;", table)).execute(&mut *connection).await?;
+            sqlx::raw_sql(SCHEMA).execute(&mut *connection).await?;
+            sqlx::query(&format!("INSERT INTO {0} ({1}) SELECT {1} FROM {0}_old; DROP TABLE {0}_old;", table, columns))
+                .execute(&mut *connection)
+                .await?;
+            Ok(())
+        }).await;
+        sqlx::query("PRAGMA legacy_alter_table = OFF; PRAGMA foreign_keys = ON;").execute(&mut *connection).await?;
+        rebuild_result
+    }
+
+    // Runs `f` in a savepoint, keeping all of its changes or none of them. Savepoints rather
+    // than `pool.begin()`, since unlike transactions they also nest in the one `batch
+    // --atomic` keeps open on the connection.
+    async fn in_savepoint<T>(
+        connection: &mut sqlx::SqliteConnection,
+        name: &str,
+        f: impl AsyncFnOnce(&mut sqlx::SqliteConnection) -> Result<T, sqlx::Error>,
+    ) -> Result<T, sqlx::Error> {
+        sqlx::query(&format!("SAVEPOINT {};", name)).execute(&mut *connection).await?;
+
+        match f(&mut *connection).await {
+            Ok(value) => {
+                sqlx::query(&format!("RELEASE {};", name)).execute(&mut *connection).await?;
+                Ok(value)
+            }
+            Err(err) => {
+                sqlx::query(&format!("ROLLBACK TO {0}; RELEASE {0};", name)).execute(&mut *connection).await?;
+                Err(err)
+            }
         }
     }
 
     pub fn get_pool(&mut self) -> Option<&sqlx::SqlitePool> {
         if self.db_pool.is_none() {
-            println!("Unable to access the database. No connection pool.");
+            report_error!("Unable to access the database. No connection pool.");
             return None;
         }
-        return self.db_pool.as_ref()
-    }
-
-    pub fn help(command: Option<&str>) {
-        if let Some(command) = command {
-            match command {
-                "edit" => {
-                    println!("\x1b[1m`edit`\x1b[0m edit an existing item in a warehouse.");
-                    println!();
-                    println!("\x1b[1mUsage:\x1b[0m");
-                    println!("\tdeposito edit [options] <warehouse> <item>");
-                    println!();
-                    println!("\x1b[1mExample:\x1b[0m");
-                    println!("\tdeposito edit --name=Egg --q=6 --description=\"Just a regular egg\" Fridge Eg");
-                    println!();
-                    println!("\x1b[1mOptions:\x1b[0m");
-                    println!("\t-n, --name=<name>         New name");
-                    println!("\t-d, --description=<desc>  New description");
-                    println!("\t-q, --quantity=<quan>     New quantity");
-                }
-                "add" => {
-                    println!("\x1b[1m`add`\x1b[0m add a new item to a warehouse.");
-                    println!();
-                    println!("\x1b[1mUsage:\x1b[0m");
-                    println!("\tdeposito add <warehouse> <item> [<quantity> | 1]");
-                    println!();
-                    println!("\x1b[1mExample:\x1b[0m");
-                    println!("\tdeposito add Fridge Egg 8");
-                }
-                "list" => {
-                    println!("\x1b[1m`list`\x1b[0m list items in the specified warehouse.");
-                    println!();
-                    println!("\x1b[1mUsage:\x1b[0m");
-                    println!("\tdeposito list <warehouse>");
-                    println!();
-                    println!("\x1b[1mExample:\x1b[0m");
-                    println!("\tdeposito list Fridge");
-                }
-                "view" => {
-                    println!("\x1b[1m`view`\x1b[0m list available warehouses.");
-                    println!();
-                    println!("\x1b[1mUsage:\x1b[0m");
-                    println!("\tdeposito view");
-                    println!();
-                    println!("\x1b[1mExample:\x1b[0m");
-                    println!("\tdeposito view");
-                }
-                "create" => {
-                    println!("\x1b[1m`create`\x1b[0m create a new warehouse.");
-                    println!();
-                    println!("\x1b[1mUsage:\x1b[0m");
-                    println!("\tdeposito create <warehouse>");
-                    println!();
-                    println!("\x1b[1mExample:\x1b[0m");
-                    println!("\tdeposito create Fridge");
-                }
-                "rules" => {
-                    println!("\x1b[1m`rules`\x1b[0m manage rules in a warehouse.");
-                    println!();
-                    println!("\x1b[1mUsage:\x1b[0m");
-                    println!("\tdeposito rules -l <warehouse>");
-                    println!("\tdeposito rules -c <warehouse> <item> <quantity>");
-                    println!("\tdeposito rules -e <warehouse> <items> <quantity>");
-                    println!("\tdeposito rules -r <warehouse> <items>");
-                    println!();
-                    println!("\x1b[1mExample:\x1b[0m");
-                    println!("\tdeposito rules -l Fridge");
-                    println!("\tdeposito rules -c Fridge Egg 1");
-                    println!("\tdeposito rules -e Fridge Egg Milk Dough 6");
-                    println!("\tdeposito rules -r Fridge Milk Dough");
-                    println!();
-                    println!("\x1b[1mOptions:\x1b[0m");
-                    println!("\t-l, --list    Show rules");
-                    println!("\t-c, --create  Create a new rule");
-                    println!("\t-e, --edit    Edit an existing rule");
-                    println!("\t-r, --remove  Remove a rule");
-                }
-                "raport" => {
-                    println!("\x1b[1m`raport`\x1b[0m create a raport.");
-                    println!();
-                    println!("\x1b[1mUsage:\x1b[0m");
-                    println!("\tdeposito raport [options] <warehouse>");
-                    println!();
-                    println!("\x1b[1mExample:\x1b[0m");
-                    println!("\tdeposito raport Fridge");
-                    println!("\tdeposito raport -a");
-                    println!();
-                    println!("\x1b[1mOptions:\x1b[0m");
-                    println!("\t-a, --all  Raport all warehouses");
-                }
-                "remove" => {
-                    println!("\x1b[1m`remove`\x1b[0m remove a warehouse or an item.");
-                    println!();
-                    println!("\x1b[1mUsage:\x1b[0m");
-                    println!("\tdeposito remove [options] <to_remove...>");
-                    println!();
-                    println!("\x1b[1mExample:\x1b[0m");
-                    println!("\tdeposito remove -w Fridge");
-                    println!("\tdeposito remove -i Fridge Egg");
-                    println!("\tdeposito remove -ia Fridge");
-                    println!();
-                    println!("\x1b[1mOptions:\x1b[0m");
-                    println!("\t-w, --warehouse  Remove a warehouse");
-                    println!("\t-i, --item       Remove an item");
-                    println!("\t-a, --all        Remove all (items only)");
-                }
-                _ => { println!("No help available for command `\x1b[1medit\x1b[1m`."); }
-            }
-        } else {
-            println!("\x1b[1mdeposito - warehouse inventory management tool.\x1b[0m");
-            println!();
-            println!("\x1b[1mUsage:\x1b[0m");
-            println!("\tdeposito [command] [options] [<args>...]");
-            println!();
-            println!("\x1b[1mCommands:\x1b[0m");
-            println!("\tFor more information about a command run the command with `\x1b[1m-h\x1b[0m`");
-            println!();
-            println!("\tcreate - create a new warehouse");
-            println!("\tview - list available warehouses");
-            println!("\tlist - list items in the specified warehouse");
-            println!("\tadd - add a new item to a warehouse");
-            println!("\tedit - edit an existing item in a warehouse");
-            println!("\tremove - remove a warehouse or an item");
-            println!("\trules - manage rules in a warehouse");
-            println!("\traport - create a raport");
-        }
+        self.db_pool.as_ref()
+    }
+
+    pub fn help(command: Option<&CommandSpec>) {
+        cli::print_help(command);
     }
 
     pub fn does_dir_exist(current_directory: &Path) -> bool {
@@ -205,89 +254,482 @@ impl Deposito {
         deposito_exists
     }
 
-    pub async fn view(&mut self) {
+    pub async fn init(current_directory: &Path) {
+        if Deposito::does_dir_exist(current_directory) {
+            println!("Deposito directory already exists in `{}`.", style::bold(current_directory.display()));
+            return;
+        }
+
+        if let Err(err) = std::fs::create_dir(current_directory.join(DIR_NAME)) {
+            report_error!("Unable to create the deposito directory. Reason: {}", err);
+            return;
+        }
+
+        let options = match DATABASE_URL.parse::<sqlx::sqlite::SqliteConnectOptions>() {
+            Ok(options) => { options.create_if_missing(true) }
+            Err(err) => { report_error!("Unable to create the database. Reason: {}", err); return; }
+        };
+
+        let pool = match sqlx::SqlitePool::connect_with(options).await {
+            Ok(pool) => { pool }
+            Err(err) => { report_error!("Unable to create the database. Reason: {}", err); return; }
+        };
+
+        let schema_result = match sqlx::raw_sql(SCHEMA).execute(&pool).await {
+            Ok(_) => { Deposito::upgrade(&pool).await }
+            Err(err) => { Err(err) }
+        };
+
+        match schema_result {
+            Ok(_) => { println!("Deposito directory successfully created in `{}`.", style::bold(current_directory.display())); }
+            Err(err) => { report_error!("Unable to create the database tables. Reason: {}", err); }
+        }
+    }
+
+    // Prints candidates for shell completion, one per line (see `complete.rs`). Stays
+    // quiet on errors, anything printed would end up offered as a completion.
+    pub async fn complete(&mut self, args: &[String]) {
+        let request = match complete::request(args) {
+            Some(request) => { request }
+            None => { return; }
+        };
+
+        let candidates = match (request.completion, self.db_pool.as_ref()) {
+            (Completion::Words(words), _) => { words }
+            (Completion::Warehouses, Some(pool)) => {
+                sqlx::query_scalar!(r#"SELECT name FROM warehouses ORDER BY name;"#)
+                    .fetch_all(pool)
+                    .await
+                    .unwrap_or_default()
+            }
+            (Completion::Items(warehouse_name), Some(pool)) => {
+                sqlx::query_scalar!(r#"SELECT items.name FROM items JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = ? ORDER BY items.name;"#, warehouse_name)
+                    .fetch_all(pool)
+                    .await
+                    .unwrap_or_default()
+            }
+            (_, None) => { return; }
+        };
+
+        for candidate in candidates.iter().filter(|candidate| candidate.starts_with(&request.current)) {
+            println!("{}", candidate);
+        }
+    }
+
+    pub async fn view(&mut self, include_archived: bool) {
+        let format = self.format;
         let pool = match self.get_pool() {
             Some(pool) => { pool }
             None => { return; }
         };
 
-        let warehouses_result = sqlx::query!(r#"SELECT * FROM warehouses;"#)
+        let warehouses_result = sqlx::query!(r#"SELECT * FROM warehouses WHERE archived_at IS NULL OR $1;"#, include_archived)
             .fetch_all(pool)
             .await;
 
         let warehouses = match warehouses_result {
             Ok(warehouses) => { warehouses }
-            Err(err) => { println!("Unable to view warehouses. Reason: {}", err); return; }
+            Err(err) => { report_error!("Unable to view warehouses. Reason: {}", err); return; }
         };
 
+        if format != Format::Text {
+            let records: Vec<WarehouseRecord> = warehouses.into_iter()
+                .map(|warehouse| WarehouseRecord { name: warehouse.name, archived_at: warehouse.archived_at })
+                .collect();
+            output::print_records(format, &records);
+            return;
+        }
+
         if warehouses.is_empty() {
-            println!("No warehouses. You can create a warehouse using \x1b[1m`deposito create <name>`\x1b[0m");
+            println!("No warehouses. You can create a warehouse using {}", style::bold("`deposito create <name>`"));
             return;
         }
 
-        println!("\x1b[1mAvailable warehouses:\x1b[0m");
+        println!("{}", style::bold("Available warehouses:"));
+        let has_archived = warehouses.iter().any(|warehouse| warehouse.archived_at.is_some());
+
+        let mut columns = vec![Column::new("Warehouse").truncate()];
+        if has_archived { columns.push(Column::new("Archived")); }
+
+        let mut table = Table::new(columns);
         for warehouse in warehouses {
-            println!("\t- {}", warehouse.name);
+            let mut cells = vec![Cell::new(warehouse.name)];
+            if has_archived { cells.push(Cell::new(warehouse.archived_at.map(raport::format_timestamp).unwrap_or_default())); }
+            table.row(cells);
         }
+        table.print();
     }
 
-    pub async fn create(&mut self, name: &str) {
+    pub async fn create(&mut self, name: &str, stock: Stock, zero: bool) {
         let pool = match self.get_pool() {
             Some(pool) => { pool }
             None => { return; }
         };
 
-        let create_result = sqlx::query!(r#"INSERT INTO warehouses (id, name) VALUES (NULL, ?)"#, name)
-            .execute(pool)
-            .await;
+        let stock = match stock {
+            Stock::Warehouse(warehouse_name) if !Deposito::check_warehouse(pool, &warehouse_name).await => { return; }
+            Stock::Template(template_name) if !Deposito::check_template(pool, &template_name).await => { return; }
+            Stock::Default => {
+                match sqlx::query_scalar!("SELECT COUNT(*) FROM templates WHERE name = $1;", DEFAULT_TEMPLATE).fetch_one(pool).await {
+                    Ok(0) => { Stock::Empty }
+                    Ok(_) => { Stock::Template(String::from(DEFAULT_TEMPLATE)) }
+                    Err(err) => { report_error!("Unable to look up templates. Reason: {}", err); return; }
+                }
+            }
+            stock => { stock }
+        };
 
-        match create_result {
-            Ok(_) => { println!("Warehouse with the name `\x1b[1m{}\x1b[0m` successfully created!", name); }
+        match Deposito::create_warehouse(pool, name, &stock, zero).await {
+            Ok((items, rules)) => {
+                println!("Warehouse with the name `{}` successfully created!", style::bold(name));
+                match &stock {
+                    Stock::Warehouse(warehouse_name) => { println!("Copied {} item(s) and {} rule(s) from the `{}` warehouse.", items, rules, style::bold(warehouse_name)); }
+                    Stock::Template(template_name) => { println!("Copied {} item(s) and {} rule(s) from the `{}` template.", items, rules, style::bold(template_name)); }
+                    Stock::Empty | Stock::Default => { }
+                }
+            }
             Err(err) => {
                 // Warehouse already exists
                 if let Some(err) = err.as_database_error() {
                 if let Some(code) = err.code() {
                 if code == "2067" {
-                    println!("Warehouse with the name `\x1b[1m{}\x1b[0m` already exists.", name);
+                    report_error!("Warehouse with the name `{}` already exists.", style::bold(name));
                     return;
                 }}}
 
-                println!("Unable to create a new warehouse with the name `\x1b[1m{}\x1b[0m`. Reason: {}", name, err);
+                report_error!("Unable to create a new warehouse with the name `{}`. Reason: {}", style::bold(name), err);
+            }
+        }
+    }
+
+    // Creates the warehouse with the items and rules of the stock, all of it or nothing,
+    // returning how many items and rules it got. Archived items of a warehouse aren't
+    // copied, and starting quantities are recorded as movements like those of `add`.
+    async fn create_warehouse(pool: &sqlx::SqlitePool, name: &str, stock: &Stock, zero: bool) -> Result<(u64, u64), sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        Deposito::in_savepoint(&mut connection, "create_warehouse", async |connection| {
+            sqlx::query!("INSERT INTO warehouses (id, name) VALUES (NULL, $1);", name)
+                .execute(&mut *connection)
+                .await?;
+
+            let copied = match stock {
+                Stock::Warehouse(warehouse_name) => {
+                    let items = sqlx::query!("
+                        INSERT INTO items (id, warehouse_id, name, description, quantity, unit, tags)
+                        SELECT NULL, (SELECT id FROM warehouses WHERE name = $1), i.name, i.description, CASE WHEN $3 THEN 0 ELSE i.quantity END, i.unit, i.tags
+                        FROM items i JOIN warehouses w ON w.id = i.warehouse_id
+                        WHERE w.name = $2 AND i.archived_at IS NULL;",
+                        name, warehouse_name, zero)
+                        .execute(&mut *connection)
+                        .await?;
+
+                    let rules = sqlx::query!("
+                        INSERT INTO rules (id, item_id, gets_below_quantity, expression, severity)
+                        SELECT NULL, copy.id, r.gets_below_quantity, r.expression, r.severity
+                        FROM
+                            rules r
+                            JOIN items i ON i.id = r.item_id
+                            JOIN warehouses w ON w.id = i.warehouse_id
+                            JOIN items copy ON copy.name = i.name AND copy.warehouse_id = (SELECT id FROM warehouses WHERE name = $1)
+                        WHERE w.name = $2 AND i.archived_at IS NULL;",
+                        name, warehouse_name)
+                        .execute(&mut *connection)
+                        .await?;
+
+                    (items.rows_affected(), rules.rows_affected())
+                }
+                Stock::Template(template_name) => {
+                    let items = sqlx::query!("
+                        INSERT INTO items (id, warehouse_id, name, description, quantity, unit, tags)
+                        SELECT NULL, (SELECT id FROM warehouses WHERE name = $1), ti.name, ti.description, CASE WHEN $3 THEN 0 ELSE ti.quantity END, ti.unit, ti.tags
+                        FROM template_items ti JOIN templates t ON t.id = ti.template_id
+                        WHERE t.name = $2;",
+                        name, template_name, zero)
+                        .execute(&mut *connection)
+                        .await?;
+
+                    let rules = sqlx::query!("
+                        INSERT INTO rules (id, item_id, gets_below_quantity, expression, severity)
+                        SELECT NULL, copy.id, ti.gets_below_quantity, ti.expression, ti.severity
+                        FROM
+                            template_items ti
+                            JOIN templates t ON t.id = ti.template_id
+                            JOIN items copy ON copy.name = ti.name AND copy.warehouse_id = (SELECT id FROM warehouses WHERE name = $1)
+                        WHERE t.name = $2 AND ti.severity IS NOT NULL;",
+                        name, template_name)
+                        .execute(&mut *connection)
+                        .await?;
+
+                    (items.rows_affected(), rules.rows_affected())
+                }
+                Stock::Empty | Stock::Default => { (0, 0) }
+            };
+
+            sqlx::query!("
+                INSERT INTO movements (id, item_id, delta)
+                SELECT NULL, items.id, items.quantity FROM items JOIN warehouses ON warehouses.id = items.warehouse_id
+                WHERE warehouses.name = $1 AND items.quantity != 0;",
+                name)
+                .execute(&mut *connection)
+                .await?;
+
+            Ok(copied)
+        }).await
+    }
+
+    pub async fn rename(&mut self, old_name: &str, new_name: &str) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        if !Deposito::check_warehouse(pool, old_name).await {
+            return;
+        }
+
+        let rename_result = sqlx::query!("UPDATE warehouses SET name = $1 WHERE name = $2;", new_name, old_name)
+            .execute(pool)
+            .await;
+
+        match rename_result {
+            Ok(_) => { println!("Renamed the `{}` warehouse to `{}`.", style::bold(old_name), style::bold(new_name)); }
+            Err(err) => {
+                if err.as_database_error().and_then(|err| err.code()).is_some_and(|code| code == "2067") {
+                    report_error!("Warehouse with the name `{}` already exists.", style::bold(new_name));
+                } else {
+                    report_error!("Unable to rename the `{}` warehouse. Reason: {}", style::bold(old_name), err);
+                }
+                return;
+            }
+        }
+
+        // `deposito shell` keeps using it under the new name
+        if self.default_warehouse.as_deref() == Some(old_name) {
+            self.default_warehouse = Some(new_name.to_string());
+        }
+    }
+
+    // Moves all items of the source warehouse into the target and removes the source. Items
+    // of the same name are combined, see `merge::plan`.
+    pub async fn merge(&mut self, source_name: &str, target_name: &str, conflict: export::Conflict) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        if !(Deposito::check_warehouse(pool, source_name).await & Deposito::check_warehouse(pool, target_name).await) {
+            return;
+        }
+
+        if source_name == target_name {
+            report_error!("Can't merge the `{}` warehouse into itself.", style::bold(source_name));
+            return;
+        }
+
+        let items_result = async {
+            Ok::<_, sqlx::Error>((Deposito::merge_items(pool, source_name).await?, Deposito::merge_items(pool, target_name).await?))
+        }.await;
+
+        let (source, target) = match items_result {
+            Ok(items) => { items }
+            Err(err) => { report_error!("Unable to list the items to merge. Reason: {}", err); return; }
+        };
+
+        let steps = match merge::plan(&source, &target, conflict) {
+            Ok(steps) => { steps }
+            Err(errors) => {
+                for err in errors {
+                    report_error!("{}", err);
+                }
+                println!("Nothing was merged, use `--on-conflict=skip` to keep the rules of `{}` or `--on-conflict=overwrite` to take those of `{}`.", style::bold(target_name), style::bold(source_name));
+                return;
             }
+        };
+
+        if let Err(err) = Deposito::apply_merge(pool, source_name, target_name, &steps).await {
+            report_error!("Unable to merge the `{}` warehouse into `{}`, nothing was merged. Reason: {}", style::bold(source_name), style::bold(target_name), err);
+            return;
+        }
+
+        let mut table = Table::new(vec![Column::new("Item").truncate(), Column::new("Quantity").right(), Column::new("Rule").truncate()]);
+        let mut moved = 0;
+        for step in steps.iter() {
+            let (name, quantity, rule) = match step {
+                merge::Step::Move(item) => {
+                    moved += 1;
+                    (&item.name, item.quantity.to_string(), &item.rule)
+                }
+                merge::Step::Combine { source, target, take_rule } => {
+                    let quantity = format!("{} + {} = {}", target.quantity, source.quantity, target.quantity + source.quantity);
+                    (&target.name, quantity, if *take_rule { &source.rule } else { &target.rule })
+                }
+            };
+            table.row(vec![Cell::new(name), Cell::new(quantity), Cell::new(rule.as_ref().map(snapshot::Rule::describe).unwrap_or_default())]);
         }
+
+        println!("Merged the `{}` warehouse into `{}`: {} item(s) moved, {} combined.", style::bold(source_name), style::bold(target_name), moved, steps.len() - moved);
+        if !steps.is_empty() {
+            table.print();
+        }
+
+        if self.default_warehouse.as_deref() == Some(source_name) {
+            self.default_warehouse = Some(target_name.to_string());
+        }
+    }
+
+    async fn merge_items(pool: &sqlx::SqlitePool, warehouse_name: &str) -> Result<Vec<merge::Item>, sqlx::Error> {
+        let items = sqlx::query!(r#"
+            SELECT
+                i.id,
+                i.name,
+                i.quantity,
+                r.gets_below_quantity,
+                r.expression,
+                r.severity AS "severity?"
+            FROM
+                items i
+                JOIN warehouses w ON w.id = i.warehouse_id
+                LEFT JOIN rules r ON r.item_id = i.id
+            WHERE
+                w.name = $1
+            ORDER BY
+                i.name;
+        "#, warehouse_name)
+            .fetch_all(pool)
+            .await?;
+
+        Ok(items.into_iter().map(|item| {
+            let rule = item.severity.map(|severity| snapshot::Rule { gets_below_quantity: item.gets_below_quantity, expression: item.expression, severity });
+            merge::Item { id: item.id, name: item.name, quantity: item.quantity, rule }
+        }).collect())
+    }
+
+    // Applies all of the merge or none of it. Combined
+    // items keep the id of the target's, which gets the movements of both, and a rule taken
+    // from the source is written over the target's one rather than moved, so that undoing
+    // puts each rule back where it was.
+    async fn apply_merge(pool: &sqlx::SqlitePool, source_name: &str, target_name: &str, steps: &[merge::Step<'_>]) -> Result<(), sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        Deposito::in_savepoint(&mut connection, "merge", async |connection| {
+            for step in steps.iter() {
+                let (source, target, take_rule) = match step {
+                    merge::Step::Move(_) => { continue; }
+                    merge::Step::Combine { source, target, take_rule } => { (source.id, target.id, *take_rule) }
+                };
+
+                // What the target's item lacks is taken from the source's
+                sqlx::query!("
+                    UPDATE items SET
+                        quantity = items.quantity + source.quantity,
+                        description = COALESCE(items.description, source.description),
+                        unit = COALESCE(items.unit, source.unit),
+                        tags = COALESCE(items.tags, source.tags)
+                    FROM items AS source
+                    WHERE items.id = $1 AND source.id = $2;",
+                    target, source)
+                    .execute(&mut *connection)
+                    .await?;
+
+                sqlx::query!("UPDATE movements SET item_id = $1 WHERE item_id = $2;", target, source)
+                    .execute(&mut *connection)
+                    .await?;
+
+                if take_rule {
+                    sqlx::query!("
+                        INSERT INTO rules (id, item_id, gets_below_quantity, expression, severity)
+                        SELECT NULL, $1, gets_below_quantity, expression, severity FROM rules WHERE item_id = $2
+                        ON CONFLICT (item_id) DO UPDATE SET
+                            gets_below_quantity = excluded.gets_below_quantity, expression = excluded.expression, severity = excluded.severity;",
+                        target, source)
+                        .execute(&mut *connection)
+                        .await?;
+                }
+
+                sqlx::query!("DELETE FROM rules WHERE item_id = $1;", source)
+                    .execute(&mut *connection)
+                    .await?;
+                sqlx::query!("DELETE FROM items WHERE id = $1;", source)
+                    .execute(&mut *connection)
+                    .await?;
+            }
+
+            // The rest moves as it is
+            sqlx::query!("
+                UPDATE items SET warehouse_id = (SELECT id FROM warehouses WHERE name = $1)
+                WHERE warehouse_id = (SELECT id FROM warehouses WHERE name = $2);",
+                target_name, source_name)
+                .execute(&mut *connection)
+                .await?;
+            sqlx::query!("DELETE FROM warehouses WHERE name = $1;", source_name)
+                .execute(&mut *connection)
+                .await?;
+
+            Ok(())
+        }).await
     }
 
-    pub async fn list(&mut self, warehouse_name: &str) {
+    pub async fn list(&mut self, warehouse_name: &str, include_archived: bool) {
+        let format = self.format;
         let pool = match self.get_pool() {
             Some(pool) => { pool }
             None => { return; }
         };
 
-        let items_result = sqlx::query!(r#"SELECT items.* FROM items JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = ?;"#, warehouse_name)
+        if !Deposito::check_warehouse(pool, warehouse_name).await
+            || !(include_archived || Deposito::check_unarchived(pool, warehouse_name).await) {
+            return;
+        }
+
+        let items_result = sqlx::query!(r#"
+            SELECT items.* FROM items JOIN warehouses ON items.warehouse_id = warehouses.id
+            WHERE warehouses.name = $1 AND (items.archived_at IS NULL OR $2);"#,
+            warehouse_name, include_archived)
             .fetch_all(pool)
             .await;
 
         let items = match items_result {
             Ok(items) => { items }
             Err(err) => {
-                println!("Unable to list items in the `\x1b[1m{}\x1b[0m` warehouse. Reason: {}", warehouse_name, err);
+                report_error!("Unable to list items in the `{}` warehouse. Reason: {}", style::bold(warehouse_name), err);
                 return;
             }
         };
 
+        if format != Format::Text {
+            let records: Vec<ItemRecord> = items.into_iter()
+                .map(|item| ItemRecord { name: item.name, quantity: item.quantity, description: item.description, unit: item.unit, tags: item.tags, archived_at: item.archived_at })
+                .collect();
+            output::print_records(format, &records);
+            return;
+        }
+
         if items.is_empty() {
-            println!("Warehouse `\x1b[1m{}\x1b[0m` contains no items.", warehouse_name);
+            println!("Warehouse `{}` contains no items.", style::bold(warehouse_name));
             return;
         }
 
-        println!("\x1b[1mAvailable items in the `{}` warehouse:\x1b[0m", warehouse_name);
+        println!("{}", style::bold(format!("Available items in the `{}` warehouse:", warehouse_name)));
+        // Units, tags and archiving only get a column when some item has them
+        let has_units = items.iter().any(|item| item.unit.is_some());
+        let has_tags = items.iter().any(|item| item.tags.is_some());
+        let has_archived = items.iter().any(|item| item.archived_at.is_some());
+
+        let mut columns = vec![Column::new("Item"), Column::new("Quantity").right()];
+        if has_units { columns.push(Column::new("Unit")); }
+        columns.push(Column::new("Description").truncate());
+        if has_tags { columns.push(Column::new("Tags").truncate()); }
+        if has_archived { columns.push(Column::new("Archived")); }
+
+        let mut table = Table::new(columns);
         for item in items {
-            print!("\t- {} ({})", item.name, item.quantity);
-            if let Some(description) = item.description {
-                print!(" {}", description);
-            }
-            print!("\n")
+            let mut cells = vec![Cell::new(item.name), Cell::new(item.quantity)];
+            if has_units { cells.push(Cell::new(item.unit.unwrap_or_default())); }
+            cells.push(Cell::new(item.description.unwrap_or_default()));
+            if has_tags { cells.push(Cell::new(item.tags.unwrap_or_default())); }
+            if has_archived { cells.push(Cell::new(item.archived_at.map(raport::format_timestamp).unwrap_or_default())); }
+            table.row(cells);
         }
+        table.print();
     }
 
     pub async fn edit(&mut self, warehouse_name: &str, item_name: &str, edit: ItemEdit) {
@@ -296,25 +738,49 @@ impl Deposito {
             None => { return; }
         };
 
-        let edit_result = sqlx::query!("
-            UPDATE items SET
-                name = COALESCE(?, name),
-                description = COALESCE(?, description),
-                quantity = COALESCE(?, quantity)
-            WHERE
-                items.warehouse_id = (SELECT warehouses.id FROM warehouses WHERE warehouses.name = ?)
-                AND items.name = ?;",
-            edit.name, edit.description, edit.quantity, warehouse_name, item_name)
-            .execute(pool)
-            .await;
+        if !Deposito::check_warehouse(pool, warehouse_name).await
+            || !Deposito::check_items(pool, warehouse_name, &[item_name.to_string()]).await {
+            return;
+        }
 
-        match edit_result {
-            Ok(_) => { println!("Item with the name `\x1b[1m{}\x1b[0m` in the `\x1b[1m{}\x1b[0m` warehouse successfully edited.", item_name, warehouse_name); }
-            Err(err) => { println!("Unable to edit item with the name `\x1b[1m{}\x1b[0m` in the `\x1b[1m{}\x1b[0m` warehouse. Reason: {}", item_name, warehouse_name, err); }
+        match Deposito::update_item(pool, warehouse_name, item_name, &edit).await {
+            Ok(_) => { println!("Item with the name `{}` in the `{}` warehouse successfully edited.", style::bold(item_name), style::bold(warehouse_name)); }
+            Err(err) => { report_error!("Unable to edit item with the name `{}` in the `{}` warehouse. Reason: {}", style::bold(item_name), style::bold(warehouse_name), err); }
         }
 
         println!();
-        self.raport_warehouse(warehouse_name).await;
+        self.raport_warehouse(warehouse_name, None, None, None, true).await;
+    }
+
+    // Applies the edit to the item. Quantity changes are recorded as movements, so both
+    // happen together.
+    async fn update_item(pool: &sqlx::SqlitePool, warehouse_name: &str, item_name: &str, edit: &ItemEdit) -> Result<(), sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        Deposito::in_savepoint(&mut connection, "update_item", async |connection| {
+            if let Some(quantity) = edit.quantity {
+                sqlx::query!("
+                    INSERT INTO movements (id, item_id, delta)
+                    SELECT NULL, items.id, $1 - items.quantity FROM items JOIN warehouses ON warehouses.id = items.warehouse_id
+                    WHERE warehouses.name = $2 AND items.name = $3 AND items.quantity != $1;",
+                    quantity, warehouse_name, item_name)
+                    .execute(&mut *connection)
+                    .await?;
+            }
+
+            sqlx::query!("
+                UPDATE items SET
+                    name = COALESCE(?, name),
+                    description = COALESCE(?, description),
+                    quantity = COALESCE(?, quantity)
+                WHERE
+                    items.warehouse_id = (SELECT warehouses.id FROM warehouses WHERE warehouses.name = ?)
+                    AND items.name = ?;",
+                edit.name, edit.description, edit.quantity, warehouse_name, item_name)
+                .execute(&mut *connection)
+                .await?;
+
+            Ok(())
+        }).await
     }
 
     pub async fn add(&mut self, warehouse_name: &str, item_name: &str, quantity: i64) {
@@ -323,6 +789,10 @@ impl Deposito {
             None => { return; }
         };
 
+        if !Deposito::check_warehouse(pool, warehouse_name).await {
+            return;
+        }
+
         let add_result = sqlx::query!(
             r#"INSERT INTO items (id, warehouse_id, name, quantity) VALUES (NULL, (SELECT warehouses.id FROM warehouses WHERE warehouses.name = ?), ?, ?);"#,
             warehouse_name, item_name, quantity)
@@ -331,7 +801,8 @@ impl Deposito {
 
         match add_result {
             Ok(_) => {
-                println!("New item `\x1b[1m{}\x1b[0m` successfully added in quantity of `\x1b[1m{}\x1b[0m` into the `\x1b[1m{}\x1b[0m` warehouse.", item_name, quantity, warehouse_name);
+                println!("New item `{}` successfully added in quantity of `{}` into the `{}` warehouse.", style::bold(item_name), style::bold(quantity), style::bold(warehouse_name));
+                Deposito::record_movement(pool, warehouse_name, item_name, quantity).await;
             }
             Err(err) => {
                 // Item already exists
@@ -351,54 +822,176 @@ impl Deposito {
                         .await;
 
                     match add_result {
-                        Ok(_) => { println!("Successfully added `\x1b[1m{}\x1b[0m` quantity to `\x1b[1m{}\x1b[0m` in the `\x1b[1m{}\x1b[0m` warehouse.", quantity, item_name, warehouse_name) }
-                        Err(err) => { println!("Unable to add `\x1b[1m{}\x1b[0m` quantity to `\x1b[1m{}\x1b[0m` in the `\x1b[1m{}\x1b[0m` warehouse. Reason: {}", quantity, item_name, warehouse_name, err) }
+                        Ok(result) => {
+                            println!("Successfully added `{}` quantity to `{}` in the `{}` warehouse.", style::bold(quantity), style::bold(item_name), style::bold(warehouse_name));
+                            if result.rows_affected() > 0 {
+                                Deposito::record_movement(pool, warehouse_name, item_name, quantity).await;
+                            }
+                        }
+                        Err(err) => { report_error!("Unable to add `{}` quantity to `{}` in the `{}` warehouse. Reason: {}", style::bold(quantity), style::bold(item_name), style::bold(warehouse_name), err) }
                     }
 
                     return;
                 }}}
 
-                println!("Unable to add new item `\x1b[1m{}\x1b[0m` into the `\x1b[1m{}\x1b[0m` warehouse. Reason: {}", item_name, warehouse_name, err);
+                report_error!("Unable to add new item `{}` into the `{}` warehouse. Reason: {}", style::bold(item_name), style::bold(warehouse_name), err);
             }
         }
     }
 
-    pub async fn remove(&mut self, remove_type: RemoveTypes, to_remove: Vec<String>) {
+    pub async fn remove(&mut self, remove_type: RemoveTypes, to_remove: Vec<String>, confirm: Confirm, purge: bool) {
         let pool = match self.get_pool() {
             Some(pool) => { pool }
             None => { return; }
         };
 
-        let mut query_string: String;
-        let mut query: sqlx::query::Query<'_, sqlx::Sqlite, _>;
-        match remove_type {
+        // Everything has to exist, otherwise it's likely a typo and nothing gets removed
+        let found = match remove_type {
             RemoveTypes::Warehouse => {
-                query_string = String::from("DELETE FROM items WHERE items.warehouse_id IN (SELECT warehouses.id FROM warehouses WHERE warehouses.name IN (");
-                for index in 0..to_remove.len() {
-                    query_string.push_str(&format!("${}", index+1));
-                    if index != to_remove.len()-1 { query_string.push(','); }
+                let mut found = true;
+                for warehouse_name in to_remove.iter() {
+                    found &= Deposito::check_warehouse(pool, warehouse_name).await;
                 }
-                query_string.push_str("));");
-                query_string.push_str("DELETE FROM warehouses WHERE warehouses.name IN (");
-                for index in 0..to_remove.len() {
-                    query_string.push_str(&format!("${}", index+1));
-                    if index != to_remove.len()-1 { query_string.push(','); }
-                }
-                query_string.push_str(");");
+                found
+            }
+            RemoveTypes::Item(RTItemOption::List) => {
+                Deposito::check_warehouse(pool, &to_remove[0]).await
+                    && Deposito::check_items(pool, &to_remove[0], &to_remove[1..]).await
+            }
+            RemoveTypes::Item(RTItemOption::All) => { Deposito::check_warehouse(pool, &to_remove[0]).await }
+        };
+        if !found {
+            return;
+        }
+
+        let placeholders = |first: usize| (first..=to_remove.len()).map(|index| format!("${}", index)).collect::<Vec<_>>().join(",");
+
+        // Without `--purge` only what was archived before goes, items of an archived
+        // warehouse count as archived
+        let archived = "(items.archived_at IS NOT NULL OR items.warehouse_id IN (SELECT warehouses.id FROM warehouses WHERE warehouses.archived_at IS NOT NULL))";
+        let unarchived_string = match remove_type {
+            RemoveTypes::Warehouse => {
+                Some(format!("SELECT name FROM warehouses WHERE name IN ({}) AND archived_at IS NULL ORDER BY name;", placeholders(1)))
             }
             RemoveTypes::Item(RTItemOption::List) => {
-                query_string = String::from("DELETE FROM items WHERE items.warehouse_id IN (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $1) AND items.name IN (");
-                for index in 0..to_remove.len()-1 {
-                    query_string.push_str(&format!("${}", index+2));
-                    if index != to_remove.len()-2 { query_string.push(','); }
+                Some(format!("
+                    SELECT items.name FROM items JOIN warehouses ON warehouses.id = items.warehouse_id
+                    WHERE warehouses.name = $1 AND items.name IN ({}) AND NOT {}
+                    ORDER BY items.name;", placeholders(2), archived))
+            }
+            RemoveTypes::Item(RTItemOption::All) => { None }
+        };
+
+        if let (false, Some(unarchived_string)) = (purge, unarchived_string) {
+            let mut unarchived_query = sqlx::query_scalar::<_, String>(&unarchived_string);
+            for remove in to_remove.iter() {
+                unarchived_query = unarchived_query.bind(remove);
+            }
+
+            let unarchived = match unarchived_query.fetch_all(pool).await {
+                Ok(unarchived) => { unarchived }
+                Err(err) => { report_error!("Unable to look up what would be removed. Reason: {}", err); return; }
+            };
+
+            if !unarchived.is_empty() {
+                let names: Vec<String> = unarchived.iter().map(|name| format!("`{}`", style::bold(name))).collect();
+                match remove_type {
+                    RemoveTypes::Warehouse => {
+                        report_error!("Not removing the warehouse(s) {}, they aren't archived. Archive them first with `deposito archive -w`, or remove them anyway with `--purge`.", names.join(", "));
+                    }
+                    RemoveTypes::Item(_) => {
+                        report_error!("Not removing the item(s) {} from the `{}` warehouse, they aren't archived. Archive them first with `deposito archive -i`, or remove them anyway with `--purge`.", names.join(", "), style::bold(&to_remove[0]));
+                    }
                 }
-                query_string.push_str(");");
+                return;
+            }
+        }
+
+        let items_condition = match remove_type {
+            RemoveTypes::Warehouse => {
+                format!("items.warehouse_id IN (SELECT warehouses.id FROM warehouses WHERE warehouses.name IN ({}))", placeholders(1))
+            }
+            RemoveTypes::Item(RTItemOption::List) => {
+                format!("items.warehouse_id IN (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $1) AND items.name IN ({})", placeholders(2))
+            }
+            RemoveTypes::Item(RTItemOption::All) if purge => {
+                String::from("items.warehouse_id IN (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $1)")
             }
             RemoveTypes::Item(RTItemOption::All) => {
-                query_string = String::from("DELETE FROM items WHERE items.warehouse_id IN (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $1);");
+                format!("items.warehouse_id IN (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $1) AND {}", archived)
+            }
+        };
+
+        if confirm != Confirm::Yes {
+            let preview_string = format!("
+                SELECT
+                    w.name,
+                    items.name,
+                    items.quantity,
+                    (SELECT COUNT(*) FROM rules WHERE rules.item_id = items.id),
+                    (SELECT COUNT(*) FROM movements WHERE movements.item_id = items.id)
+                FROM items JOIN warehouses w ON w.id = items.warehouse_id
+                WHERE {}
+                ORDER BY w.name, items.name;", items_condition);
+            let mut preview_query = sqlx::query_as::<_, (String, String, i64, i64, i64)>(&preview_string);
+            for remove in to_remove.iter() {
+                preview_query = preview_query.bind(remove);
+            }
+
+            let items = match preview_query.fetch_all(pool).await {
+                Ok(items) => { items }
+                Err(err) => { report_error!("Unable to look up what would be removed. Reason: {}", err); return; }
+            };
+
+            let rules: i64 = items.iter().map(|(_, _, _, rules, _)| rules).sum();
+            let movements: i64 = items.iter().map(|(_, _, _, _, movements)| movements).sum();
+            let contents = format!("{} item(s), {} rule(s) and {} movement(s)", items.len(), rules, movements);
+            match remove_type {
+                RemoveTypes::Warehouse => {
+                    let names: Vec<String> = to_remove.iter().map(|name| format!("`{}`", style::bold(name))).collect();
+                    println!("This removes the warehouse(s) {} with {}.", names.join(", "), contents);
+                }
+                RemoveTypes::Item(_) if items.is_empty() && purge => {
+                    println!("The `{}` warehouse has no items, nothing to remove.", style::bold(&to_remove[0]));
+                    return;
+                }
+                RemoveTypes::Item(_) if items.is_empty() => {
+                    println!("The `{}` warehouse has no archived items, nothing to remove. `--purge` removes the others as well.", style::bold(&to_remove[0]));
+                    return;
+                }
+                RemoveTypes::Item(_) => { println!("This removes {} from the `{}` warehouse.", contents, style::bold(&to_remove[0])); }
+            }
+
+            if !items.is_empty() {
+                let warehouses = matches!(remove_type, RemoveTypes::Warehouse);
+                let mut columns = vec![Column::new("Item").truncate(), Column::new("Quantity").right(), Column::new("Rule")];
+                if warehouses {
+                    columns.insert(0, Column::new("Warehouse"));
+                }
+
+                let mut table = Table::new(columns);
+                for (warehouse, item, quantity, rules, _) in items {
+                    let mut cells = vec![Cell::new(item), Cell::new(quantity), Cell::new(if rules > 0 { "yes" } else { "" })];
+                    if warehouses {
+                        cells.insert(0, Cell::new(warehouse));
+                    }
+                    table.row(cells);
+                }
+                table.print();
+            }
+
+            if !Deposito::go_ahead(confirm) {
+                return;
             }
         }
 
+        // Rules go along with their items, movements are deleted by the foreign key
+        let mut query_string = format!("DELETE FROM rules WHERE rules.item_id IN (SELECT items.id FROM items WHERE {0}); DELETE FROM items WHERE {0};", items_condition);
+        if let RemoveTypes::Warehouse = remove_type {
+            query_string.push_str(&format!("DELETE FROM warehouses WHERE warehouses.name IN ({});", placeholders(1)));
+        }
+
+        let mut query: sqlx::query::Query<'_, sqlx::Sqlite, _>;
         query = sqlx::query(&query_string);
         for remove in to_remove.iter() {
             query = query.bind(remove);
@@ -407,108 +1000,541 @@ impl Deposito {
         let remove_result = query.execute(pool).await;
         match remove_result {
             Ok(_) => { println!("Removed successfully."); }
-            Err(err) => { println!("Unable to remove. Reason: {}", err); }
+            Err(err) => { report_error!("Unable to remove. Reason: {}", err); }
         }
     }
 
-    pub async fn raport_warehouse(&mut self, warehouse_name: &str) {
+    // Archives the warehouses (without a `warehouse_name`) or the items of the warehouse, or
+    // brings them back. Those already in the requested state keep it, and when they were
+    // archived.
+    pub async fn archive(&mut self, warehouse_name: Option<&str>, names: Vec<String>, archive: bool) {
         let pool = match self.get_pool() {
             Some(pool) => { pool }
             None => { return; }
         };
 
-        let raport_result = sqlx::query!(r#"
-            SELECT
-                i.name,
-                i.quantity,
-                i.description,
-                r.gets_below_quantity,
-                (SELECT COALESCE((SELECT 1 WHERE r.gets_below_quantity > i.quantity), FALSE, TRUE)) AS "is_critical: bool"
-            FROM
-                items i
-                JOIN warehouses ON i.warehouse_id = warehouses.id
-                JOIN rules r ON i.id = r.item_id
-            WHERE
-                warehouses.name = $1;
-        "#, warehouse_name)
-            .fetch_all(pool)
-            .await;
+        let found = match warehouse_name {
+            Some(warehouse_name) => {
+                Deposito::check_warehouse(pool, warehouse_name).await
+                    && Deposito::check_items(pool, warehouse_name, &names).await
+            }
+            None => {
+                let mut found = true;
+                for warehouse_name in names.iter() {
+                    found &= Deposito::check_warehouse(pool, warehouse_name).await;
+                }
+                found
+            }
+        };
+        if !found {
+            return;
+        }
 
-        match raport_result {
-            Ok(items) => {
-                println!("Raport for the `\x1b[1m{}\x1b[0m` warehouse:", warehouse_name);
-                for item in items {
-                    print!("\t- {} ({})   ", item.name, item.quantity);
-
-                    if item.is_critical.unwrap() {
-                        print!("\x1b[31mCRITICAL\x1b[0m");
-                    } else {
-                        print!("\x1b[32mOK\x1b[0m");
-                    }
+        let archived_at = archive.then(raport::unix_now);
+        let list = serde_json::Value::from(names.as_slice()).to_string();
+        let archive_result = match warehouse_name {
+            Some(warehouse_name) => {
+                sqlx::query!("
+                    UPDATE items SET archived_at = $1
+                    WHERE
+                        items.warehouse_id = (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $2)
+                        AND items.name IN (SELECT value FROM json_each($3))
+                        AND (items.archived_at IS NULL) = ($1 IS NOT NULL);",
+                    archived_at, warehouse_name, list)
+                    .execute(pool)
+                    .await
+            }
+            None => {
+                sqlx::query!("
+                    UPDATE warehouses SET archived_at = $1
+                    WHERE name IN (SELECT value FROM json_each($2)) AND (archived_at IS NULL) = ($1 IS NOT NULL);",
+                    archived_at, list)
+                    .execute(pool)
+                    .await
+            }
+        };
 
-                    print!(" (Can't go below {})\n", item.gets_below_quantity);
+        let changed = match archive_result {
+            Ok(result) => { result.rows_affected() as usize }
+            Err(err) => { report_error!("Unable to {}. Reason: {}", if archive { "archive" } else { "unarchive" }, err); return; }
+        };
+
+        let names: Vec<String> = names.iter().map(|name| format!("`{}`", style::bold(name))).collect();
+        let what = match warehouse_name {
+            Some(warehouse_name) => { format!("the item(s) {} in the `{}` warehouse", names.join(", "), style::bold(warehouse_name)) }
+            None => { format!("the warehouse(s) {}", names.join(", ")) }
+        };
+
+        let (verb, state) = if archive { ("Archived", "archived already") } else { ("Unarchived", "not archived") };
+        match names.len() - changed {
+            0 => { println!("{} {}.", verb, what); }
+            unchanged if changed == 0 => { println!("Nothing changed, {} {} {}.", what, if unchanged == 1 { "is" } else { "are" }, state); }
+            unchanged => { println!("{} {}, {} of them {} {}.", verb, what, unchanged, if unchanged == 1 { "was" } else { "were" }, state); }
+        }
+    }
+
+    // Whether a destructive command goes ahead once it has shown what it would delete
+    fn go_ahead(confirm: Confirm) -> bool {
+        match confirm {
+            Confirm::Yes => { true }
+            Confirm::DryRun => { println!("Dry run, nothing was removed."); false }
+            Confirm::Ask => {
+                match Deposito::ask("Remove?") {
+                    Some(true) => { true }
+                    Some(false) => { println!("Nothing was removed."); false }
+                    None => {
+                        report_error!("Not removing anything without a confirmation, which needs a terminal. Run it with `--yes` to go ahead.");
+                        false
+                    }
                 }
             }
-            Err(err) => { println!("Unable to make a raport for the `\x1b[1m{}\x1b[0m` warehouse. Reason: {}", warehouse_name, err); }
         }
     }
 
-    pub async fn raport_all(&mut self) {
-        let pool = match self.get_pool() {
-            Some(pool) => { pool }
-            None => { return; }
-        };
+    // Asks a yes or no question on the terminal, `None` when there is none to ask on
+    fn ask(question: &str) -> Option<bool> {
+        use std::io::{IsTerminal, Write};
 
-        let raport_result = sqlx::query!(r#"
-            SELECT
-                warehouses.name AS warehouse_name,
-                i.name AS item_name,
-                i.quantity,
-                r.gets_below_quantity,
-                (SELECT COALESCE((SELECT 1 WHERE r.gets_below_quantity > i.quantity), FALSE, TRUE)) AS "is_critical: bool"
-            FROM
+        if !std::io::stdin().is_terminal() {
+            return None;
+        }
+
+        print!("{} [y/N] ", question);
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        match std::io::stdin().read_line(&mut answer) {
+            Ok(_) => { Some(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")) }
+            Err(_) => { Some(false) }
+        }
+    }
+
+    async fn record_movement(pool: &sqlx::SqlitePool, warehouse_name: &str, item_name: &str, delta: i64) {
+        let record_result = sqlx::query!("
+            INSERT INTO movements (id, item_id, delta) VALUES (NULL, (SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE warehouses.name = $1 AND items.name = $2), $3);",
+            warehouse_name, item_name, delta)
+            .execute(pool)
+            .await;
+
+        if let Err(err) = record_result {
+            report_error!("Unable to record the movement of `{}` in the `{}` warehouse. Reason: {}", style::bold(item_name), style::bold(warehouse_name), err);
+        }
+    }
+
+    // Whether the warehouse exists. When it doesn't, says so and suggests the closest name.
+    async fn check_warehouse(pool: &sqlx::SqlitePool, warehouse_name: &str) -> bool {
+        let warehouses_result = sqlx::query_scalar!(r#"SELECT name FROM warehouses;"#)
+            .fetch_all(pool)
+            .await;
+
+        let warehouses = match warehouses_result {
+            Ok(warehouses) => { warehouses }
+            Err(err) => { report_error!("Unable to look up the `{}` warehouse. Reason: {}", style::bold(warehouse_name), err); return false; }
+        };
+
+        if warehouses.iter().any(|warehouse| warehouse == warehouse_name) {
+            return true;
+        }
+
+        match suggest::closest(warehouse_name, warehouses.iter().map(String::as_str)) {
+            Some(suggestion) => { report_error!("Warehouse `{}` not found. Did you mean `{}`?", style::bold(warehouse_name), style::bold(suggestion)); }
+            None => { report_error!("Warehouse `{}` not found.", style::bold(warehouse_name)); }
+        }
+        false
+    }
+
+    // Whether all of the items exist in the warehouse, reporting each one that doesn't
+    async fn check_items(pool: &sqlx::SqlitePool, warehouse_name: &str, item_names: &[String]) -> bool {
+        let items_result = sqlx::query_scalar!(r#"SELECT items.name FROM items JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = ?;"#, warehouse_name)
+            .fetch_all(pool)
+            .await;
+
+        let items = match items_result {
+            Ok(items) => { items }
+            Err(err) => { report_error!("Unable to look up items in the `{}` warehouse. Reason: {}", style::bold(warehouse_name), err); return false; }
+        };
+
+        let mut found = true;
+        for item_name in item_names.iter().filter(|item_name| !items.contains(item_name)) {
+            match suggest::closest(item_name, items.iter().map(String::as_str)) {
+                Some(suggestion) => { report_error!("Item `{}` not found in the `{}` warehouse. Did you mean `{}`?", style::bold(item_name), style::bold(warehouse_name), style::bold(suggestion)); }
+                None => { report_error!("Item `{}` not found in the `{}` warehouse.", style::bold(item_name), style::bold(warehouse_name)); }
+            }
+            found = false;
+        }
+        found
+    }
+
+    // Whether the warehouse isn't archived, which hides it from `list` and `raport` unless
+    // they're asked to include archived ones
+    async fn check_unarchived(pool: &sqlx::SqlitePool, warehouse_name: &str) -> bool {
+        let archived_result = sqlx::query_scalar!(r#"SELECT archived_at FROM warehouses WHERE name = $1;"#, warehouse_name)
+            .fetch_optional(pool)
+            .await;
+
+        match archived_result {
+            Ok(Some(Some(_))) => {
+                report_error!("The `{}` warehouse is archived. Add `--include-archived` to show it anyway.", style::bold(warehouse_name));
+                false
+            }
+            Ok(_) => { true }
+            Err(err) => { report_error!("Unable to look up the `{}` warehouse. Reason: {}", style::bold(warehouse_name), err); false }
+        }
+    }
+
+    // Archived items are left out unless `include_archived`, but still count in the
+    // expressions of the others
+    async fn evaluate_raport(pool: &sqlx::SqlitePool, warehouse_name: &str, include_archived: bool) -> Result<Vec<RaportEntry>, sqlx::Error> {
+        let quantities = sqlx::query!(r#"SELECT items.name, items.quantity FROM items JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = $1;"#, warehouse_name)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|item| (item.name, item.quantity))
+            .collect();
+
+        let movements = sqlx::query!(r#"
+            SELECT
+                i.name,
+                m.delta,
+                m.created_at
+            FROM
+                movements m
+                JOIN items i ON i.id = m.item_id
+                JOIN warehouses w ON w.id = i.warehouse_id
+            WHERE
+                w.name = $1;
+        "#, warehouse_name)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|movement| Movement { item: movement.name, delta: movement.delta, created_at: movement.created_at })
+            .collect();
+
+        let rules = sqlx::query!(r#"
+            SELECT
+                i.name,
+                i.quantity,
+                i.description,
+                r.gets_below_quantity,
+                r.expression,
+                r.severity
+            FROM
                 items i
                 JOIN warehouses ON i.warehouse_id = warehouses.id
                 JOIN rules r ON i.id = r.item_id
-            ORDER BY
-                warehouses.name ASC;
-        "#)
+            WHERE
+                warehouses.name = $1
+                AND (i.archived_at IS NULL OR $2);
+        "#, warehouse_name, include_archived)
             .fetch_all(pool)
-            .await;
+            .await?;
+
+        let state = WarehouseState::new(quantities, movements, raport::unix_now());
+        let entries = rules.into_iter().map(|item| {
+            let rule = Rule {
+                gets_below_quantity: item.gets_below_quantity,
+                expression: item.expression,
+                severity: item.severity.parse().unwrap_or(Severity::Critical),
+            };
+            let status = state.evaluate(&item.name, &rule);
+            let forecast = state.forecast(&item.name, &rule);
+
+            RaportEntry { name: item.name, quantity: item.quantity, description: item.description, rule, status, forecast }
+        }).collect();
+
+        Ok(entries)
+    }
 
-        match raport_result {
-            Ok(items) => {
-                let mut current_warehouse = String::from("");
-                for item in items {
-                    if current_warehouse != item.warehouse_name {
-                        current_warehouse = item.warehouse_name;
-                        println!("\nRaport for the `\x1b[1m{}\x1b[0m` warehouse:", current_warehouse);
-                    }
-                    print!("\t- {} ({})   ", item.item_name, item.quantity);
+    fn raport_status(entry: &RaportEntry, horizon: Option<u32>) -> (String, Color) {
+        // Items that are fine today but will run low within the horizon get flagged
+        let critical_soon = match (horizon, entry.forecast.days_until_critical) {
+            (Some(horizon), Some(days)) => days <= horizon,
+            _ => false,
+        };
 
-                    if item.is_critical.unwrap() {
-                        print!("\x1b[31mCRITICAL\x1b[0m");
-                    } else {
-                        print!("\x1b[32mOK\x1b[0m");
-                    }
+        match &entry.status {
+            Status::Ok if critical_soon => {
+                (format!("CRITICAL IN {} DAY(S)", entry.forecast.days_until_critical.unwrap_or_default()), Color::Yellow)
+            }
+            Status::Ok => (String::from("OK"), Color::Green),
+            Status::Triggered(Severity::Critical) => (String::from("CRITICAL"), Color::Red),
+            Status::Triggered(Severity::Warn) => (String::from("WARN"), Color::Yellow),
+            Status::Triggered(Severity::Info) => (String::from("INFO"), Color::Blue),
+            Status::Invalid(_) => (String::from("INVALID RULE"), Color::Red),
+        }
+    }
+
+    // Number of rules of each status: all, ok, info, warn, critical and invalid
+    fn raport_counts(entries: &[RaportEntry]) -> [usize; 6] {
+        let mut counts = [entries.len(), 0, 0, 0, 0, 0];
+        for entry in entries.iter() {
+            let index = match entry.status {
+                Status::Ok => 1,
+                Status::Triggered(Severity::Info) => 2,
+                Status::Triggered(Severity::Warn) => 3,
+                Status::Triggered(Severity::Critical) => 4,
+                Status::Invalid(_) => 5,
+            };
+            counts[index] += 1;
+        }
+        counts
+    }
+
+    // A summary sheet counting the statuses of each warehouse, followed by a sheet per warehouse
+    fn raport_workbook(raports: &[(String, Vec<RaportEntry>)], horizon: Option<u32>) -> Workbook {
+        let mut workbook = Workbook::new();
+
+        let summary = workbook.sheet("Summary", vec!["Warehouse", "Items", "OK", "Info", "Warn", "Critical", "Invalid"]);
+        let mut totals = [0usize; 6];
+        for (warehouse_name, entries) in raports.iter() {
+            let mut row = vec![Value::from(warehouse_name.as_str())];
+            for (total, count) in totals.iter_mut().zip(Deposito::raport_counts(entries)) {
+                *total += count;
+                row.push(Value::from(count));
+            }
+            summary.rows.push(row);
+        }
+        summary.footer = Some(std::iter::once(Value::from("Total")).chain(totals.into_iter().map(Value::from)).collect());
+
+        for (warehouse_name, entries) in raports.iter() {
+            let sheet = workbook.sheet(warehouse_name, vec!["Item", "Quantity", "Threshold", "Status"]);
+            sheet.highlight = Some(Highlight { column: 3, text: "CRITICAL" });
+            for entry in entries.iter() {
+                let threshold = match (&entry.rule.expression, entry.rule.gets_below_quantity) {
+                    (Some(expression), _) => Value::from(expression.as_str()),
+                    (None, quantity) => Value::from(quantity),
+                };
+                let (status, _) = Deposito::raport_status(entry, horizon);
+                sheet.rows.push(vec![Value::from(entry.name.as_str()), Value::from(entry.quantity), threshold, Value::from(status)]);
+            }
+        }
+
+        workbook
+    }
+
+    // The title page of PDFs has a table like the summary sheet of spreadsheets
+    fn raport_summary_table(raports: &[(String, Vec<RaportEntry>)]) -> Table {
+        let mut table = Table::new(vec![
+            Column::new("Warehouse").truncate(),
+            Column::new("Items").right(),
+            Column::new("OK").right(),
+            Column::new("Info").right(),
+            Column::new("Warn").right(),
+            Column::new("Critical").right(),
+            Column::new("Invalid").right(),
+        ]);
+
+        for (warehouse_name, entries) in raports.iter() {
+            let counts = Deposito::raport_counts(entries);
+            let mut row = vec![Cell::new(warehouse_name)];
+            row.extend(counts.iter().enumerate().map(|(index, count)| match index {
+                4 if *count > 0 => Cell::colored(count, Color::Red),
+                5 if *count > 0 => Cell::colored(count, Color::Yellow),
+                _ => Cell::new(count),
+            }));
+            table.row(row);
+        }
+
+        table
+    }
+
+    // `subtitle` says which warehouses the raport is of, for PDFs
+    fn write_raport(raports: &[(String, Vec<RaportEntry>)], horizon: Option<u32>, path: &str, file: RaportFile, subtitle: &str) {
+        let bytes = match file {
+            RaportFile::Xlsx => { Deposito::raport_workbook(raports, horizon).xlsx() }
+            RaportFile::Ods => { Deposito::raport_workbook(raports, horizon).ods() }
+            RaportFile::Pdf => {
+                let workspace = match std::env::current_dir() {
+                    Ok(current_dir) => { current_dir.display().to_string() }
+                    Err(err) => { report_error!("Unable to get current working directory. Reason: {}", err); return; }
+                };
+
+                let title_page = TitlePage {
+                    title: String::from("Raport"),
+                    subtitle: subtitle.to_string(),
+                    lines: vec![format!("Generated on {}", raport::format_timestamp(raport::unix_now())), format!("in {}", workspace)],
+                };
+                let sections: Vec<(String, Table)> = raports.iter()
+                    .map(|(warehouse_name, entries)| (warehouse_name.clone(), Deposito::raport_table(entries, horizon)))
+                    .collect();
+                pdf::raport(&title_page, &Deposito::raport_summary_table(raports), &sections)
+            }
+        };
+
+        match std::fs::write(path, bytes) {
+            Ok(_) => { println!("Raport of {} warehouse(s) written to `{}`.", raports.len(), style::bold(path)); }
+            Err(err) => { report_error!("Unable to write the raport to `{}`. Reason: {}", style::bold(path), err); }
+        }
+    }
+
+    // A section per warehouse, holding the table of the text output
+    fn print_raport_page(format: Format, raports: &[(String, Vec<RaportEntry>)], horizon: Option<u32>, stamp: Option<&Stamp>) {
+        let sections: Vec<(String, Table)> = raports.iter()
+            .map(|(warehouse_name, entries)| (warehouse_name.clone(), Deposito::raport_table(entries, horizon)))
+            .collect();
+
+        match format {
+            Format::Html => { print!("{}", render::html("Raport", &sections, stamp)); }
+            _ => { print!("{}", render::markdown("Raport", &sections, stamp)); }
+        }
+    }
+
+    fn print_stamp(stamp: Option<&Stamp>) {
+        if let Some(stamp) = stamp {
+            let (generated, workspace) = stamp.describe();
+            println!("\n{} `{}`.", generated, style::bold(workspace));
+        }
+    }
+
+    fn raport_table(entries: &[RaportEntry], horizon: Option<u32>) -> Table {
+        let mut table = Table::new(vec![
+            Column::new("Item"),
+            Column::new("Quantity").right(),
+            Column::new("Status"),
+            Column::new("Rule").truncate(),
+            Column::new("Forecast").truncate(),
+        ]);
+
+        for entry in entries.iter() {
+            let (status, color) = Deposito::raport_status(entry, horizon);
+            let status = Cell::colored(status, color);
+
+            let mut rule = entry.rule.describe();
+            if let Status::Invalid(err) = &entry.status {
+                rule.push_str(&format!(" ({})", err));
+            }
+
+            let forecast = &entry.forecast;
+            let mut estimates = Vec::new();
+            if forecast.daily_consumption > 0.0 {
+                estimates.push(format!("{:.1}/day", forecast.daily_consumption));
+                match forecast.days_until_critical {
+                    Some(0) => { }
+                    Some(days) => { estimates.push(format!("critical in {} day(s)", days)); }
+                    None if entry.rule.severity == Severity::Critical => { estimates.push(String::from("not critical within a year")); }
+                    None => { }
+                }
+                if let Some(days) = forecast.days_until_empty {
+                    estimates.push(format!("empty in {} day(s)", days.floor()));
+                }
+            }
+
+            table.row(vec![
+                Cell::new(&entry.name),
+                Cell::new(entry.quantity),
+                status,
+                Cell::new(rule),
+                Cell::new(estimates.join(", ")),
+            ]);
+        }
+
+        table
+    }
+
+    pub async fn raport_warehouse(&mut self, warehouse_name: &str, horizon: Option<u32>, output: Option<(&str, RaportFile)>, stamp: Option<&Stamp>, include_archived: bool) {
+        let format = self.format;
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        if !Deposito::check_warehouse(pool, warehouse_name).await
+            || !(include_archived || Deposito::check_unarchived(pool, warehouse_name).await) {
+            return;
+        }
+
+        match (Deposito::evaluate_raport(pool, warehouse_name, include_archived).await, output) {
+            (Ok(entries), Some((path, file))) => {
+                Deposito::write_raport(&[(warehouse_name.to_string(), entries)], horizon, path, file, &format!("The {} warehouse", warehouse_name));
+            }
+            (Ok(entries), None) if matches!(format, Format::Html | Format::Markdown) => {
+                Deposito::print_raport_page(format, &[(warehouse_name.to_string(), entries)], horizon, stamp);
+            }
+            (Ok(entries), None) if format != Format::Text => {
+                let records: Vec<_> = entries.iter().map(|entry| entry.to_record(warehouse_name)).collect();
+                output::print_records(format, &records);
+            }
+            (Ok(entries), None) => {
+                println!("Raport for the `{}` warehouse:", style::bold(warehouse_name));
+                Deposito::raport_table(&entries, horizon).print();
+                Deposito::print_stamp(stamp);
+            }
+            (Err(err), _) => { report_error!("Unable to make a raport for the `{}` warehouse. Reason: {}", style::bold(warehouse_name), err); }
+        }
+    }
+
+    pub async fn raport_all(&mut self, horizon: Option<u32>, output: Option<(&str, RaportFile)>, stamp: Option<&Stamp>, include_archived: bool) {
+        let format = self.format;
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let raport_result: Result<Vec<(String, Vec<RaportEntry>)>, sqlx::Error> = async {
+            let warehouses = sqlx::query!(r#"
+                SELECT DISTINCT
+                    warehouses.name
+                FROM
+                    items i
+                    JOIN warehouses ON i.warehouse_id = warehouses.id
+                    JOIN rules r ON i.id = r.item_id
+                WHERE
+                    (i.archived_at IS NULL AND warehouses.archived_at IS NULL) OR $1
+                ORDER BY
+                    warehouses.name ASC;
+            "#, include_archived)
+                .fetch_all(pool)
+                .await?;
+
+            let mut raports = Vec::new();
+            for warehouse in warehouses {
+                let entries = Deposito::evaluate_raport(pool, &warehouse.name, include_archived).await?;
+                raports.push((warehouse.name, entries));
+            }
+
+            Ok(raports)
+        }.await;
 
-                    print!(" (Can't go below {})\n", item.gets_below_quantity);
+        match (raport_result, output) {
+            (Ok(raports), Some((path, file))) => {
+                Deposito::write_raport(&raports, horizon, path, file, "All warehouses");
+            }
+            (Ok(raports), None) if matches!(format, Format::Html | Format::Markdown) => {
+                Deposito::print_raport_page(format, &raports, horizon, stamp);
+            }
+            (Ok(raports), None) if format != Format::Text => {
+                let records: Vec<_> = raports.iter()
+                    .flat_map(|(warehouse_name, entries)| entries.iter().map(|entry| entry.to_record(warehouse_name)))
+                    .collect();
+                output::print_records(format, &records);
+            }
+            (Ok(raports), None) => {
+                for (warehouse_name, entries) in raports.iter() {
+                    println!("\nRaport for the `{}` warehouse:", style::bold(warehouse_name));
+                    Deposito::raport_table(entries, horizon).print();
                 }
+                Deposito::print_stamp(stamp);
             }
-            Err(err) => { println!("Unable to make a raport for all the warehouse. Reason: {}", err); }
+            (Err(err), _) => { report_error!("Unable to make a raport for all the warehouse. Reason: {}", err); }
         }
     }
 
     pub async fn show_rules(&mut self, warehouse_name: &str) {
+        let format = self.format;
         let pool = match self.get_pool() {
             Some(pool) => { pool }
             None => { return; }
         };
 
+        if !Deposito::check_warehouse(pool, warehouse_name).await {
+            return;
+        }
+
         let rules_result = sqlx::query!("
             SELECT
                 i.name,
-                r.gets_below_quantity
+                r.gets_below_quantity,
+                r.expression,
+                r.severity
             FROM
                 rules r
                 JOIN items i ON i.id = r.item_id
@@ -520,47 +1546,102 @@ impl Deposito {
             .await;
 
         match rules_result {
+            Ok(rules) if format != Format::Text => {
+                let records: Vec<RuleRecord> = rules.into_iter()
+                    .map(|rule| RuleRecord { name: rule.name, gets_below_quantity: rule.gets_below_quantity, expression: rule.expression, severity: rule.severity })
+                    .collect();
+                output::print_records(format, &records);
+            }
             Ok(rules) => {
-                if rules.len() == 0 {
-                    println!("No rules for the `\x1b[1m{}\x1b[0m` warehouse.", warehouse_name);
+                if rules.is_empty() {
+                    println!("No rules for the `{}` warehouse.", style::bold(warehouse_name));
                     return;
                 }
 
-                println!("Rules for the `\x1b[1m{}\x1b[0m` warehouse:", warehouse_name);
+                println!("Rules for the `{}` warehouse:", style::bold(warehouse_name));
+                let mut table = Table::new(vec![Column::new("Item"), Column::new("Severity"), Column::new("Condition").truncate()]);
                 for rule in rules {
-                    println!("\t- `\x1b[1m{}\x1b[0m` can't get below `\x1b[1m{}\x1b[0m`", rule.name, rule.gets_below_quantity);
+                    let condition = match (rule.expression, rule.gets_below_quantity) {
+                        (Some(expression), _) => { expression }
+                        (None, gets_below_quantity) => { format!("can't get below {}", gets_below_quantity.unwrap_or_default()) }
+                    };
+                    table.row(vec![Cell::new(rule.name), Cell::new(rule.severity), Cell::new(condition)]);
                 }
+                table.print();
             }
-            Err(err) => { println!("Unable get rules for the `\x1b[1m{}\x1b[0m` warehouse. Reason: {}", warehouse_name, err); }
+            Err(err) => { report_error!("Unable get rules for the `{}` warehouse. Reason: {}", style::bold(warehouse_name), err); }
         }
     }
 
-    pub async fn create_rules(&mut self, warehouse_name: &str, item_name: &str, quantity: i64) {
+    pub async fn create_rules(&mut self, warehouse_name: &str, item_name: &str, condition: RuleCondition, severity: Severity) {
         let pool = match self.get_pool() {
             Some(pool) => { pool }
             None => { return; }
         };
 
+        if !Deposito::check_warehouse(pool, warehouse_name).await
+            || !Deposito::check_items(pool, warehouse_name, &[item_name.to_string()]).await {
+            return;
+        }
+
+        let (quantity, expression) = match condition {
+            RuleCondition::Below(quantity) => { (Some(quantity), None) }
+            RuleCondition::Expression(expression) => {
+                let expr = match Expr::parse(&expression) {
+                    Ok(expr) => { expr }
+                    Err(err) => { report_error!("Invalid rule expression `{}`. Reason: {}", style::bold(expression), err); return; }
+                };
+
+                let items_result = sqlx::query!(r#"SELECT items.name FROM items JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = ?;"#, warehouse_name)
+                    .fetch_all(pool)
+                    .await;
+
+                let items: Vec<String> = match items_result {
+                    Ok(items) => { items.into_iter().map(|item| item.name).collect() }
+                    Err(err) => { report_error!("Unable to validate the rule expression. Reason: {}", err); return; }
+                };
+
+                // Catch typos in item names now instead of in every future raport
+                for referenced in expr.items() {
+                    if !items.iter().any(|item| item == referenced) {
+                        let suggestion = suggest::closest(referenced, items.iter().map(String::as_str)).map(|suggestion| style::bold(suggestion).to_string());
+                        report_error!("Rule expression references `{}`, which isn't an item in the `{}` warehouse.{}", style::bold(referenced), style::bold(warehouse_name), suggest::did_you_mean(&suggestion));
+                        return;
+                    }
+                }
+
+                (None, Some(expression))
+            }
+        };
+
+        let severity_name = severity.as_str();
         let create_rule_result = sqlx::query!("
-            INSERT INTO rules (id, item_id, gets_below_quantity) VALUES (NULL, (SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE items.name = $2 AND warehouses.name = $1), $3)
-        ", warehouse_name, item_name, quantity)
+            INSERT INTO rules (id, item_id, gets_below_quantity, expression, severity) VALUES (NULL, (SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE items.name = $2 AND warehouses.name = $1), $3, $4, $5)
+        ", warehouse_name, item_name, quantity, expression, severity_name)
             .execute(pool)
             .await;
 
         match create_rule_result {
             Ok(_) => {
-                println!("Rule in the `\x1b[1m{}\x1b[0m` warehouse successfully created for `\x1b[1m{}\x1b[0m`. You will be alerted in the raport whenever the items' quantity gets below `\x1b[1m{}\x1b[0m`.\n", warehouse_name, item_name, quantity);
-                self.raport_warehouse(warehouse_name).await;
+                match (&expression, quantity) {
+                    (Some(expression), _) => {
+                        println!("Rule in the `{}` warehouse successfully created for `{}`. The raport will mark it as `{}` whenever `{}`.\n", style::bold(warehouse_name), style::bold(item_name), style::bold(severity), style::bold(expression));
+                    }
+                    (None, quantity) => {
+                        println!("Rule in the `{}` warehouse successfully created for `{}`. You will be alerted in the raport whenever the items' quantity gets below `{}`.\n", style::bold(warehouse_name), style::bold(item_name), style::bold(quantity.unwrap_or_default()));
+                    }
+                }
+                self.raport_warehouse(warehouse_name, None, None, None, true).await;
             }
             Err(err) => {
                 if let Some(err) = err.as_database_error() {
                 if let Some(code) = err.code() {
                 if code == "2067" {
-                    println!("Rule in the `\x1b[1m{}\x1b[0m` warehouse for `\x1b[1m{}\x1b[0m` already exists.", warehouse_name, item_name);
+                    println!("Rule in the `{}` warehouse for `{}` already exists.", style::bold(warehouse_name), style::bold(item_name));
                     return;
                 }}}
 
-                println!("Unable to create a rule in the `\x1b[1m{}\x1b[0m` warehouse for `\x1b[1m{}\x1b[0m`. Reason: {}", warehouse_name, item_name, err);
+                report_error!("Unable to create a rule in the `{}` warehouse for `{}`. Reason: {}", style::bold(warehouse_name), style::bold(item_name), err);
             }
         }
     }
@@ -571,7 +1652,12 @@ impl Deposito {
             None => { return; }
         };
 
-        let mut query_string = String::from("UPDATE rules SET gets_below_quantity = $2 WHERE rules.item_id IN (SELECT items.id FROM items JOIN rules ON items.id = rules.item_id JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = $1 AND items.name IN (");
+        if !Deposito::check_warehouse(pool, warehouse_name).await
+            || !Deposito::check_items(pool, warehouse_name, item_names).await {
+            return;
+        }
+
+        let mut query_string = String::from("UPDATE rules SET gets_below_quantity = $2, expression = NULL WHERE rules.item_id IN (SELECT items.id FROM items JOIN rules ON items.id = rules.item_id JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = $1 AND items.name IN (");
 
         for index in 0..item_names.len() {
             query_string.push_str(&format!("${}", index+3));
@@ -587,25 +1673,67 @@ impl Deposito {
         }
 
         match query.execute(pool).await {
-            Ok(_) => { println!("Successfully edited specified rules in the `\x1b[1m{}\x1b[0m` warehouse.", warehouse_name); }
-            Err(err) => { println!("Unable to edit rule(s) in the `\x1b[1m{}\x1b[0m` warehouse for `\x1b[1m{:?}\x1b[0m`. Reason: {}", warehouse_name, item_names, err); }
+            Ok(_) => { println!("Successfully edited specified rules in the `{}` warehouse.", style::bold(warehouse_name)); }
+            Err(err) => { report_error!("Unable to edit rule(s) in the `{}` warehouse for `{}`. Reason: {}", style::bold(warehouse_name), style::bold(format!("{:?}", item_names)), err); }
         }
     }
 
-    pub async fn remove_rules(&mut self, warehouse_name: &str, item_names: &[String]) {
+    pub async fn remove_rules(&mut self, warehouse_name: &str, item_names: &[String], confirm: Confirm) {
         let pool = match self.get_pool() {
             Some(pool) => { pool }
             None => { return; }
         };
 
-        let mut query_string = String::from("DELETE FROM rules WHERE rules.item_id IN (SELECT items.id FROM items JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = $1 AND items.name IN (");
+        if !Deposito::check_warehouse(pool, warehouse_name).await
+            || !Deposito::check_items(pool, warehouse_name, item_names).await {
+            return;
+        }
 
+        let mut rules_condition = String::from("rules.item_id IN (SELECT items.id FROM items JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = $1 AND items.name IN (");
         for index in 0..item_names.len() {
-            query_string.push_str(&format!("${}", index+2));
-            if index != item_names.len()-1 { query_string.push(','); }
+            rules_condition.push_str(&format!("${}", index+2));
+            if index != item_names.len()-1 { rules_condition.push(','); }
+        }
+        rules_condition.push_str("))");
+
+        if confirm != Confirm::Yes {
+            let preview_string = format!("
+                SELECT i.name, rules.gets_below_quantity, rules.expression, rules.severity
+                FROM rules JOIN items i ON i.id = rules.item_id
+                WHERE {}
+                ORDER BY i.name;", rules_condition);
+            let mut preview_query = sqlx::query_as::<_, (String, Option<i64>, Option<String>, String)>(&preview_string).bind(warehouse_name);
+            for item in item_names.iter() {
+                preview_query = preview_query.bind(item);
+            }
+
+            let rules = match preview_query.fetch_all(pool).await {
+                Ok(rules) => { rules }
+                Err(err) => { report_error!("Unable to look up what would be removed. Reason: {}", err); return; }
+            };
+
+            if rules.is_empty() {
+                println!("None of the items have a rule in the `{}` warehouse, nothing to remove.", style::bold(warehouse_name));
+                return;
+            }
+
+            println!("This removes {} rule(s) from the `{}` warehouse.", rules.len(), style::bold(warehouse_name));
+            let mut table = Table::new(vec![Column::new("Item"), Column::new("Severity"), Column::new("Condition").truncate()]);
+            for (item, gets_below_quantity, expression, severity) in rules {
+                let condition = match (expression, gets_below_quantity) {
+                    (Some(expression), _) => { expression }
+                    (None, gets_below_quantity) => { format!("can't get below {}", gets_below_quantity.unwrap_or_default()) }
+                };
+                table.row(vec![Cell::new(item), Cell::new(severity), Cell::new(condition)]);
+            }
+            table.print();
+
+            if !Deposito::go_ahead(confirm) {
+                return;
+            }
         }
-        query_string.push_str("));");
 
+        let query_string = format!("DELETE FROM rules WHERE {};", rules_condition);
         let mut query: sqlx::query::Query<'_, sqlx::Sqlite, _> = sqlx::query(&query_string);
         query = query.bind(warehouse_name);
         for item in item_names.iter() {
@@ -613,340 +1741,2094 @@ impl Deposito {
         }
 
         match query.execute(pool).await {
-            Ok(_) => { println!("Successfully removed specified rules from the `\x1b[1m{}\x1b[0m` warehouse.", warehouse_name); }
-            Err(err) => { println!("Unable to remove rule(s) in the `\x1b[1m{}\x1b[0m` warehouse for `\x1b[1m{:?}\x1b[0m`. Reason: {}", warehouse_name, item_names, err); }
+            Ok(_) => { println!("Successfully removed specified rules from the `{}` warehouse.", style::bold(warehouse_name)); }
+            Err(err) => { report_error!("Unable to remove rule(s) in the `{}` warehouse for `{}`. Reason: {}", style::bold(warehouse_name), style::bold(format!("{:?}", item_names)), err); }
         }
     }
-}
 
-impl Default for Deposito {
-    fn default() -> Self {
-        Self {
-            db_pool: Default::default(),
-            default_warehouse: Default::default()
+    pub async fn shell(&mut self) {
+        let history_path = std::env::current_dir().ok().map(|dir| dir.join(DIR_NAME).join(HISTORY_FILE));
+        let mut editor = Editor::new(history_path);
+        let mut names = self.load_names().await;
+
+        if editor.is_interactive() {
+            println!("Type `{}` for help and `{}` to leave.", style::bold("help"), style::bold("exit"));
         }
-    }
-}
 
-struct ItemEdit {
-    name: Option<String>,
-    description: Option<String>,
-    quantity: Option<i64>,
-}
+        loop {
+            let prompt = match &self.default_warehouse {
+                Some(warehouse_name) => { format!("deposito ({})> ", warehouse_name) }
+                None => { String::from("deposito> ") }
+            };
+
+            let default_warehouse = self.default_warehouse.as_deref();
+            let line = match editor.read_line(&prompt, &|line| shell::complete(line, &names, default_warehouse)) {
+                Ok(ReadLine::Line(line)) => { line }
+                Ok(ReadLine::Interrupted) => { continue; }
+                Ok(ReadLine::Eof) => { break; }
+                Err(err) => { report_error!("Unable to read the input. Reason: {}", err); break; }
+            };
+            editor.add_history(&line);
+
+            let words = match shlex::split(&line) {
+                Some(words) => { words }
+                None => { report_error!("Unable to read the command. Reason: unclosed quote"); continue; }
+            };
+
+            match words.first().map(String::as_str) {
+                None => { continue; }
+                Some("exit" | "quit") => { break; }
+                Some("help") => { Deposito::shell_help(); continue; }
+                Some("use") => { self.use_warehouse(&words[1..]).await; continue; }
+                Some(_) => { }
+            }
 
-impl Default for ItemEdit {
-    fn default() -> Self {
-        Self {
-            name: Default::default(),
-            description: Default::default(),
-            quantity: Default::default(),
+            let words = shell::with_default_warehouse(words, self.default_warehouse.as_deref(), &names);
+            match cli::parse(&words) {
+                Ok(matches) => {
+                    match matches.command {
+                        Some(command) if !matches.help => { self.run(command, &matches).await; }
+                        command => { Deposito::help(command); }
+                    }
+                }
+                Err(err) => { cli::print_error(&err, &words); }
+            }
+
+            names = self.load_names().await;
+            if let Some(warehouse_name) = &self.default_warehouse {
+                if !names.warehouses.contains(warehouse_name) {
+                    println!("The `{}` warehouse doesn't exist anymore, no warehouse in use.", style::bold(warehouse_name));
+                    self.default_warehouse = None;
+                }
+            }
         }
     }
-}
 
-#[derive(Debug)]
-struct CliOption {
-    name: String,
-    value: Option<String>,
-}
+    fn shell_help() {
+        Deposito::help(None);
+        println!();
+        println!("{}", style::bold("Shell commands:"));
+        let entries: Vec<(String, &str)> = commands::SHELL_COMMANDS.iter().map(|(term, description)| (term.to_string(), *description)).collect();
+        cli::print_aligned(&entries);
+    }
 
-impl Default for CliOption {
-    fn default() -> Self {
-        Self {
-            name: Default::default(),
-            value: Default::default()
+    async fn use_warehouse(&mut self, args: &[String]) {
+        match args {
+            [] => {
+                match &self.default_warehouse {
+                    Some(warehouse_name) => { println!("Using the `{}` warehouse.", style::bold(warehouse_name)); }
+                    None => { println!("No warehouse in use."); }
+                }
+            }
+            [warehouse_name] if warehouse_name == "-" => {
+                self.default_warehouse = None;
+                println!("No warehouse in use.");
+            }
+            [warehouse_name] => {
+                let pool = match self.get_pool() {
+                    Some(pool) => { pool }
+                    None => { return; }
+                };
+
+                if Deposito::check_warehouse(pool, warehouse_name).await {
+                    println!("Using the `{}` warehouse.", style::bold(warehouse_name));
+                    self.default_warehouse = Some(warehouse_name.clone());
+                }
+            }
+            _ => { report_error!("`{}` takes a single warehouse name.", style::bold("use")); }
         }
     }
-}
 
-#[derive(Debug, Error)]
-enum CliOptionError {
-    #[error("An option must start with a single or a double hyphen ('-').")]
-    MustStartWithHyphen,
+    // Names offered by tab completion in the shell
+    async fn load_names(&mut self) -> Names {
+        let pool = match self.db_pool.as_ref() {
+            Some(pool) => { pool }
+            None => { return Names::default(); }
+        };
 
-    #[error("Provided string is too short to be an option.")]
-    TooShort,
-}
+        let warehouses = sqlx::query_scalar!(r#"SELECT name FROM warehouses ORDER BY name;"#)
+            .fetch_all(pool)
+            .await
+            .unwrap_or_default();
 
-impl TryFrom<String> for CliOption {
-    type Error = CliOptionError;
+        let items = sqlx::query!(r#"SELECT warehouses.name AS warehouse, items.name AS item FROM items JOIN warehouses ON items.warehouse_id = warehouses.id ORDER BY items.name;"#)
+            .fetch_all(pool)
+            .await
+            .map(|rows| rows.into_iter().map(|row| (row.warehouse, row.item)).collect())
+            .unwrap_or_default();
 
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        let mut cli_option = CliOption::default();
+        Names { warehouses, items }
+    }
 
-        // Count the hyphens (max 2)
-        let mut hyphens = 0;
-        match value.get(0..2) {
-            Some(chars) => {
-                for char in chars.chars() {
-                    if char == '-' { hyphens += 1; }
-                }
+    pub async fn tui(&mut self, warehouse_name: Option<&str>) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        if let Some(warehouse_name) = warehouse_name {
+            if !Deposito::check_warehouse(pool, warehouse_name).await {
+                return;
             }
-            None => { return Err(CliOptionError::TooShort); }
         }
 
-        // Check if the option correctly begins with `-` or `--`
-        if hyphens == 0 {
-            return Err(CliOptionError::MustStartWithHyphen);
+        let mut app = App::new();
+        match Deposito::tui_warehouses(pool).await {
+            Ok(warehouses) => { app.set_warehouses(warehouses); }
+            Err(err) => { report_error!("Unable to load warehouses. Reason: {}", err); return; }
         }
-
-        // Check if conatins a value for the option (separated by `=`)
-        if let Some(eq_index) = value.find('=') {
-            if let Some(name) = value.get(hyphens..eq_index) {
-                cli_option.name = name.to_string();
-            }
-
-            if let Some(val) = value.get(eq_index+1..value.len()) {
-                cli_option.value = Some(val.to_string());
-            }
-        } else {
-            if let Some(name) = value.get(hyphens..value.len()) {
-                cli_option.name = name.to_string();
+        if let Some(warehouse_name) = warehouse_name {
+            app.select_warehouse(warehouse_name);
+        }
+        if let Some(warehouse_name) = app.warehouse_name().map(str::to_string) {
+            match Deposito::tui_items(pool, &warehouse_name).await {
+                Ok(items) => { app.set_items(items); }
+                Err(err) => { report_error!("Unable to list items in the `{}` warehouse. Reason: {}", style::bold(warehouse_name), err); return; }
             }
         }
 
-        Ok(cli_option)
-    }
-}
+        let mut backend = match tui::backend() {
+            Ok(backend) => { backend }
+            Err(err) => { report_error!("Unable to start the terminal interface. Reason: {}", err); return; }
+        };
 
+        // Nothing can be printed while the interface is up, so failures end up in its status
+        // line, or here once it's gone
+        let run_result: std::io::Result<()> = async {
+            loop {
+                let (width, height) = backend.size();
+                backend.draw(&app.render(width, height))?;
+
+                let key = match backend.read_key()? {
+                    Some(key) => { key }
+                    None => { break; }
+                };
+
+                let warehouse_name = match app.handle_key(key) {
+                    Action::None => { continue; }
+                    Action::Quit => { break; }
+                    Action::SelectWarehouse(warehouse_name) => { warehouse_name }
+                    Action::Edit { warehouse, item, edit } => {
+                        match Deposito::update_item(pool, &warehouse, &item, &edit).await {
+                            Ok(_) => {
+                                let change = match (&edit.name, edit.quantity) {
+                                    (Some(name), _) => format!("Renamed `{}` to `{}`.", item, name),
+                                    (None, Some(quantity)) => format!("`{}` now has a quantity of {}.", item, quantity),
+                                    (None, None) => format!("Changed the description of `{}`.", item),
+                                };
+                                app.notice(change);
+                            }
+                            Err(err) => {
+                                // Item names are unique within a warehouse
+                                let exists = err.as_database_error().and_then(|err| err.code()).is_some_and(|code| code == "2067");
+                                match (exists, &edit.name) {
+                                    (true, Some(name)) => { app.error(format!("Item `{}` already exists.", name)); }
+                                    _ => { app.error(format!("Unable to edit `{}`. Reason: {}", item, err)); }
+                                }
+                            }
+                        }
 
-#[tokio::main(flavor = "current_thread")]
-async fn main() {
-    let mut cli_args = std::env::args();
-    // Discard executable path
-    cli_args.next();
+                        // Another warehouse may have changed its critical items as well
+                        match Deposito::tui_warehouses(pool).await {
+                            Ok(warehouses) => { app.set_warehouses(warehouses); }
+                            Err(err) => { app.error(format!("Unable to load warehouses. Reason: {}", err)); }
+                        }
+                        warehouse
+                    }
+                };
 
-    let command = match cli_args.next() {
-        Some(command) => { command }
-        None => { Deposito::help(None); return; }
-    };
+                match Deposito::tui_items(pool, &warehouse_name).await {
+                    Ok(items) => { app.set_items(items); }
+                    Err(err) => { app.error(format!("Unable to list items in the `{}` warehouse. Reason: {}", warehouse_name, err)); }
+                }
+            }
 
+            backend.finish()
+        }.await;
 
-    let mut args: Vec<String> = Vec::new();
-    let mut options: Vec<CliOption> = Vec::new();
-    for arg in cli_args.into_iter() {
-        if arg.starts_with('-') {
-            match CliOption::try_from(arg.clone()) {
-                Ok(option) => { options.push(option); }
-                Err(_) => { println!("Unknown option `\x1b[1m{}\x1b[0m`.", arg); return; }
-                
-            }
-        } else {
-            args.push(arg);
+        // Gives the terminal back before saying anything
+        drop(backend);
+        if let Err(err) = run_result {
+            report_error!("Unable to run the terminal interface. Reason: {}", err);
         }
     }
 
-    for option in options.iter() {
-        if option.name == "h" || option.name == "help" {
-            Deposito::help(Some(&command));
-            return;
+    async fn tui_warehouses(pool: &sqlx::SqlitePool) -> Result<Vec<WarehouseRow>, sqlx::Error> {
+        let names = sqlx::query_scalar!(r#"SELECT name FROM warehouses ORDER BY name;"#)
+            .fetch_all(pool)
+            .await?;
+
+        let mut warehouses = Vec::new();
+        for name in names {
+            let critical = Deposito::evaluate_raport(pool, &name, true).await?
+                .iter()
+                .filter(|entry| matches!(entry.status, Status::Triggered(Severity::Critical)))
+                .count();
+            warehouses.push(WarehouseRow { name, critical });
         }
+        Ok(warehouses)
     }
 
-    // TODO: God, make it work more reasonably
-    // Check if a command requires the `deposito` directory to exist
-    let does_require_deposito = match command.as_str() {
-        "create"
-        | "list"
-        | "raport"
-        | "add"
-        | "rules"
-        | "edit"
-        | "remove"
-        | "view" => { true }
+    // Items of the warehouse along with the status of their rules
+    async fn tui_items(pool: &sqlx::SqlitePool, warehouse_name: &str) -> Result<Vec<ItemRow>, sqlx::Error> {
+        let items = sqlx::query!(r#"SELECT items.* FROM items JOIN warehouses ON items.warehouse_id = warehouses.id WHERE warehouses.name = ?;"#, warehouse_name)
+            .fetch_all(pool)
+            .await?;
 
-        "init" => { false }
+        let mut statuses: HashMap<String, Status> = Deposito::evaluate_raport(pool, warehouse_name, true).await?
+            .into_iter()
+            .map(|entry| (entry.name, entry.status))
+            .collect();
 
-        _ => { println!("Unknown command `{}`.", command); return; }
-    };
+        let items = items.into_iter()
+            .map(|item| ItemRow { status: statuses.remove(&item.name), name: item.name, quantity: item.quantity, description: item.description })
+            .collect();
+        Ok(items)
+    }
 
-    let mut current_dir = PathBuf::new();
-    let mut deposito = Deposito::default();
-    if does_require_deposito {
-        current_dir = match std::env::current_dir() {
-            Ok(dir) => { dir }
-            Err(err) => { panic!("Unable to get current working directory. Reason: {}", err); }
+    // Reads a file, or stdin for `-`
+    fn read_input(path: &str) -> Option<String> {
+        let input_result = match path {
+            "-" => { std::io::read_to_string(std::io::stdin()) }
+            path => { std::fs::read_to_string(path) }
         };
 
-        match deposito.connect(&current_dir).await {
-            Ok(_) => { }
-            Err(err) => {
-                if let DepositoError::NoDepositoDir(_) = err {
-                    println!("Deposito directory doesn't exist. You can create one using \x1b[1m`deposito init`\x1b[0m");
-                    return;
-                }
+        match input_result {
+            Ok(input) => { Some(input) }
+            Err(err) => { report_error!("Unable to read `{}`. Reason: {}", style::bold(path), err); None }
+        }
+    }
 
-                println!("Unable to connect with the local SQLite deposito database. Reason: {}", err);
-            }
+    pub async fn export(&mut self, path: &str) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
         };
-    }
 
-    match command.as_str() {
-        "view" => { deposito.view().await }
-        "create" => {
-            if let None = args.get(0) {
-                println!("`\x1b[1mcreate\x1b[0m` requires a name for the new warehouse.");
-                return;
-            }
+        let warehouses_result = sqlx::query_scalar!(r#"SELECT name FROM warehouses ORDER BY id;"#)
+            .fetch_all(pool)
+            .await;
+
+        let mut warehouses: Vec<export::Warehouse> = match warehouses_result {
+            Ok(names) => { names.into_iter().map(|name| export::Warehouse { name, items: Vec::new() }).collect() }
+            Err(err) => { report_error!("Unable to list warehouses. Reason: {}", err); return; }
+        };
+
+        let items_result = sqlx::query!(r#"
+            SELECT
+                i.id,
+                w.name AS warehouse,
+                i.name,
+                i.quantity,
+                i.description,
+                i.unit,
+                i.tags,
+                r.gets_below_quantity AS "gets_below_quantity?",
+                r.expression AS "expression?",
+                r.severity AS "severity?"
+            FROM
+                items i
+                JOIN warehouses w ON w.id = i.warehouse_id
+                LEFT JOIN rules r ON r.item_id = i.id
+            ORDER BY
+                i.id;
+        "#)
+            .fetch_all(pool)
+            .await;
+
+        let items = match items_result {
+            Ok(items) => { items }
+            Err(err) => { report_error!("Unable to list items. Reason: {}", err); return; }
+        };
+
+        let movements_result = sqlx::query!(r#"SELECT item_id, delta, created_at FROM movements ORDER BY id;"#)
+            .fetch_all(pool)
+            .await;
+
+        // By item id, names repeat across warehouses
+        let mut movements: HashMap<i64, Vec<export::Movement>> = HashMap::new();
+        match movements_result {
+            Ok(rows) => {
+                for row in rows {
+                    movements.entry(row.item_id).or_default().push(export::Movement { delta: row.delta, created_at: row.created_at });
+                }
+            }
+            Err(err) => { report_error!("Unable to list movements. Reason: {}", err); return; }
+        }
+
+        let (mut item_count, mut rule_count, movement_count) = (0, 0, movements.values().map(Vec::len).sum::<usize>());
+        for item in items {
+            let rule = match (item.gets_below_quantity, item.expression, item.severity) {
+                (None, None, _) => { None }
+                (gets_below_quantity, expression, severity) => {
+                    rule_count += 1;
+                    Some(export::Rule { gets_below_quantity, expression, severity: severity.unwrap_or_else(|| Severity::Critical.to_string()) })
+                }
+            };
+
+            if let Some(warehouse) = warehouses.iter_mut().find(|warehouse| warehouse.name == item.warehouse) {
+                item_count += 1;
+                warehouse.items.push(export::Item {
+                    tags: export::tags_to_list(item.tags.as_deref()),
+                    movements: movements.remove(&item.id).unwrap_or_default(),
+                    name: item.name,
+                    quantity: item.quantity,
+                    description: item.description,
+                    unit: item.unit,
+                    rule,
+                });
+            }
+        }
+
+        let warehouse_count = warehouses.len();
+        let document = export::Document { version: export::VERSION, exported_at: Some(raport::unix_now()), warehouses };
+        let json = match serde_json::to_string_pretty(&document) {
+            Ok(json) => { json }
+            Err(err) => { report_error!("Unable to export the workspace. Reason: {}", err); return; }
+        };
+
+        if path == "-" {
+            println!("{}", json);
+            return;
+        }
+
+        match std::fs::write(path, json + "\n") {
+            Ok(_) => { println!("Exported {} warehouse(s), {} item(s), {} rule(s) and {} movement(s) to `{}`.", warehouse_count, item_count, rule_count, movement_count, style::bold(path)); }
+            Err(err) => { report_error!("Unable to write `{}`. Reason: {}", style::bold(path), err); }
+        }
+    }
+
+    // Imports a document of `deposito export`, only the warehouse `only` of it if given
+    pub async fn import_json(&mut self, path: &str, only: Option<&str>, conflict: export::Conflict, dry_run: bool) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let input = match Deposito::read_input(path) {
+            Some(input) => { input }
+            None => { return; }
+        };
+
+        let mut document = match export::read(&input) {
+            Ok(document) => { document }
+            Err(errors) => {
+                for err in errors {
+                    report_error!("{}", err);
+                }
+                println!("Nothing was imported.");
+                return;
+            }
+        };
+
+        if let Some(only) = only {
+            if !document.warehouses.iter().any(|warehouse| warehouse.name == only) {
+                let suggestion = suggest::closest(only, document.warehouses.iter().map(|warehouse| warehouse.name.as_str())).map(|suggestion| style::bold(suggestion).to_string());
+                report_error!("The document has no `{}` warehouse.{}", style::bold(only), suggest::did_you_mean(&suggestion));
+                return;
+            }
+            document.warehouses.retain(|warehouse| warehouse.name == only);
+        }
+
+        let warehouses_result = sqlx::query_scalar!(r#"SELECT name FROM warehouses;"#)
+            .fetch_all(pool)
+            .await;
+
+        let warehouses: std::collections::HashSet<String> = match warehouses_result {
+            Ok(names) => { names.into_iter().collect() }
+            Err(err) => { report_error!("Unable to list warehouses. Reason: {}", err); return; }
+        };
+
+        let items_result = sqlx::query!(r#"SELECT items.name, warehouses.name AS warehouse FROM items JOIN warehouses ON items.warehouse_id = warehouses.id;"#)
+            .fetch_all(pool)
+            .await;
+
+        let existing: std::collections::HashSet<(String, String)> = match items_result {
+            Ok(items) => { items.into_iter().map(|item| (item.warehouse, item.name)).collect() }
+            Err(err) => { report_error!("Unable to list items. Reason: {}", err); return; }
+        };
+
+        let plan = match export::plan(&document, &warehouses, &existing, conflict) {
+            Ok(plan) => { plan }
+            Err(errors) => {
+                for err in errors {
+                    report_error!("{}", err);
+                }
+                println!("Nothing was imported, use `--on-conflict=skip` or `--on-conflict=overwrite` to import it anyway.");
+                return;
+            }
+        };
+
+        for (name, warehouse) in plan.skipped.iter() {
+            println!("Skipping `{}`, it already exists in the `{}` warehouse.", style::bold(name), style::bold(warehouse));
+        }
+
+        let imported = plan.new_items.iter().chain(plan.replaced.iter());
+        let rules = imported.clone().filter(|(_, item)| item.rule.is_some()).count();
+        let movements: usize = imported.map(|(_, item)| item.movements.len()).sum();
+        let summary = format!("{} new warehouse(s), {} new item(s), {} replaced, {} skipped, {} rule(s) and {} movement(s)",
+            plan.new_warehouses.len(), plan.new_items.len(), plan.replaced.len(), plan.skipped.len(), rules, movements);
+
+        if dry_run {
+            println!("Dry run, nothing was imported: {}.", summary);
+            return;
+        }
+
+        match Deposito::apply_document(pool, &plan).await {
+            Ok(_) => { println!("Imported {}.", summary); }
+            Err(err) => { report_error!("Unable to import the document, nothing was imported. Reason: {}", err); }
+        }
+    }
+
+    // Applies all of the plan or none of it
+    async fn apply_document(pool: &sqlx::SqlitePool, plan: &export::Plan<'_>) -> Result<(), sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        Deposito::in_savepoint(&mut connection, "import_document", async |connection| {
+            for warehouse_name in plan.new_warehouses.iter() {
+                sqlx::query!("INSERT INTO warehouses (id, name) VALUES (NULL, $1);", warehouse_name)
+                    .execute(&mut *connection)
+                    .await?;
+            }
+
+            // Replaced items go away entirely first, movements are deleted along with them
+            for (warehouse_name, item) in plan.replaced.iter() {
+                sqlx::query!("
+                    DELETE FROM rules WHERE rules.item_id = (
+                        SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE warehouses.name = $1 AND items.name = $2);",
+                    warehouse_name, item.name)
+                    .execute(&mut *connection)
+                    .await?;
+                sqlx::query!("
+                    DELETE FROM items WHERE items.warehouse_id = (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $1) AND items.name = $2;",
+                    warehouse_name, item.name)
+                    .execute(&mut *connection)
+                    .await?;
+            }
+
+            for (warehouse_name, item) in plan.new_items.iter().chain(plan.replaced.iter()) {
+                let tags = export::tags_from_list(&item.tags);
+                sqlx::query!(
+                    r#"INSERT INTO items (id, warehouse_id, name, description, quantity, unit, tags) VALUES (NULL, (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $1), $2, $3, $4, $5, $6);"#,
+                    warehouse_name, item.name, item.description, item.quantity, item.unit, tags)
+                    .execute(&mut *connection)
+                    .await?;
+
+                if let Some(rule) = item.rule.as_ref() {
+                    // Checked by `export::read`
+                    let severity = rule.severity.parse::<Severity>().unwrap_or(Severity::Critical);
+                    let severity_name = severity.as_str();
+                    sqlx::query!("
+                        INSERT INTO rules (id, item_id, gets_below_quantity, expression, severity) VALUES (
+                            NULL, (SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE warehouses.name = $1 AND items.name = $2), $3, $4, $5);",
+                        warehouse_name, item.name, rule.gets_below_quantity, rule.expression, severity_name)
+                        .execute(&mut *connection)
+                        .await?;
+                }
+
+                for movement in item.movements.iter() {
+                    sqlx::query!("
+                        INSERT INTO movements (id, item_id, delta, created_at) VALUES (
+                            NULL, (SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE warehouses.name = $1 AND items.name = $2), $3, $4);",
+                        warehouse_name, item.name, movement.delta, movement.created_at)
+                        .execute(&mut *connection)
+                        .await?;
+                }
+            }
+
+            Ok(())
+        }).await
+    }
+
+    pub async fn import_csv(&mut self, warehouse_name: &str, path: &str, mode: import::Mode, columns: Option<&str>, dry_run: bool) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        if !Deposito::check_warehouse(pool, warehouse_name).await {
+            return;
+        }
+
+        let input = match Deposito::read_input(path) {
+            Some(input) => { input }
+            None => { return; }
+        };
+
+        let rows = match import::read(&input, columns) {
+            Ok(rows) => { rows }
+            Err(errors) => {
+                for err in errors {
+                    report_error!("{}", err);
+                }
+                println!("Nothing was imported.");
+                return;
+            }
+        };
+
+        let existing_result = sqlx::query!(r#"
+            SELECT
+                i.name,
+                i.quantity,
+                i.description,
+                i.unit,
+                i.tags,
+                r.gets_below_quantity AS "min?",
+                e.expression AS "expression?"
+            FROM
+                items i
+                JOIN warehouses w ON w.id = i.warehouse_id
+                LEFT JOIN rules r ON r.item_id = i.id AND r.expression IS NULL
+                LEFT JOIN rules e ON e.item_id = i.id AND e.expression IS NOT NULL
+            WHERE
+                w.name = $1;
+        "#, warehouse_name)
+            .fetch_all(pool)
+            .await;
+
+        let existing = match existing_result {
+            Ok(items) => {
+                items.into_iter()
+                    .map(|item| import::Item { name: item.name, quantity: item.quantity, description: item.description, min: item.min, unit: item.unit, tags: item.tags, expression: item.expression })
+                    .collect::<Vec<_>>()
+            }
+            Err(err) => { report_error!("Unable to list items in the `{}` warehouse. Reason: {}", style::bold(warehouse_name), err); return; }
+        };
+
+        let kept_rules = import::kept_rules(&rows, &existing, mode);
+        let changes = import::plan(rows, existing, mode);
+
+        let mut table = Table::new(vec![Column::new(""), Column::new("Item"), Column::new("Quantity").right(), Column::new("Changes").truncate()]);
+        let (mut new, mut updated, mut removed, mut unchanged) = (0, 0, 0, 0);
+        for change in changes.iter() {
+            match change {
+                Change::New(item) => {
+                    new += 1;
+                    table.row(vec![Cell::colored("+", Color::Green), Cell::new(&item.name), Cell::new(item.quantity), Cell::new("new")]);
+                }
+                Change::Update { before, after } => {
+                    updated += 1;
+                    let quantity = match before.quantity == after.quantity {
+                        true => { after.quantity.to_string() }
+                        false => { format!("{} → {}", before.quantity, after.quantity) }
+                    };
+                    let fields = import::Change::changed_fields(before, after);
+                    table.row(vec![Cell::colored("~", Color::Yellow), Cell::new(&after.name), Cell::new(quantity), Cell::new(fields.join(", "))]);
+                }
+                Change::Remove(item) => {
+                    removed += 1;
+                    table.row(vec![Cell::colored("-", Color::Red), Cell::new(&item.name), Cell::new(item.quantity), Cell::new("removed")]);
+                }
+                Change::Unchanged => { unchanged += 1; }
+            }
+        }
+
+        let summary = format!("{} new, {} updated, {} removed, {} unchanged", new, updated, removed, unchanged);
+        let nothing_to_import = new + updated + removed == 0;
+        if !nothing_to_import {
+            table.print();
+            println!();
+        }
+
+        for (name, expression) in kept_rules.iter() {
+            println!("Skipped the min of `{}`, its rule `{}` is kept.", style::bold(name), expression);
+        }
+        if nothing_to_import {
+            println!("Nothing to import, the `{}` warehouse already has everything ({}).", style::bold(warehouse_name), summary);
+            return;
+        }
+        if dry_run {
+            println!("Dry run, nothing was imported into the `{}` warehouse: {}.", style::bold(warehouse_name), summary);
+            return;
+        }
+
+        match Deposito::apply_import(pool, warehouse_name, &changes).await {
+            Ok(_) => { println!("Imported into the `{}` warehouse: {}.", style::bold(warehouse_name), summary); }
+            Err(err) => { report_error!("Unable to import into the `{}` warehouse, nothing was imported. Reason: {}", style::bold(warehouse_name), err); }
+        }
+    }
+
+    // Applies all of the changes or none of them
+    async fn apply_import(pool: &sqlx::SqlitePool, warehouse_name: &str, changes: &[Change]) -> Result<(), sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        Deposito::in_savepoint(&mut connection, "import", async |connection| {
+            for change in changes.iter() {
+                let (item, delta, min_changed) = match change {
+                    Change::New(item) => {
+                        sqlx::query!(
+                            r#"INSERT INTO items (id, warehouse_id, name, description, quantity, unit, tags) VALUES (NULL, (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $1), $2, $3, $4, $5, $6);"#,
+                            warehouse_name, item.name, item.description, item.quantity, item.unit, item.tags)
+                            .execute(&mut *connection)
+                            .await?;
+                        (item, item.quantity, item.min.is_some())
+                    }
+                    Change::Update { before, after } => {
+                        sqlx::query!("
+                            UPDATE items SET
+                                quantity = $3,
+                                description = $4,
+                                unit = $5,
+                                tags = $6
+                            WHERE
+                                items.warehouse_id = (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $1)
+                                AND items.name = $2;",
+                            warehouse_name, after.name, after.quantity, after.description, after.unit, after.tags)
+                            .execute(&mut *connection)
+                            .await?;
+                        (after, after.quantity - before.quantity, after.min != before.min)
+                    }
+                    Change::Remove(item) => {
+                        sqlx::query!("
+                            DELETE FROM rules WHERE rules.item_id = (SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE warehouses.name = $1 AND items.name = $2);",
+                            warehouse_name, item.name)
+                            .execute(&mut *connection)
+                            .await?;
+                        sqlx::query!("
+                            DELETE FROM items WHERE items.warehouse_id = (SELECT warehouses.id FROM warehouses WHERE warehouses.name = $1) AND items.name = $2;",
+                            warehouse_name, item.name)
+                            .execute(&mut *connection)
+                            .await?;
+                        continue;
+                    }
+                    Change::Unchanged => { continue; }
+                };
+
+                if delta != 0 {
+                    sqlx::query!("
+                        INSERT INTO movements (id, item_id, delta) VALUES (NULL, (SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE warehouses.name = $1 AND items.name = $2), $3);",
+                        warehouse_name, item.name, delta)
+                        .execute(&mut *connection)
+                        .await?;
+                }
+
+                // A minimum replaces the item's threshold, expression rules are left alone
+                if let (true, Some(min)) = (min_changed, item.min) {
+                    sqlx::query!("
+                        INSERT INTO rules (id, item_id, gets_below_quantity) VALUES (NULL, (SELECT items.id FROM items JOIN warehouses ON warehouses.id = items.warehouse_id WHERE warehouses.name = $1 AND items.name = $2), $3)
+                        ON CONFLICT (item_id) DO UPDATE SET gets_below_quantity = excluded.gets_below_quantity WHERE rules.expression IS NULL;",
+                        warehouse_name, item.name, min)
+                        .execute(&mut *connection)
+                        .await?;
+                }
+            }
+
+            Ok(())
+        }).await
+    }
+
+    // Copies the database with `VACUUM INTO`, which reads it in a single transaction, so
+    // the copy is consistent even while other processes are writing to it. Into a
+    // directory (`deposito/backups` by default) the copy gets a timestamped name and the
+    // backups beyond the newest `keep` are removed.
+    pub async fn backup(&mut self, path: Option<&str>, keep: Option<usize>) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let directory = match path {
+            None => { Some(Path::new(DIR_NAME).join(backup::DIR_NAME)) }
+            Some(path) if path.ends_with(std::path::MAIN_SEPARATOR) || Path::new(path).is_dir() => { Some(PathBuf::from(path)) }
+            Some(_) => { None }
+        };
+
+        let target = match directory.as_ref() {
+            Some(directory) => {
+                if let Err(err) = std::fs::create_dir_all(directory) {
+                    report_error!("Unable to create the backup directory `{}`. Reason: {}", style::bold(directory.display()), err);
+                    return;
+                }
+
+                let now = raport::unix_now();
+                let mut attempt = 1;
+                loop {
+                    let target = directory.join(backup::file_name(now, attempt));
+                    if !target.exists() { break target; }
+                    attempt += 1;
+                }
+            }
+            None => {
+                if keep.is_some() {
+                    report_error!("`--keep` only applies to backups into a directory.");
+                    return;
+                }
+
+                let target = PathBuf::from(path.unwrap_or_default());
+                if target.exists() {
+                    report_error!("`{}` already exists, backups never overwrite files.", style::bold(target.display()));
+                    return;
+                }
+                target
+            }
+        };
+
+        if let Err(err) = sqlx::query("VACUUM INTO ?;").bind(target.to_string_lossy()).execute(pool).await {
+            report_error!("Unable to back up the database. Reason: {}", err);
+            return;
+        }
+        println!("Backed up the database to `{}`.", style::bold(target.display()));
+
+        let Some(directory) = directory else { return; };
+        let names: Vec<String> = match std::fs::read_dir(&directory) {
+            Ok(entries) => { entries.filter_map(|entry| entry.ok()?.file_name().into_string().ok()).collect() }
+            Err(err) => { report_error!("Unable to rotate the backups in `{}`. Reason: {}", style::bold(directory.display()), err); return; }
+        };
+
+        let keep = keep.unwrap_or(backup::DEFAULT_KEEP);
+        let expired = backup::expired(&names, keep);
+        for name in expired.iter() {
+            if let Err(err) = std::fs::remove_file(directory.join(name)) {
+                report_error!("Unable to remove the old backup `{}`. Reason: {}", style::bold(name), err);
+                return;
+            }
+        }
+        if !expired.is_empty() {
+            println!("Removed {} old backup(s), keeping the newest {}.", expired.len(), keep);
+        }
+    }
+
+    // Replaces the contents of the database with a backup. The backup is checked and
+    // upgraded in a copy first, then every table is emptied and filled from the copy in
+    // one savepoint, so other processes never see a half restored or missing database.
+    // The current contents are backed up beforehand.
+    pub async fn restore(&mut self, path: &str) {
+        if let Err(err) = std::fs::metadata(path) {
+            report_error!("Unable to read `{}`. Reason: {}", style::bold(path), err);
+            return;
+        }
+
+        let copy = Path::new(DIR_NAME).join("restore.db");
+        let _ = std::fs::remove_file(&copy);
+
+        let options = sqlx::sqlite::SqliteConnectOptions::new().filename(path).read_only(true);
+        let checked = match sqlx::SqlitePool::connect_with(options).await {
+            Ok(backup_pool) => {
+                let result = match Deposito::check_backup(&backup_pool).await {
+                    Ok(_) => {
+                        sqlx::query("VACUUM INTO ?;").bind(copy.to_string_lossy()).execute(&backup_pool).await
+                            .map(|_| ())
+                            .map_err(|err| format!("Unable to copy `{}`. Reason: {}", style::bold(path), err))
+                    }
+                    Err(reason) => { Err(format!("Can't restore `{}`, {}.", style::bold(path), reason)) }
+                };
+                backup_pool.close().await;
+                result
+            }
+            Err(err) => { Err(format!("Unable to open `{}`. Reason: {}", style::bold(path), err)) }
+        };
+
+        if let Err(message) = checked {
+            report_error!("{}", message);
+            return;
+        }
+
+        let upgraded = match sqlx::SqlitePool::connect_with(sqlx::sqlite::SqliteConnectOptions::new().filename(&copy)).await {
+            Ok(copy_pool) => {
+                let result = Deposito::upgrade(&copy_pool).await;
+                copy_pool.close().await;
+                result
+            }
+            Err(err) => { Err(err) }
+        };
+
+        if let Err(err) = upgraded {
+            report_error!("Unable to upgrade `{}` to the current schema. Reason: {}", style::bold(path), err);
+            let _ = std::fs::remove_file(&copy);
+            return;
+        }
+
+        let errors = ERRORS.load(Ordering::Relaxed);
+        self.backup(None, Some(0)).await;
+        if ERRORS.load(Ordering::Relaxed) != errors {
+            println!("Nothing was restored.");
+            let _ = std::fs::remove_file(&copy);
+            return;
+        }
+
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let restore_result = Deposito::replace_tables(pool, &copy).await;
+        let _ = std::fs::remove_file(&copy);
+        match restore_result {
+            Ok(_) => { println!("Restored the database from `{}`.", style::bold(path)); }
+            Err(err) => { report_error!("Unable to restore the database, nothing was changed. Reason: {}", err); }
+        }
+    }
+
+    // Whether a file is a deposito database this version can read, with the reason if not
+    async fn check_backup(pool: &sqlx::SqlitePool) -> Result<(), String> {
+        let integrity: Result<String, sqlx::Error> = sqlx::query_scalar("PRAGMA quick_check;").fetch_one(pool).await;
+        match integrity {
+            Ok(result) if result == "ok" => { }
+            Ok(result) => { return Err(format!("it is damaged ({})", result)); }
+            Err(err) => { return Err(format!("it isn't a database ({})", err)); }
+        }
+
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version;").fetch_one(pool).await.map_err(|err| err.to_string())?;
+        if version > SCHEMA_VERSION {
+            return Err(format!("it was made by a newer version of deposito (schema version {}, this one knows up to {})", version, SCHEMA_VERSION));
+        }
+
+        let tables: Vec<String> = sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table';")
+            .fetch_all(pool)
+            .await
+            .map_err(|err| err.to_string())?;
+        match backup::REQUIRED.iter().find(|table| !tables.iter().any(|name| name == *table)) {
+            Some(table) => { Err(format!("it isn't a deposito database (there is no `{}` table)", table)) }
+            None => { Ok(()) }
+        }
+    }
+
+    async fn replace_tables(pool: &sqlx::SqlitePool, copy: &Path) -> Result<(), sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        sqlx::query("ATTACH DATABASE ? AS restored;").bind(copy.to_string_lossy()).execute(&mut *connection).await?;
+        let restore_result = Deposito::in_savepoint(&mut connection, "restore", async |connection| {
+            for (table, _) in backup::TABLES.iter().rev() {
+                sqlx::query(&format!("DELETE FROM main.{};", table)).execute(&mut *connection).await?;
+            }
+            for (table, columns) in backup::TABLES.iter() {
+                sqlx::query(&format!("INSERT INTO main.{0} ({1}) SELECT {1} FROM restored.{0};", table, columns))
+                    .execute(&mut *connection)
+                    .await?;
+            }
+
+            Ok(())
+        }).await;
+        sqlx::query("DETACH DATABASE restored;").execute(&mut *connection).await?;
+        restore_result
+    }
+
+    pub async fn snapshot_create(&mut self, label: &str) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        if label == "now" {
+            report_error!("`now` stands for the current state in `deposito diff`, pick another label.");
+            return;
+        }
+
+        let exists_result = sqlx::query_scalar!(r#"SELECT COUNT(*) FROM snapshots WHERE label = $1;"#, label)
+            .fetch_one(pool)
+            .await;
+
+        match exists_result {
+            Ok(0) => { }
+            Ok(_) => { report_error!("Snapshot `{}` already exists.", style::bold(label)); return; }
+            Err(err) => { report_error!("Unable to look up snapshots. Reason: {}", err); return; }
+        }
+
+        match Deposito::take_snapshot(pool, label).await {
+            Ok(count) => { println!("Snapshot `{}` of {} item(s) taken.", style::bold(label), count); }
+            Err(err) => { report_error!("Unable to take the snapshot `{}`. Reason: {}", style::bold(label), err); }
+        }
+    }
+
+    // Copies every item with its rule into the snapshot, all of them or none, returning how
+    // many there were
+    async fn take_snapshot(pool: &sqlx::SqlitePool, label: &str) -> Result<u64, sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        Deposito::in_savepoint(&mut connection, "snapshot", async |connection| {
+            sqlx::query!("INSERT INTO snapshots (id, label) VALUES (NULL, $1);", label)
+                .execute(&mut *connection)
+                .await?;
+
+            let items = sqlx::query!("
+                INSERT INTO snapshot_items (id, snapshot_id, warehouse, name, quantity, gets_below_quantity, expression, severity)
+                SELECT NULL, (SELECT id FROM snapshots WHERE label = $1), w.name, i.name, i.quantity, r.gets_below_quantity, r.expression, r.severity
+                FROM
+                    items i
+                    JOIN warehouses w ON w.id = i.warehouse_id
+                    LEFT JOIN rules r ON r.item_id = i.id;",
+                label)
+                .execute(&mut *connection)
+                .await?;
+
+            Ok(items.rows_affected())
+        }).await
+    }
+
+    pub async fn snapshot_list(&mut self) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let snapshots_result = sqlx::query!(r#"
+            SELECT s.label, s.created_at, COUNT(si.id) AS "items!: i64", COUNT(DISTINCT si.warehouse) AS "warehouses!: i64"
+            FROM snapshots s LEFT JOIN snapshot_items si ON si.snapshot_id = s.id
+            GROUP BY s.id
+            ORDER BY s.created_at, s.id;
+        "#)
+            .fetch_all(pool)
+            .await;
+
+        let snapshots = match snapshots_result {
+            Ok(snapshots) => { snapshots }
+            Err(err) => { report_error!("Unable to list snapshots. Reason: {}", err); return; }
+        };
+
+        if snapshots.is_empty() {
+            println!("No snapshots. You can take one using {}", style::bold("`deposito snapshot create <label>`"));
+            return;
+        }
+
+        println!("{}", style::bold("Snapshots:"));
+        let mut table = Table::new(vec![Column::new("Label").truncate(), Column::new("Taken"), Column::new("Warehouses").right(), Column::new("Items").right()]);
+        for snapshot in snapshots {
+            table.row(vec![Cell::new(snapshot.label), Cell::new(raport::format_timestamp(snapshot.created_at)), Cell::new(snapshot.warehouses), Cell::new(snapshot.items)]);
+        }
+        table.print();
+    }
+
+    pub async fn snapshot_remove(&mut self, label: &str) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        if !Deposito::check_snapshot(pool, label).await {
+            return;
+        }
+
+        // Its items go along with it
+        match sqlx::query!("DELETE FROM snapshots WHERE label = $1;", label).execute(pool).await {
+            Ok(_) => { println!("Snapshot `{}` removed.", style::bold(label)); }
+            Err(err) => { report_error!("Unable to remove the snapshot `{}`. Reason: {}", style::bold(label), err); }
+        }
+    }
+
+    // Whether the snapshot exists, reporting it (with a suggestion) if it doesn't
+    async fn check_snapshot(pool: &sqlx::SqlitePool, label: &str) -> bool {
+        let labels = match sqlx::query_scalar!(r#"SELECT label FROM snapshots;"#).fetch_all(pool).await {
+            Ok(labels) => { labels }
+            Err(err) => { report_error!("Unable to look up the snapshot `{}`. Reason: {}", style::bold(label), err); return false; }
+        };
+
+        if labels.iter().any(|existing| existing == label) {
+            return true;
+        }
+
+        match suggest::closest(label, labels.iter().map(String::as_str)) {
+            Some(suggestion) => { report_error!("Snapshot `{}` not found. Did you mean `{}`?", style::bold(label), style::bold(suggestion)); }
+            None => { report_error!("Snapshot `{}` not found.", style::bold(label)); }
+        }
+        false
+    }
+
+    pub async fn template_create(&mut self, template_name: &str, warehouse_name: &str) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let exists_result = sqlx::query_scalar!(r#"SELECT COUNT(*) FROM templates WHERE name = $1;"#, template_name)
+            .fetch_one(pool)
+            .await;
+
+        match exists_result {
+            Ok(0) => { }
+            Ok(_) => { report_error!("Template `{}` already exists.", style::bold(template_name)); return; }
+            Err(err) => { report_error!("Unable to look up templates. Reason: {}", err); return; }
+        }
+
+        if !Deposito::check_warehouse(pool, warehouse_name).await {
+            return;
+        }
+
+        match Deposito::make_template(pool, template_name, warehouse_name).await {
+            Ok(count) => {
+                println!("Template `{}` of {} item(s) made from the `{}` warehouse.", style::bold(template_name), count, style::bold(warehouse_name));
+                if template_name == DEFAULT_TEMPLATE {
+                    println!("New warehouses start with it, unless `create` is given --from, --template or --empty.");
+                }
+            }
+            Err(err) => { report_error!("Unable to make the template `{}`. Reason: {}", style::bold(template_name), err); }
+        }
+    }
+
+    // Copies the items of the warehouse with their rules into the template, all of them or
+    // none, returning how many there were. Archived items are left out.
+    async fn make_template(pool: &sqlx::SqlitePool, template_name: &str, warehouse_name: &str) -> Result<u64, sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        Deposito::in_savepoint(&mut connection, "template", async |connection| {
+            sqlx::query!("INSERT INTO templates (id, name) VALUES (NULL, $1);", template_name)
+                .execute(&mut *connection)
+                .await?;
+
+            let items = sqlx::query!("
+                INSERT INTO template_items (id, template_id, name, description, quantity, unit, tags, gets_below_quantity, expression, severity)
+                SELECT NULL, (SELECT id FROM templates WHERE name = $1), i.name, i.description, i.quantity, i.unit, i.tags, r.gets_below_quantity, r.expression, r.severity
+                FROM
+                    items i
+                    JOIN warehouses w ON w.id = i.warehouse_id
+                    LEFT JOIN rules r ON r.item_id = i.id
+                WHERE w.name = $2 AND i.archived_at IS NULL;",
+                template_name, warehouse_name)
+                .execute(&mut *connection)
+                .await?;
+
+            Ok(items.rows_affected())
+        }).await
+    }
+
+    pub async fn template_list(&mut self) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let templates_result = sqlx::query!(r#"
+            SELECT t.name, t.created_at, COUNT(ti.id) AS "items!: i64", COUNT(ti.severity) AS "rules!: i64"
+            FROM templates t LEFT JOIN template_items ti ON ti.template_id = t.id
+            GROUP BY t.id
+            ORDER BY t.name;
+        "#)
+            .fetch_all(pool)
+            .await;
+
+        let templates = match templates_result {
+            Ok(templates) => { templates }
+            Err(err) => { report_error!("Unable to list templates. Reason: {}", err); return; }
+        };
+
+        if templates.is_empty() {
+            println!("No templates. You can make one using {}", style::bold("`deposito template create <name> <warehouse>`"));
+            return;
+        }
+
+        println!("{}", style::bold("Templates:"));
+        let mut table = Table::new(vec![Column::new("Name").truncate(), Column::new("Made"), Column::new("Items").right(), Column::new("Rules").right()]);
+        for template in templates {
+            table.row(vec![Cell::new(template.name), Cell::new(raport::format_timestamp(template.created_at)), Cell::new(template.items), Cell::new(template.rules)]);
+        }
+        table.print();
+    }
+
+    pub async fn template_remove(&mut self, template_name: &str) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        if !Deposito::check_template(pool, template_name).await {
+            return;
+        }
+
+        // Its items go along with it
+        match sqlx::query!("DELETE FROM templates WHERE name = $1;", template_name).execute(pool).await {
+            Ok(_) => { println!("Template `{}` removed.", style::bold(template_name)); }
+            Err(err) => { report_error!("Unable to remove the template `{}`. Reason: {}", style::bold(template_name), err); }
+        }
+    }
+
+    // Whether the template exists, reporting it (with a suggestion) if it doesn't
+    async fn check_template(pool: &sqlx::SqlitePool, template_name: &str) -> bool {
+        let names = match sqlx::query_scalar!(r#"SELECT name FROM templates;"#).fetch_all(pool).await {
+            Ok(names) => { names }
+            Err(err) => { report_error!("Unable to look up the template `{}`. Reason: {}", style::bold(template_name), err); return false; }
+        };
+
+        if names.iter().any(|existing| existing == template_name) {
+            return true;
+        }
+
+        match suggest::closest(template_name, names.iter().map(String::as_str)) {
+            Some(suggestion) => { report_error!("Template `{}` not found. Did you mean `{}`?", style::bold(template_name), style::bold(suggestion)); }
+            None => { report_error!("Template `{}` not found.", style::bold(template_name)); }
+        }
+        false
+    }
+
+    // Items with their rules in the snapshot, or currently for `now`
+    async fn snapshot_items(pool: &sqlx::SqlitePool, label: &str) -> Option<Vec<snapshot::Item>> {
+        if label == "now" {
+            let items_result = sqlx::query!(r#"
+                SELECT
+                    w.name AS warehouse,
+                    i.name,
+                    i.quantity,
+                    r.gets_below_quantity AS "gets_below_quantity?",
+                    r.expression AS "expression?",
+                    r.severity AS "severity?"
+                FROM
+                    items i
+                    JOIN warehouses w ON w.id = i.warehouse_id
+                    LEFT JOIN rules r ON r.item_id = i.id;
+            "#)
+                .fetch_all(pool)
+                .await;
+
+            return match items_result {
+                Ok(items) => { Some(items.into_iter().map(|item| snapshot::Item::new(item.warehouse, item.name, item.quantity, item.gets_below_quantity, item.expression, item.severity)).collect()) }
+                Err(err) => { report_error!("Unable to list items. Reason: {}", err); None }
+            };
+        }
+
+        if !Deposito::check_snapshot(pool, label).await {
+            return None;
+        }
+
+        let items_result = sqlx::query!(r#"
+            SELECT si.warehouse, si.name, si.quantity, si.gets_below_quantity, si.expression, si.severity
+            FROM snapshot_items si JOIN snapshots s ON s.id = si.snapshot_id
+            WHERE s.label = $1;
+        "#, label)
+            .fetch_all(pool)
+            .await;
+
+        match items_result {
+            Ok(items) => { Some(items.into_iter().map(|item| snapshot::Item::new(item.warehouse, item.name, item.quantity, item.gets_below_quantity, item.expression, item.severity)).collect()) }
+            Err(err) => { report_error!("Unable to read the snapshot `{}`. Reason: {}", style::bold(label), err); None }
+        }
+    }
+
+    pub async fn diff(&mut self, from: &str, to: &str) {
+        let format = self.format;
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let (before, after) = match (Deposito::snapshot_items(pool, from).await, Deposito::snapshot_items(pool, to).await) {
+            (Some(before), Some(after)) => { (before, after) }
+            _ => { return; }
+        };
+        let warehouses = snapshot::diff(&before, &after);
+
+        if format != Format::Text {
+            let mut records = Vec::new();
+            for (warehouse, changes) in warehouses.iter() {
+                for change in changes.iter() {
+                    let (change, before, after) = match change {
+                        snapshot::Change::Added(item) => { ("added", None, Some(*item)) }
+                        snapshot::Change::Removed(item) => { ("removed", Some(*item), None) }
+                        snapshot::Change::Changed { before, after } => { ("changed", Some(*before), Some(*after)) }
+                    };
+                    let rule = |item: Option<&snapshot::Item>| item.and_then(|item| item.rule.as_ref()).map(snapshot::Rule::describe);
+
+                    records.push(DiffRecord {
+                        warehouse: warehouse.to_string(),
+                        name: after.or(before).map(|item| item.name.clone()).unwrap_or_default(),
+                        change,
+                        quantity_before: before.map(|item| item.quantity),
+                        quantity_after: after.map(|item| item.quantity),
+                        delta: after.map(|item| item.quantity).unwrap_or(0) - before.map(|item| item.quantity).unwrap_or(0),
+                        rule_before: rule(before),
+                        rule_after: rule(after),
+                    });
+                }
+            }
+            output::print_records(format, &records);
+            return;
+        }
+
+        let describe = |label: &str| match label {
+            "now" => { "now".to_string() }
+            label => { format!("`{}`", style::bold(label)) }
+        };
+        let span = format!("from {} to {}", describe(from), describe(to));
+        if warehouses.is_empty() {
+            println!("No changes {}.", span);
+            return;
+        }
+
+        println!("Changes {}:", span);
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        for (warehouse, changes) in warehouses.iter() {
+            println!();
+            println!("`{}` warehouse:", style::bold(warehouse));
+            let mut table = Table::new(vec![Column::new(""), Column::new("Item"), Column::new("Quantity").right(), Column::new("Rule").truncate()]);
+            for change in changes.iter() {
+                let rule = |item: &snapshot::Item| item.rule.as_ref().map(snapshot::Rule::describe).unwrap_or_default();
+                match change {
+                    snapshot::Change::Added(item) => {
+                        added += 1;
+                        table.row(vec![Cell::colored("+", Color::Green), Cell::new(&item.name), Cell::new(item.quantity), Cell::new(rule(item))]);
+                    }
+                    snapshot::Change::Removed(item) => {
+                        removed += 1;
+                        table.row(vec![Cell::colored("-", Color::Red), Cell::new(&item.name), Cell::new(item.quantity), Cell::new(rule(item))]);
+                    }
+                    snapshot::Change::Changed { before, after } => {
+                        changed += 1;
+                        let quantity = match after.quantity - before.quantity {
+                            0 => { after.quantity.to_string() }
+                            delta => { format!("{} → {} ({:+})", before.quantity, after.quantity, delta) }
+                        };
+                        let rule = match before.rule == after.rule {
+                            true => { rule(after) }
+                            false => {
+                                let describe = |item: &snapshot::Item| item.rule.as_ref().map(snapshot::Rule::describe).unwrap_or_else(|| "none".to_string());
+                                format!("{} → {}", describe(before), describe(after))
+                            }
+                        };
+                        table.row(vec![Cell::colored("~", Color::Yellow), Cell::new(change.name()), Cell::new(quantity), Cell::new(rule)]);
+                    }
+                }
+            }
+            table.print();
+        }
+
+        println!();
+        println!("{} added, {} removed, {} changed.", added, removed, changed);
+    }
+
+    // Rows of the warehouses, with their items, rules and movements
+    async fn capture(pool: &sqlx::SqlitePool, warehouses: &[String]) -> Result<Vec<Row>, sqlx::Error> {
+        let names = serde_json::Value::from(warehouses).to_string();
+        let mut rows = Vec::new();
+
+        let warehouses = sqlx::query!("SELECT id, name, archived_at FROM warehouses WHERE name IN (SELECT value FROM json_each($1));", names)
+            .fetch_all(pool)
+            .await?;
+        rows.extend(warehouses.into_iter().map(|warehouse| Row::Warehouse { id: warehouse.id, name: warehouse.name, archived_at: warehouse.archived_at }));
+
+        let items = sqlx::query!("
+            SELECT i.id, i.warehouse_id, i.name, i.description, i.quantity, i.unit, i.tags, i.archived_at
+            FROM items i JOIN warehouses w ON w.id = i.warehouse_id
+            WHERE w.name IN (SELECT value FROM json_each($1));",
+            names)
+            .fetch_all(pool)
+            .await?;
+        rows.extend(items.into_iter().map(|item| Row::Item {
+            id: item.id, warehouse_id: item.warehouse_id, name: item.name, description: item.description, quantity: item.quantity, unit: item.unit, tags: item.tags,
+            archived_at: item.archived_at,
+        }));
+
+        let rules = sqlx::query!("
+            SELECT r.id, r.item_id, r.gets_below_quantity, r.expression, r.severity
+            FROM rules r JOIN items i ON i.id = r.item_id JOIN warehouses w ON w.id = i.warehouse_id
+            WHERE w.name IN (SELECT value FROM json_each($1));",
+            names)
+            .fetch_all(pool)
+            .await?;
+        rows.extend(rules.into_iter().map(|rule| Row::Rule {
+            id: rule.id, item_id: rule.item_id, gets_below_quantity: rule.gets_below_quantity, expression: rule.expression, severity: rule.severity,
+        }));
+
+        let movements = sqlx::query!("
+            SELECT m.id, m.item_id, m.delta, m.created_at
+            FROM movements m JOIN items i ON i.id = m.item_id JOIN warehouses w ON w.id = i.warehouse_id
+            WHERE w.name IN (SELECT value FROM json_each($1));",
+            names)
+            .fetch_all(pool)
+            .await?;
+        rows.extend(movements.into_iter().map(|movement| Row::Movement { id: movement.id, item_id: movement.item_id, delta: movement.delta, created_at: movement.created_at }));
+
+        Ok(rows)
+    }
+
+    // Records what the command changed in the warehouses since `before` was captured
+    async fn record(&mut self, command_line: &str, warehouses: &[String], before: Vec<Row>) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let after = match Deposito::capture(pool, warehouses).await {
+            Ok(rows) => { rows }
+            Err(err) => { report_error!("Unable to record `{}` in the undo journal. Reason: {}", style::bold(command_line), err); return; }
+        };
 
-            deposito.create(&args[0]).await
+        let (before, after) = journal::changes(before, after);
+        if before.is_empty() && after.is_empty() {
+            return;
         }
-        "list" => {
-            if let None = args.get(0) {
-                println!("`\x1b[1mlist\x1b[0m` requires a name of the warehouse to list the items of.");
-                return;
+
+        let rows = serde_json::to_string(&before).and_then(|before| Ok((before, serde_json::to_string(&after)?)));
+        let record_result = match rows {
+            Ok((before, after)) => { Deposito::append_journal(pool, command_line, &before, &after).await.map_err(|err| err.to_string()) }
+            Err(err) => { Err(err.to_string()) }
+        };
+
+        if let Err(err) = record_result {
+            report_error!("Unable to record `{}` in the undo journal. Reason: {}", style::bold(command_line), err);
+        }
+    }
+
+    // A new entry makes the undone ones impossible to redo, and pushes out the oldest ones
+    // beyond `journal::LIMIT`
+    async fn append_journal(pool: &sqlx::SqlitePool, command_line: &str, before: &str, after: &str) -> Result<(), sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        Deposito::in_savepoint(&mut connection, "journal", async |connection| {
+            sqlx::query!("DELETE FROM journal WHERE undone = 1;")
+                .execute(&mut *connection)
+                .await?;
+            sqlx::query!("INSERT INTO journal (id, command, rows_before, rows_after) VALUES (NULL, $1, $2, $3);", command_line, before, after)
+                .execute(&mut *connection)
+                .await?;
+            sqlx::query!("DELETE FROM journal WHERE id <= (SELECT MAX(id) FROM journal) - $1;", journal::LIMIT)
+                .execute(&mut *connection)
+                .await?;
+
+            Ok(())
+        }).await
+    }
+
+    pub async fn undo_list(&mut self) {
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let entries_result = sqlx::query!("SELECT command, created_at, undone FROM journal ORDER BY id DESC LIMIT 20;")
+            .fetch_all(pool)
+            .await;
+
+        let entries = match entries_result {
+            Ok(entries) => { entries }
+            Err(err) => { report_error!("Unable to read the undo journal. Reason: {}", err); return; }
+        };
+
+        if entries.is_empty() {
+            println!("Nothing to undo yet, commands that change the inventory will show up here.");
+            return;
+        }
+
+        // Undone commands are the newest ones, the rest is numbered the way `undo <n>` counts
+        println!("{}", style::bold("Recent commands, newest first:"));
+        let mut table = Table::new(vec![Column::new("").right(), Column::new("When"), Column::new("Command").truncate()]);
+        let mut position = 0;
+        for entry in entries {
+            let marker = match entry.undone != 0 {
+                true => { Cell::colored("undone", Color::Yellow) }
+                false => { position += 1; Cell::new(position) }
+            };
+            table.row(vec![marker, Cell::new(raport::format_timestamp(entry.created_at)), Cell::new(entry.command)]);
+        }
+        table.print();
+    }
+
+    // Undoes the latest `count` commands of the journal, or redoes the earliest `count`
+    // undone ones, one after another until one can't be
+    pub async fn replay(&mut self, count: i64, undo: bool) {
+        let (verb, past) = if undo { ("undo", "Undid") } else { ("redo", "Redid") };
+        if count < 1 {
+            report_error!("`{}` needs a count of at least 1.", verb);
+            return;
+        }
+
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
+
+        let undone = !undo as i64;
+        let entries_result = sqlx::query!("
+            SELECT id, command, rows_before, rows_after FROM journal
+            WHERE undone = $1
+            ORDER BY CASE WHEN $1 = 1 THEN id ELSE -id END
+            LIMIT $2;",
+            undone, count)
+            .fetch_all(pool)
+            .await;
+
+        let entries = match entries_result {
+            Ok(entries) => { entries }
+            Err(err) => { report_error!("Unable to read the undo journal. Reason: {}", err); return; }
+        };
+
+        if entries.is_empty() {
+            println!("Nothing to {}.", verb);
+            return;
+        }
+
+        for entry in entries.iter() {
+            let rows = serde_json::from_str::<Vec<Row>>(&entry.rows_before)
+                .and_then(|before| Ok((before, serde_json::from_str::<Vec<Row>>(&entry.rows_after)?)));
+            let (from, to) = match rows {
+                Ok((before, after)) if undo => { (after, before) }
+                Ok((before, after)) => { (before, after) }
+                Err(err) => { report_error!("Unable to {} `{}`, its journal entry is damaged. Reason: {}", verb, style::bold(&entry.command), err); return; }
+            };
+
+            match Deposito::apply_journal(pool, entry.id, &from, &to, undo).await {
+                Ok(true) => { println!("{} `{}`.", past, style::bold(&entry.command)); }
+                Ok(false) => {
+                    report_error!("Can't {} `{}`, what it changed has been changed since by something that isn't in the journal (such as an import).", verb, style::bold(&entry.command));
+                    return;
+                }
+                Err(err) => { report_error!("Unable to {} `{}`. Reason: {}", verb, style::bold(&entry.command), err); return; }
             }
+        }
 
-            deposito.list(&args[0]).await
+        if entries.len() < count as usize {
+            println!("Nothing more to {}.", verb);
         }
-        "add" => {
-            if let None = args.get(0) {
-                println!("`\x1b[1madd\x1b[0m` requires a name of the warehouse to add the item to as the first argument.");
-                return;
+    }
+
+    // Turns the rows `from` back into the rows `to` and marks the entry as (not) undone,
+    // all of it or nothing. Does nothing and returns false when the rows aren't as the
+    // journal left them.
+    async fn apply_journal(pool: &sqlx::SqlitePool, id: i64, from: &[Row], to: &[Row], undone: bool) -> Result<bool, sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        Deposito::in_savepoint(&mut connection, "replay", async |connection| {
+            let steps = journal::steps(from, to);
+            for row in from.iter() {
+                if Deposito::current_row(&mut *connection, row).await?.as_ref() != Some(row) {
+                    return Ok(false);
+                }
+            }
+            for row in steps.inserts.iter() {
+                if Deposito::current_row(&mut *connection, row).await?.is_some() {
+                    return Ok(false);
+                }
             }
 
-            if let None = args.get(1) {
-                println!("`\x1b[1madd\x1b[0m` requires a name of item to add to the warehouse as the second argument.");
-                return;
+            for row in steps.inserts.iter().chain(steps.updates.iter()) {
+                Deposito::write_row(&mut *connection, row).await?;
+            }
+            for row in steps.deletes.iter() {
+                sqlx::query(&format!("DELETE FROM {} WHERE id = $1;", row.table()))
+                    .bind(row.id())
+                    .execute(&mut *connection)
+                    .await?;
             }
 
-            let quantity: i64;
-            match args.get(2) {
-                Some(arg) => {
-                    match arg.parse::<i64>() {
-                        Ok(result) => { quantity = result }
-                        Err(_) => { println!("`\x1b[1madd\x1b[0m` requires a valid quantity as the third argument."); return; }
-                    }
-                }
-                None => { quantity = 1; }
+            sqlx::query!("UPDATE journal SET undone = $1 WHERE id = $2;", undone, id)
+                .execute(&mut *connection)
+                .await?;
+            Ok(true)
+        }).await
+    }
+
+    // The row with the same table and id as it is in the database
+    async fn current_row(connection: &mut sqlx::SqliteConnection, row: &Row) -> Result<Option<Row>, sqlx::Error> {
+        let id = row.id();
+        let current = match row {
+            Row::Warehouse { .. } => {
+                sqlx::query!("SELECT id, name, archived_at FROM warehouses WHERE id = $1;", id)
+                    .fetch_optional(&mut *connection)
+                    .await?
+                    .map(|warehouse| Row::Warehouse { id: warehouse.id, name: warehouse.name, archived_at: warehouse.archived_at })
+            }
+            Row::Item { .. } => {
+                sqlx::query!("SELECT id, warehouse_id, name, description, quantity, unit, tags, archived_at FROM items WHERE id = $1;", id)
+                    .fetch_optional(&mut *connection)
+                    .await?
+                    .map(|item| Row::Item {
+                        id: item.id, warehouse_id: item.warehouse_id, name: item.name, description: item.description, quantity: item.quantity, unit: item.unit, tags: item.tags,
+                        archived_at: item.archived_at,
+                    })
+            }
+            Row::Rule { .. } => {
+                sqlx::query!("SELECT id, item_id, gets_below_quantity, expression, severity FROM rules WHERE id = $1;", id)
+                    .fetch_optional(&mut *connection)
+                    .await?
+                    .map(|rule| Row::Rule {
+                        id: rule.id, item_id: rule.item_id, gets_below_quantity: rule.gets_below_quantity, expression: rule.expression, severity: rule.severity,
+                    })
+            }
+            Row::Movement { .. } => {
+                sqlx::query!("SELECT id, item_id, delta, created_at FROM movements WHERE id = $1;", id)
+                    .fetch_optional(&mut *connection)
+                    .await?
+                    .map(|movement| Row::Movement { id: movement.id, item_id: movement.item_id, delta: movement.delta, created_at: movement.created_at })
             }
+        };
+        Ok(current)
+    }
 
-            deposito.add(&args[0], &args[1], quantity).await;
-        }
-        "edit" => {
-            if let None = args.get(0) {
-                println!("`\x1b[1medit\x1b[0m` requires a name of the warehouse to edit the item of as the first argument.");
-                return;
+    // Inserts the row, or overwrites the one with its id
+    async fn write_row(connection: &mut sqlx::SqliteConnection, row: &Row) -> Result<(), sqlx::Error> {
+        match row {
+            Row::Warehouse { id, name, archived_at } => {
+                sqlx::query!("
+                    INSERT INTO warehouses (id, name, archived_at) VALUES ($1, $2, $3)
+                    ON CONFLICT (id) DO UPDATE SET name = excluded.name, archived_at = excluded.archived_at;",
+                    id, name, archived_at)
+                    .execute(&mut *connection)
+                    .await?;
             }
+            Row::Item { id, warehouse_id, name, description, quantity, unit, tags, archived_at } => {
+                sqlx::query!("
+                    INSERT INTO items (id, warehouse_id, name, description, quantity, unit, tags, archived_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                    ON CONFLICT (id) DO UPDATE SET
+                        warehouse_id = excluded.warehouse_id, name = excluded.name, description = excluded.description,
+                        quantity = excluded.quantity, unit = excluded.unit, tags = excluded.tags, archived_at = excluded.archived_at;",
+                    id, warehouse_id, name, description, quantity, unit, tags, archived_at)
+                    .execute(&mut *connection)
+                    .await?;
+            }
+            Row::Rule { id, item_id, gets_below_quantity, expression, severity } => {
+                sqlx::query!("
+                    INSERT INTO rules (id, item_id, gets_below_quantity, expression, severity) VALUES ($1, $2, $3, $4, $5)
+                    ON CONFLICT (id) DO UPDATE SET
+                        item_id = excluded.item_id, gets_below_quantity = excluded.gets_below_quantity,
+                        expression = excluded.expression, severity = excluded.severity;",
+                    id, item_id, gets_below_quantity, expression, severity)
+                    .execute(&mut *connection)
+                    .await?;
+            }
+            Row::Movement { id, item_id, delta, created_at } => {
+                sqlx::query!("
+                    INSERT INTO movements (id, item_id, delta, created_at) VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (id) DO UPDATE SET item_id = excluded.item_id, delta = excluded.delta, created_at = excluded.created_at;",
+                    id, item_id, delta, created_at)
+                    .execute(&mut *connection)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
 
-            if let None = args.get(1) {
-                println!("`\x1b[1medit\x1b[0m` requires a name of item to edit in the warehouse as the second argument.");
-                return;
+    pub async fn batch(&mut self, path: &str, atomic: bool) {
+        let (source, input_result) = match path {
+            "-" => { (String::from("stdin"), std::io::read_to_string(std::io::stdin())) }
+            path => { (format!("`{}`", style::bold(path)), std::fs::read_to_string(path)) }
+        };
+
+        let input = match input_result {
+            Ok(input) => { input }
+            Err(err) => { report_error!("Unable to read {}. Reason: {}", source, err); return; }
+        };
+
+        // Parsed lines with their numbers, blank lines and `#` comments are skipped
+        let mut lines: Vec<(usize, String, Result<Matches, String>)> = Vec::new();
+        for (index, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
             }
 
-            let mut edit_options = ItemEdit::default();
-            for option in options.into_iter() {
-                match option.name.as_str() {
-                    "n" | "name" => { edit_options.name = option.value; }
-                    "d" | "description" => { edit_options.description = option.value; }
-                    "q" | "quantity" => {
-                        edit_options.quantity = match option.value {
-                            Some(quantity) => {
-                                match quantity.parse::<i64>() {
-                                    Ok(quantity) => { Some(quantity) }
-                                    Err(_) => { println!("Invalid quantity of `\x1b[1m{}\x1b[0m`.", quantity); return; }
-                                }
+            let parsed = match shlex::split(line) {
+                Some(mut words) => {
+                    // Lines copied from a terminal may still start with `deposito`
+                    if words.first().is_some_and(|word| word == "deposito") {
+                        words.remove(0);
+                    }
+
+                    match cli::parse(&words) {
+                        Ok(matches) => {
+                            match matches.command.map(|command| command.name) {
+                                Some(name @ ("shell" | "tui" | "batch")) => { Err(format!("`{}` can't be run in a batch.", style::bold(name))) }
+                                // Both need the database outside of a transaction, `--atomic` holds one
+                                Some(name @ ("backup" | "restore")) if atomic => { Err(format!("`{}` can't be run in an atomic batch.", style::bold(name))) }
+                                _ => { Ok(matches) }
                             }
-                            None => { None }
                         }
+                        Err(err) => { Err(err.to_string()) }
                     }
-                    _ => { println!("Unknown option `\x1b[1m{}\x1b[0m`.", option.name); return; }
                 }
+                None => { Err(String::from("Unclosed quote.")) }
+            };
+            lines.push((index + 1, line.to_string(), parsed));
+        }
+
+        // With `--atomic` nothing runs unless every line makes sense
+        let invalid: Vec<_> = lines.iter().filter_map(|(number, _, parsed)| parsed.as_ref().err().map(|err| (number, err))).collect();
+        if atomic && !invalid.is_empty() {
+            for (number, err) in invalid {
+                report_error!("Line {}: {}", number, err);
             }
+            println!("Nothing was run.");
+            return;
+        }
 
-            deposito.edit(&args[0], &args[1], edit_options).await;
+        // Commands run one after another over a single connection, which also makes
+        // the transaction of `--atomic` cover all of them
+        let pool = match sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect(DATABASE_URL).await {
+            Ok(pool) => { pool }
+            Err(err) => { report_error!("Unable to connect with the local SQLite deposito database. Reason: {}", err); return; }
+        };
+        if atomic {
+            if let Err(err) = sqlx::query("BEGIN IMMEDIATE;").execute(&pool).await {
+                report_error!("Unable to start the transaction. Reason: {}", err);
+                return;
+            }
         }
-        "remove" => {
-            let mut has_all_option = false;
-            for option in options.iter() {
-                if option.name == "a" || option.name == "all" {
-                    has_all_option = true;
-                    break;
+        if let Some(previous) = self.db_pool.replace(pool) {
+            previous.close().await;
+        }
+
+        let mut failed = Vec::new();
+        for (number, line, parsed) in lines.iter() {
+            println!("{}", style::bold(format!("Line {}: {}", number, line)));
+            let errors = ERRORS.load(Ordering::Relaxed);
+
+            match parsed {
+                Ok(matches) => {
+                    match matches.command {
+                        Some(command) if !matches.help => { self.execute(command, matches).await; }
+                        command => { Deposito::help(command); }
+                    }
                 }
+                Err(err) => { report_error!("{}", err); }
             }
 
-            for option in options.into_iter() {
-                match option.name.as_str() {
-                    "w" | "warehouse" => {
-                        if args.len() < 1 {
-                            println!("`\x1b[1mremove\x1b[0m` with the --warehouse option requires at least one argument (which warehouse to remove).");
-                            return;
-                        }
+            if ERRORS.load(Ordering::Relaxed) != errors {
+                failed.push(*number);
+                if atomic { break; }
+            }
+        }
 
-                        deposito.remove(RemoveTypes::Warehouse, args.clone()).await;
-                    }
-                    "i" | "item" => {
-                        if args.len() < 1 {
-                            println!("`\x1b[1mremove\x1b[0m` with the --item option requires at least two arguments (from which warehouse, what item to remove).");
-                            return;
-                        }
+        let pool = match self.get_pool() {
+            Some(pool) => { pool }
+            None => { return; }
+        };
 
-                        if has_all_option {
-                            // TODO: Error when item args
-                            deposito.remove(RemoveTypes::Item(RTItemOption::All), args.clone()).await;
-                        } else {
-                            deposito.remove(RemoveTypes::Item(RTItemOption::List), args.clone()).await;
-                        }
-                    }
-                    "a" | "all" => { /* skip */ }
-                    _ => { println!("Unknown option `\x1b[1m{}\x1b[0m`.", option.name); return; }
+        println!();
+        match (failed.as_slice(), atomic) {
+            ([], false) => { println!("Ran {} command(s) from {}.", lines.len(), source); }
+            ([], true) => {
+                match sqlx::query("COMMIT;").execute(pool).await {
+                    Ok(_) => { println!("Ran {} command(s) from {}, all changes saved.", lines.len(), source); }
+                    Err(err) => { report_error!("Unable to save the changes. Reason: {}", err); }
+                }
+            }
+            ([number, ..], true) => {
+                match sqlx::query("ROLLBACK;").execute(pool).await {
+                    Ok(_) => { report_error!("Line {} failed, none of the changes were saved.", number); }
+                    Err(err) => { report_error!("Line {} failed and the changes couldn't be rolled back. Reason: {}", number, err); }
                 }
             }
+            (failed, false) => {
+                let numbers: Vec<String> = failed.iter().map(|number| number.to_string()).collect();
+                report_error!("{} of {} command(s) failed, on line(s) {}.", failed.len(), lines.len(), numbers.join(", "));
+            }
+        }
+    }
+
+    // Runs a parsed command, also `batch` which can't be part of `execute` as it runs
+    // commands itself
+    async fn run(&mut self, command: &CommandSpec, matches: &Matches) {
+        if command.name == "batch" {
+            let path = matches.arg("file").unwrap_or("-");
+            self.batch(path, matches.flag("atomic")).await;
+            return;
         }
-        "raport" => {
-            let mut has_all_option = false;
-            for option in options.iter() {
-                if option.name == "a" || option.name == "all" {
-                    has_all_option = true;
-                    break;
+
+        self.execute(command, matches).await;
+    }
+
+    // Runs a parsed command, shared by `main` and `deposito shell`
+    async fn execute(&mut self, command: &CommandSpec, matches: &Matches) {
+        self.format = match matches.text("format") {
+            Some(format) => {
+                match format.parse::<Format>() {
+                    Ok(format) => { format }
+                    Err(err) => { report_error!("{}", err); return; }
                 }
             }
+            None => { Format::default() }
+        };
+
+        if matches!(self.format, Format::Html | Format::Markdown) && command.name != "raport" {
+            report_error!("`--format={}` only applies to `raport`.", matches.text("format").unwrap_or_default());
+            return;
+        }
 
-            if has_all_option {
-                deposito.raport_all().await;
-            } else {
-                deposito.raport_warehouse(&args[0]).await;
+        // Commands that change the inventory go into the undo journal, by the rows of the
+        // warehouses they touch before and after
+        let scope = Deposito::journal_scope(command, matches);
+        let before = match scope.as_ref() {
+            Some(warehouses) => {
+                let pool = match self.get_pool() {
+                    Some(pool) => { pool }
+                    None => { return; }
+                };
+
+                match Deposito::capture(pool, warehouses).await {
+                    Ok(rows) => { Some(rows) }
+                    Err(err) => { report_error!("Unable to read what `{}` would change for the undo journal, nothing was changed. Reason: {}", command.name, err); return; }
+                }
             }
+            None => { None }
+        };
+
+        self.dispatch(command, matches).await;
+
+        if let (Some(warehouses), Some(before)) = (scope, before) {
+            self.record(&matches.command_line(), &warehouses, before).await;
         }
-        "rules" => {
-            for option in options.iter() {
-                match option.name.as_str() {
-                    "l" | "list" => { deposito.show_rules(&args[0]).await; }
-                    "c" | "create" => {
-                        if args.len() < 3 {
-                            println!("`\x1b[1mrules\x1b[0m` with the --create option requires at least three arguments (warehouse, item, quantity).");
+    }
+
+    // Warehouses a command changes, `None` for commands that don't go into the undo journal
+    fn journal_scope(command: &CommandSpec, matches: &Matches) -> Option<Vec<String>> {
+        let warehouse = || vec![matches.arg("warehouse").unwrap_or_default().to_string()];
+        match command.name {
+            "create" | "add" | "edit" => { Some(warehouse()) }
+            "rename" => { Some(vec![matches.arg("old").unwrap_or_default().to_string(), matches.arg("new").unwrap_or_default().to_string()]) }
+            "merge" => { Some(vec![matches.arg("source").unwrap_or_default().to_string(), matches.arg("target").unwrap_or_default().to_string()]) }
+            "remove" | "archive" | "unarchive" if matches.is_mode("warehouse") => { Some(matches.arg_list("warehouses")) }
+            "remove" | "archive" | "unarchive" => { Some(warehouse()) }
+            "rules" if !matches.is_mode("list") => { Some(warehouse()) }
+            _ => { None }
+        }
+    }
+
+    fn confirm_option(matches: &Matches) -> Confirm {
+        match (matches.flag("dry-run"), matches.flag("yes")) {
+            (true, _) => { Confirm::DryRun }
+            (false, true) => { Confirm::Yes }
+            (false, false) => { Confirm::Ask }
+        }
+    }
+
+    // Runs a parsed command, without the undo journal
+    async fn dispatch(&mut self, command: &CommandSpec, matches: &Matches) {
+        // Arguments were validated against the command's grammar, so the required ones are there
+        let arg = |name: &str| matches.arg(name).unwrap_or_default().to_string();
+
+        match command.name {
+            "shell" => { println!("Already in the shell."); }
+            "init" => {
+                match std::env::current_dir() {
+                    Ok(current_dir) => { Deposito::init(&current_dir).await }
+                    Err(err) => { report_error!("Unable to get current working directory. Reason: {}", err); }
+                }
+            }
+            "man" => {
+                if matches.is_mode("markdown") {
+                    print!("{}", manual::markdown());
+                    return;
+                }
+
+                match matches.arg("command") {
+                    Some(name) => {
+                        match CommandSpec::find(name) {
+                            Some(command) => { print!("{}", manual::roff(Some(command))); }
+                            None => { report_error!("{}", CliError::unknown_command(name)); }
+                        }
+                    }
+                    None => { print!("{}", manual::roff(None)); }
+                }
+            }
+            "completions" => {
+                match complete::script(&arg("shell")) {
+                    Some(script) => { print!("{}", script); }
+                    None => { report_error!("Unknown shell `{}`.", style::bold(arg("shell"))); }
+                }
+            }
+            "tui" => { self.tui(matches.arg("warehouse")).await }
+            "export" => {
+                // The only format there is for now, more may follow
+                if let Some(format @ ("text" | "csv" | "tsv")) = matches.text("format") {
+                    report_error!("`export` can't write `{}`, only `json`.", style::bold(format));
+                    return;
+                }
+
+                self.export(matches.arg("file").unwrap_or("-")).await;
+            }
+            "backup" => {
+                let keep = match matches.integer("keep") {
+                    Some(keep) if keep < 0 => { report_error!("`--keep` can't be negative."); return; }
+                    keep => { keep.map(|keep| keep as usize) }
+                };
+
+                self.backup(matches.arg("path"), keep).await;
+            }
+            "restore" => { self.restore(&arg("file")).await }
+            "undo" => {
+                match matches.is_mode("list") {
+                    true => { self.undo_list().await }
+                    false => { self.replay(matches.arg_integer("count").unwrap_or(1), true).await }
+                }
+            }
+            "redo" => { self.replay(matches.arg_integer("count").unwrap_or(1), false).await }
+            "snapshot" => {
+                match (arg("action").as_str(), matches.arg("label")) {
+                    ("list", None) => { self.snapshot_list().await }
+                    ("list", Some(_)) => { report_error!("`snapshot list` doesn't take a <label>."); }
+                    (action, None) => { report_error!("`snapshot {}` needs a <label>.", action); }
+                    ("create", Some(label)) => { self.snapshot_create(label).await }
+                    (_, Some(label)) => { self.snapshot_remove(label).await }
+                }
+            }
+            "diff" => { self.diff(&arg("snapshot"), matches.arg("other").unwrap_or("now")).await }
+            "template" => {
+                match (arg("action").as_str(), matches.arg("name"), matches.arg("warehouse")) {
+                    ("list", None, None) => { self.template_list().await }
+                    ("list", _, _) => { report_error!("`template list` doesn't take a <name> or <warehouse>."); }
+                    (action, None, _) => { report_error!("`template {}` needs a <name>.", action); }
+                    ("create", Some(_), None) => { report_error!("`template create` needs the <warehouse> to make it from."); }
+                    ("create", Some(name), Some(warehouse)) => { self.template_create(name, warehouse).await }
+                    (_, Some(_), Some(_)) => { report_error!("`template remove` doesn't take a <warehouse>."); }
+                    (_, Some(name), None) => { self.template_remove(name).await }
+                }
+            }
+            "import" => {
+                let dry_run = matches.flag("dry-run");
+                match (arg("format").as_str(), matches.arg("warehouse")) {
+                    ("json", warehouse) => {
+                        if let Some(option) = ["mode", "columns"].into_iter().find(|option| matches.text(option).is_some()) {
+                            report_error!("`--{}` only applies to `import csv`.", option);
+                            return;
                         }
 
-                        let quantity = match args[2].parse::<i64>() {
-                            Ok(quantity) => { quantity }
-                            Err(err) => { println!("Could not validate the quantity. Reason: {}", err); return; }
+                        let conflict = match matches.text("on-conflict") {
+                            Some(conflict) => {
+                                match conflict.parse::<export::Conflict>() {
+                                    Ok(conflict) => { conflict }
+                                    Err(err) => { report_error!("{}", err); return; }
+                                }
+                            }
+                            None => { export::Conflict::default() }
                         };
 
-                        deposito.create_rules(&args[0], &args[1], quantity).await;
+                        self.import_json(&arg("file"), warehouse, conflict, dry_run).await;
                     }
-                    "e" | "edit" => {
-                        if args.len() < 3 {
-                            println!("`\x1b[1mrules\x1b[0m` with the --edit option requires at least three arguments (warehouse, item, quantity).");
+                    (_, None) => { report_error!("`import csv` needs the <warehouse> to import into."); }
+                    (_, Some(warehouse)) => {
+                        if matches.text("on-conflict").is_some() {
+                            report_error!("`--on-conflict` only applies to `import json`.");
+                            return;
                         }
 
-                        let quantity = match args[args.len()-1].parse::<i64>() {
-                            Ok(quantity) => { quantity }
-                            Err(err) => { println!("Could not validate the quantity. Reason: {}", err); return; }
+                        let mode = match matches.text("mode") {
+                            Some(mode) => {
+                                match mode.parse::<import::Mode>() {
+                                    Ok(mode) => { mode }
+                                    Err(err) => { report_error!("{}", err); return; }
+                                }
+                            }
+                            None => { import::Mode::default() }
                         };
 
-                        deposito.edit_rules(&args[0], args.get(1..args.len()-1).unwrap(), quantity).await;
+                        self.import_csv(warehouse, &arg("file"), mode, matches.text("columns"), dry_run).await;
+                    }
+                }
+            }
+            "view" => { self.view(matches.flag("include-archived")).await }
+            "create" => {
+                let stock = match (matches.text("from"), matches.text("template"), matches.flag("empty")) {
+                    (None, None, false) => { Stock::Default }
+                    (None, None, true) => { Stock::Empty }
+                    (Some(warehouse), None, false) => { Stock::Warehouse(warehouse.to_string()) }
+                    (None, Some(template), false) => { Stock::Template(template.to_string()) }
+                    _ => { report_error!("Only one of `--from`, `--template` and `--empty` can be given."); return; }
+                };
+
+                if matches.flag("zero") && matches!(stock, Stock::Empty) {
+                    report_error!("`--zero` doesn't apply to `--empty`.");
+                    return;
+                }
+
+                self.create(&arg("warehouse"), stock, matches.flag("zero")).await;
+            }
+            "rename" => { self.rename(&arg("old"), &arg("new")).await }
+            "merge" => {
+                let conflict = match matches.text("on-conflict") {
+                    Some(conflict) => {
+                        match conflict.parse::<export::Conflict>() {
+                            Ok(conflict) => { conflict }
+                            Err(err) => { report_error!("{}", err); return; }
+                        }
+                    }
+                    None => { export::Conflict::default() }
+                };
+
+                self.merge(&arg("source"), &arg("target"), conflict).await;
+            }
+            "list" => { self.list(&arg("warehouse"), matches.flag("include-archived")).await }
+            "add" => {
+                let quantity = matches.arg_integer("quantity").unwrap_or(1);
+                self.add(&arg("warehouse"), &arg("item"), quantity).await;
+            }
+            "edit" => {
+                let edit_options = ItemEdit {
+                    name: matches.text("name").map(str::to_string),
+                    description: matches.text("description").map(str::to_string),
+                    quantity: matches.integer("quantity"),
+                };
+
+                self.edit(&arg("warehouse"), &arg("item"), edit_options).await;
+            }
+            "remove" => {
+                let confirm = Deposito::confirm_option(matches);
+                let purge = matches.flag("purge");
+                if matches.is_mode("warehouse") {
+                    self.remove(RemoveTypes::Warehouse, matches.arg_list("warehouses"), confirm, purge).await;
+                } else if matches.is_mode("all") {
+                    self.remove(RemoveTypes::Item(RTItemOption::All), vec![arg("warehouse")], confirm, purge).await;
+                } else {
+                    let mut to_remove = vec![arg("warehouse")];
+                    to_remove.extend(matches.arg_list("items"));
+                    self.remove(RemoveTypes::Item(RTItemOption::List), to_remove, confirm, purge).await;
+                }
+            }
+            "archive" | "unarchive" => {
+                let archive = command.name == "archive";
+                match matches.is_mode("warehouse") {
+                    true => { self.archive(None, matches.arg_list("warehouses"), archive).await }
+                    false => { self.archive(Some(&arg("warehouse")), matches.arg_list("items"), archive).await }
+                }
+            }
+            "raport" => {
+                let horizon = match matches.text("horizon") {
+                    Some(horizon) => {
+                        match raport::parse_horizon(horizon) {
+                            Ok(days) => { Some(days) }
+                            Err(err) => { report_error!("{}", err); return; }
+                        }
                     }
-                    "r" | "remove" => {
-                        if args.len() < 2 {
-                            println!("`\x1b[1mrules\x1b[0m` with the --remove option requires at least two arguments (warehouse, rule).");
+                    None => { None }
+                };
+
+                let output = match matches.text("output") {
+                    Some(path) => {
+                        match RaportFile::from_path(path) {
+                            Ok(file) => { Some((path, file)) }
+                            Err(err) => { report_error!("{}", err); return; }
                         }
+                    }
+                    None => { None }
+                };
 
-                        deposito.remove_rules(&args[0], args.get(1..args.len()).unwrap()).await;
+                let stamp = match matches.flag("stamp") {
+                    true if output.is_some() || !matches!(self.format, Format::Text | Format::Html | Format::Markdown) => {
+                        report_error!("`--stamp` only applies to text, html and markdown raports, PDFs are always stamped.");
+                        return;
                     }
-                    _ => { println!("Unknown option `\x1b[1m{}\x1b[0m`.", option.name); return; }
+                    true => {
+                        match std::env::current_dir() {
+                            Ok(current_dir) => { Some(Stamp { generated_at: raport::unix_now(), workspace: current_dir.display().to_string() }) }
+                            Err(err) => { report_error!("Unable to get current working directory. Reason: {}", err); return; }
+                        }
+                    }
+                    false => { None }
+                };
+
+                let include_archived = matches.flag("include-archived");
+                if matches.is_mode("all") {
+                    self.raport_all(horizon, output, stamp.as_ref(), include_archived).await;
+                } else {
+                    self.raport_warehouse(&arg("warehouse"), horizon, output, stamp.as_ref(), include_archived).await;
+                }
+            }
+            "rules" => {
+                let severity = match matches.text("severity") {
+                    Some(severity) => {
+                        match severity.parse::<Severity>() {
+                            Ok(severity) => { severity }
+                            Err(err) => { report_error!("{}", err); return; }
+                        }
+                    }
+                    None => { Severity::Critical }
+                };
+
+                if let Some(option) = ["yes", "dry-run"].into_iter().find(|option| matches.flag(option) && !matches.is_mode("remove")) {
+                    report_error!("`--{}` only applies to `rules -r`.", option);
+                    return;
+                }
+
+                if matches.is_mode("list") {
+                    self.show_rules(&arg("warehouse")).await;
+                } else if matches.is_mode("create") {
+                    let condition = match matches.text("expr") {
+                        Some(expression) => { RuleCondition::Expression(expression.to_string()) }
+                        None => { RuleCondition::Below(matches.arg_integer("quantity").unwrap_or_default()) }
+                    };
+
+                    self.create_rules(&arg("warehouse"), &arg("item"), condition, severity).await;
+                } else if matches.is_mode("edit") {
+                    let quantity = matches.arg_integer("quantity").unwrap_or_default();
+                    self.edit_rules(&arg("warehouse"), &matches.arg_list("items"), quantity).await;
+                } else if matches.is_mode("remove") {
+                    self.remove_rules(&arg("warehouse"), &matches.arg_list("items"), Deposito::confirm_option(matches)).await;
                 }
             }
+            _ => { unreachable!() }
+        }
+    }
+}
+
+// Where `create` takes the items and rules of the new warehouse from
+enum Stock {
+    // The `default` template, when there is one
+    Default,
+    Empty,
+    Warehouse(String),
+    Template(String),
+}
+
+#[derive(Default)]
+struct ItemEdit {
+    name: Option<String>,
+    description: Option<String>,
+    quantity: Option<i64>,
+}
+
+enum RuleCondition {
+    Below(i64),
+    Expression(String),
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let mut cli_args = std::env::args();
+    // Discard executable path
+    cli_args.next();
+    let cli_args: Vec<String> = cli_args.collect();
+
+    // Called by the completion scripts with whatever the user typed, so it bypasses the
+    // usual parsing (and its error messages)
+    if cli_args.first().is_some_and(|arg| arg == "__complete") {
+        let mut deposito = Deposito::default();
+        if let Ok(current_dir) = std::env::current_dir() {
+            let _ = deposito.connect(&current_dir).await;
+        }
+        deposito.complete(&cli_args[1..]).await;
+        return;
+    }
+
+    // `--color` has to be known before anything gets printed (including parse errors),
+    // so look for it first
+    let mut color = ColorChoice::default();
+    for (index, arg) in cli_args.iter().enumerate() {
+        if arg == "--" { break; }
+        let value = match arg.strip_prefix("--color") {
+            Some(rest) if rest.starts_with('=') => { &rest[1..] }
+            Some("") => { cli_args.get(index+1).map(String::as_str).unwrap_or_default() }
+            _ => { continue; }
+        };
+
+        if let Ok(value) = value.parse::<ColorChoice>() {
+            color = value;
         }
-        _ => { unreachable!() }
     }
+    style::init(color);
+
+    let matches = match cli::parse(&cli_args) {
+        Ok(matches) => { matches }
+        Err(err) => { cli::print_error(&err, &cli_args); return; }
+    };
+
+    let command = match matches.command {
+        Some(command) if !matches.help => { command }
+        command => { Deposito::help(command); return; }
+    };
+
+    let current_dir = match std::env::current_dir() {
+        Ok(dir) => { dir }
+        Err(err) => { panic!("Unable to get current working directory. Reason: {}", err); }
+    };
+
+    let mut deposito = Deposito::default();
+    if command.requires_workspace {
+        match deposito.connect(&current_dir).await {
+            Ok(_) => { }
+            Err(err) => {
+                if let DepositoError::NoDepositoDir(_) = err {
+                    println!("Deposito directory doesn't exist. You can create one using {}", style::bold("`deposito init`"));
+                    return;
+                }
+
+                report_error!("Unable to connect with the local SQLite deposito database. Reason: {}", err);
+                return;
+            }
+        };
+    }
+
+    // The shell runs commands itself, so it can't be one of them
+    if command.name == "shell" {
+        deposito.shell().await;
+        return;
+    }
+
+    deposito.run(command, &matches).await;
 }
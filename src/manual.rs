@@ -0,0 +1,158 @@
+use crate::cli::{CommandSpec, OptionSpec};
+use crate::commands::{COMMANDS, DESCRIPTION, GLOBAL_OPTIONS, SYNOPSIS};
+
+// Man pages (roff) and the Markdown reference, rendered from the same command
+// definitions as the terminal help in `cli.rs`.
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Escapes text for roff: backslashes and hyphens, and a leading `.` or `'` that would
+// otherwise start a request
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        return format!("\\&{}", escaped);
+    }
+    escaped
+}
+
+fn roff_options(page: &mut String, options: &[OptionSpec]) {
+    for option in options.iter() {
+        page.push_str(&format!(".TP\n.B {}\n{}\n", escape(option.signature().trim_start()), escape(option.help)));
+    }
+}
+
+fn roff_command(page: &mut String, command: &CommandSpec, heading: &str) {
+    page.push_str(&format!(".{} SYNOPSIS\n.nf\n", heading));
+    for usage in command.usage() {
+        page.push_str(&format!("{}\n", escape(&usage)));
+    }
+    page.push_str(".fi\n");
+
+    if !command.notes.is_empty() {
+        page.push_str(&format!(".PP\n{}\n", escape(&command.notes.join(" "))));
+    }
+
+    if !command.options.is_empty() {
+        page.push_str(&format!(".{} OPTIONS\n", heading));
+        roff_options(page, command.options);
+    }
+
+    for section in command.sections.iter() {
+        page.push_str(&format!(".{} {}\n", heading, escape(&section.title.to_uppercase())));
+        for (term, description) in section.entries.iter() {
+            page.push_str(&format!(".TP\n.B {}\n", escape(term)));
+            if !description.is_empty() {
+                page.push_str(&format!("{}\n", escape(description)));
+            }
+        }
+    }
+
+    if !command.examples.is_empty() {
+        page.push_str(&format!(".{} EXAMPLES\n.nf\n", heading));
+        for example in command.examples.iter() {
+            page.push_str(&format!("{}\n", escape(example)));
+        }
+        page.push_str(".fi\n");
+    }
+}
+
+// Man page of a single command, or of deposito as a whole with every command in it
+pub fn roff(command: Option<&CommandSpec>) -> String {
+    let mut page = String::new();
+
+    if let Some(command) = command {
+        let name = format!("deposito-{}", command.name);
+        page.push_str(&format!(".TH {} 1 \"\" \"deposito {}\" \"User Commands\"\n", escape(&name.to_uppercase()), VERSION));
+        page.push_str(&format!(".SH NAME\n{} \\- {}\n", escape(&name), escape(command.summary)));
+        roff_command(&mut page, command, "SH");
+        page.push_str(".SH GLOBAL OPTIONS\n");
+        roff_options(&mut page, GLOBAL_OPTIONS);
+        page.push_str(".SH SEE ALSO\n.BR deposito (1)\n");
+        return page;
+    }
+
+    page.push_str(&format!(".TH DEPOSITO 1 \"\" \"deposito {}\" \"User Commands\"\n", VERSION));
+    page.push_str(&format!(".SH NAME\ndeposito \\- {}\n", escape(DESCRIPTION)));
+    page.push_str(&format!(".SH SYNOPSIS\n{}\n", escape(SYNOPSIS)));
+    page.push_str(".SH GLOBAL OPTIONS\n");
+    roff_options(&mut page, GLOBAL_OPTIONS);
+    page.push_str(".SH COMMANDS\n");
+    for command in COMMANDS.iter() {
+        page.push_str(&format!(".SS {}\n{}.\n", escape(command.name), escape(command.summary)));
+        roff_command(&mut page, command, "SS");
+    }
+    page
+}
+
+// Cell of a Markdown table
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn markdown_options(reference: &mut String, options: &[OptionSpec]) {
+    reference.push_str("| Option | Description |\n|--------|-------------|\n");
+    for option in options.iter() {
+        reference.push_str(&format!("| `{}` | {} |\n", cell(option.signature().trim_start()), cell(option.help)));
+    }
+}
+
+fn code_block(reference: &mut String, lines: &[String]) {
+    reference.push_str("```sh\n");
+    for line in lines.iter() {
+        reference.push_str(&format!("{}\n", line));
+    }
+    reference.push_str("```\n");
+}
+
+pub fn markdown() -> String {
+    let mut reference = String::new();
+    reference.push_str("<!-- Generated with `deposito man --markdown`, don't edit by hand. -->\n");
+    reference.push_str(&format!("# deposito\n\ndeposito - {}.\n\n", DESCRIPTION));
+    code_block(&mut reference, &[SYNOPSIS.to_string()]);
+
+    reference.push_str("\n## Global options\n\n");
+    markdown_options(&mut reference, GLOBAL_OPTIONS);
+
+    reference.push_str("\n## Commands\n\n");
+    for command in COMMANDS.iter() {
+        reference.push_str(&format!("- [`{}`](#{}) - {}\n", command.name, command.name, command.summary));
+    }
+
+    for command in COMMANDS.iter() {
+        reference.push_str(&format!("\n### {}\n\n{}.\n\n", command.name, capitalize(command.summary)));
+        code_block(&mut reference, &command.usage());
+
+        if !command.notes.is_empty() {
+            reference.push_str(&format!("\n{}\n", command.notes.join("\n")));
+        }
+
+        if !command.options.is_empty() {
+            reference.push_str("\n**Options**\n\n");
+            markdown_options(&mut reference, command.options);
+        }
+
+        for section in command.sections.iter() {
+            reference.push_str(&format!("\n**{}**\n\n| | |\n|-|-|\n", section.title));
+            for (term, description) in section.entries.iter() {
+                reference.push_str(&format!("| `` {} `` | {} |\n", cell(term), cell(description)));
+            }
+        }
+
+        if !command.examples.is_empty() {
+            reference.push_str("\n**Examples**\n\n");
+            let examples: Vec<String> = command.examples.iter().map(|example| example.to_string()).collect();
+            code_block(&mut reference, &examples);
+        }
+    }
+
+    reference
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
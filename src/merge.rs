@@ -0,0 +1,55 @@
+use crate::export::Conflict;
+use crate::snapshot::Rule;
+
+// Planning `deposito merge`: every item of the source warehouse either moves into the
+// target as it is, or is combined with the target's item of the same name.
+
+pub struct Item {
+    pub id: i64,
+    pub name: String,
+    pub quantity: i64,
+    pub rule: Option<Rule>,
+}
+
+pub enum Step<'a> {
+    Move(&'a Item),
+    // Quantities are added up, `take_rule` when the source's rule replaces the target's
+    Combine { source: &'a Item, target: &'a Item, take_rule: bool },
+}
+
+// Items of both warehouses with different rules are conflicts. `Conflict::Skip` keeps the
+// rule of the target, `Conflict::Overwrite` takes the one of the source, and
+// `Conflict::Fail` fails listing all of them.
+pub fn plan<'a>(source: &'a [Item], target: &'a [Item], conflict: Conflict) -> Result<Vec<Step<'a>>, Vec<String>> {
+    let mut steps = Vec::new();
+    let mut errors = Vec::new();
+
+    for item in source.iter() {
+        let other = match target.iter().find(|other| other.name == item.name) {
+            Some(other) => other,
+            None => { steps.push(Step::Move(item)); continue; }
+        };
+
+        let take_rule = match (&item.rule, &other.rule) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(rule), Some(other_rule)) if rule == other_rule => false,
+            (Some(rule), Some(other_rule)) => {
+                match conflict {
+                    Conflict::Fail => {
+                        errors.push(format!("`{}` has the rule `{}` in one warehouse and `{}` in the other.", item.name, rule.describe(), other_rule.describe()));
+                        continue;
+                    }
+                    Conflict::Skip => false,
+                    Conflict::Overwrite => true,
+                }
+            }
+        };
+        steps.push(Step::Combine { source: item, target: other, take_rule });
+    }
+
+    match errors.is_empty() {
+        true => Ok(steps),
+        false => Err(errors),
+    }
+}
@@ -0,0 +1,157 @@
+use std::str::FromStr;
+use serde::Serialize;
+
+// Output format of the read commands (`view`, `list`, `raport`, `rules -l` and `diff`).
+// `raport` can also be rendered as an HTML page or Markdown, see `render.rs`.
+//
+// Machine readable formats print one record per warehouse, item or rule. The fields
+// (and their order) are part of the interface, so only ever add new ones at the end:
+//
+//   view:     name, archived_at
+//   list:     name, quantity, description, unit, tags, archived_at
+//   rules -l: name, gets_below_quantity, expression, severity
+//   raport:   warehouse, name, quantity, description, gets_below_quantity, expression,
+//             severity, status, is_critical, daily_consumption, days_until_critical,
+//             days_until_empty
+//   diff:     warehouse, name, change, quantity_before, quantity_after, delta, rule_before,
+//             rule_after
+//
+// Missing values are `null` in JSON and empty cells in CSV/TSV.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Csv,
+    Tsv,
+    Html,
+    Markdown,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "html" => Ok(Format::Html),
+            "markdown" => Ok(Format::Markdown),
+            _ => Err(format!("Unknown format `{}` (expected text, json, csv, tsv, html or markdown).", value)),
+        }
+    }
+}
+
+// A row of machine readable output, `HEADERS` must match the serialized field names
+pub trait Record: Serialize {
+    const HEADERS: &'static [&'static str];
+}
+
+#[derive(Serialize)]
+pub struct WarehouseRecord {
+    pub name: String,
+    pub archived_at: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct ItemRecord {
+    pub name: String,
+    pub quantity: i64,
+    pub description: Option<String>,
+    pub unit: Option<String>,
+    pub tags: Option<String>,
+    pub archived_at: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct RuleRecord {
+    pub name: String,
+    pub gets_below_quantity: Option<i64>,
+    pub expression: Option<String>,
+    pub severity: String,
+}
+
+#[derive(Serialize)]
+pub struct RaportRecord {
+    pub warehouse: String,
+    pub name: String,
+    pub quantity: i64,
+    pub description: Option<String>,
+    pub gets_below_quantity: Option<i64>,
+    pub expression: Option<String>,
+    pub severity: String,
+    pub status: String,
+    pub is_critical: bool,
+    pub daily_consumption: f64,
+    pub days_until_critical: Option<u32>,
+    pub days_until_empty: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct DiffRecord {
+    pub warehouse: String,
+    pub name: String,
+    pub change: &'static str,
+    pub quantity_before: Option<i64>,
+    pub quantity_after: Option<i64>,
+    pub delta: i64,
+    pub rule_before: Option<String>,
+    pub rule_after: Option<String>,
+}
+
+impl Record for WarehouseRecord {
+    const HEADERS: &'static [&'static str] = &["name", "archived_at"];
+}
+
+impl Record for ItemRecord {
+    const HEADERS: &'static [&'static str] = &["name", "quantity", "description", "unit", "tags", "archived_at"];
+}
+
+impl Record for RuleRecord {
+    const HEADERS: &'static [&'static str] = &["name", "gets_below_quantity", "expression", "severity"];
+}
+
+impl Record for RaportRecord {
+    const HEADERS: &'static [&'static str] = &[
+        "warehouse", "name", "quantity", "description", "gets_below_quantity", "expression",
+        "severity", "status", "is_critical", "daily_consumption", "days_until_critical", "days_until_empty",
+    ];
+}
+
+impl Record for DiffRecord {
+    const HEADERS: &'static [&'static str] = &[
+        "warehouse", "name", "change", "quantity_before", "quantity_after", "delta", "rule_before", "rule_after",
+    ];
+}
+
+// Prints the records in a machine readable format. Headers are always printed for
+// CSV/TSV, even when there are no records.
+pub fn print_records<T: Record>(format: Format, records: &[T]) {
+    let delimiter = match format {
+        Format::Json => {
+            match serde_json::to_string_pretty(records) {
+                Ok(json) => { println!("{}", json); }
+                Err(err) => { println!("Unable to serialize the output. Reason: {}", err); }
+            }
+            return;
+        }
+        Format::Csv => b',',
+        Format::Tsv => b'\t',
+        Format::Text | Format::Html | Format::Markdown => { return; }
+    };
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .from_writer(std::io::stdout());
+
+    let result = writer.write_record(T::HEADERS)
+        .and_then(|_| records.iter().try_for_each(|record| writer.serialize(record)))
+        .and_then(|_| writer.flush().map_err(csv::Error::from));
+
+    if let Err(err) = result {
+        println!("Unable to write the output. Reason: {}", err);
+    }
+}
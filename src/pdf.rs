@@ -0,0 +1,357 @@
+use unicode_normalization::UnicodeNormalization;
+use crate::style::Color;
+use crate::table::{Align, Table};
+
+// Printable raports (`deposito raport --output=raport.pdf`): a title page, then a section per
+// warehouse starting on a new page, tables continuing over as many pages as they need.
+//
+// The PDF is written by hand and kept simple: A4 pages, the standard Helvetica fonts (which
+// every viewer has, so nothing gets embedded) and uncompressed content streams.
+//
+// https://opensource.adobe.com/dc-acrobat-sdk-docs/pdfstandards/PDF32000_2008.pdf
+
+const PAGE_WIDTH: f64 = 595.28;
+const PAGE_HEIGHT: f64 = 841.89;
+const MARGIN: f64 = 50.0;
+// Content stops here, leaving room for the page number
+const BOTTOM: f64 = 70.0;
+
+const FONT_SIZE: f64 = 9.0;
+const ROW_HEIGHT: f64 = 16.0;
+const CELL_PADDING: f64 = 5.0;
+const MIN_COLUMN_WIDTH: f64 = 40.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(&self) -> &'static str {
+        match self {
+            Font::Regular => "/F1",
+            Font::Bold => "/F2",
+        }
+    }
+
+    // Advance widths (per 1000 units of font size) of the printable ASCII characters,
+    // from the Adobe font metrics of Helvetica and Helvetica-Bold
+    fn widths(&self) -> &'static [u16; 95] {
+        const REGULAR: [u16; 95] = [
+            278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+            556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+            1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+            667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+            333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+            556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+        ];
+        const BOLD: [u16; 95] = [
+            278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+            556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+            975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+            667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+            333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+            611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+        ];
+
+        match self {
+            Font::Regular => &REGULAR,
+            Font::Bold => &BOLD,
+        }
+    }
+
+    fn text_width(&self, text: &str, size: f64) -> f64 {
+        let units: u32 = encode(text).iter().map(|byte| match byte {
+            32..=126 => self.widths()[(byte - 32) as usize] as u32,
+            // Close enough for the few accented letters and symbols there are
+            _ => 556,
+        }).sum();
+        units as f64 * size / 1000.0
+    }
+}
+
+fn rgb(color: Color) -> (f64, f64, f64) {
+    match color {
+        Color::Bold => (0.0, 0.0, 0.0),
+        Color::Red => (0.69, 0.0, 0.13),
+        Color::Green => (0.11, 0.48, 0.11),
+        Color::Yellow => (0.6, 0.4, 0.0),
+        Color::Blue => (0.1, 0.37, 0.71),
+    }
+}
+
+// The text of the title page
+pub struct TitlePage {
+    pub title: String,
+    pub subtitle: String,
+    pub lines: Vec<String>,
+}
+
+// Lays out content top to bottom, starting new pages as needed
+struct Layout {
+    pages: Vec<String>,
+    y: f64,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self { pages: Vec::new(), y: 0.0 }
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(String::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn content(&mut self) -> &mut String {
+        if self.pages.is_empty() {
+            self.new_page();
+        }
+        self.pages.last_mut().expect("there is a page")
+    }
+
+    fn text(&mut self, x: f64, y: f64, font: Font, size: f64, color: (f64, f64, f64), text: &str) {
+        let (r, g, b) = color;
+        let operators = format!("BT {} {} Tf {:.2} {:.2} {:.2} rg {:.2} {:.2} Td ({}) Tj ET\n", font.resource(), size, r, g, b, x, y, literal(text));
+        self.content().push_str(&operators);
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, color: (f64, f64, f64)) {
+        let (r, g, b) = color;
+        let operators = format!("{:.2} {:.2} {:.2} rg {:.2} {:.2} {:.2} {:.2} re f\n", r, g, b, x, y, width, height);
+        self.content().push_str(&operators);
+    }
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        let operators = format!("0.82 G 0.5 w {:.2} {:.2} m {:.2} {:.2} l S\n", x1, y1, x2, y2);
+        self.content().push_str(&operators);
+    }
+
+    fn heading(&mut self, text: &str, size: f64) {
+        self.y -= size;
+        let text = fit(text, PAGE_WIDTH - 2.0 * MARGIN, Font::Bold, size);
+        self.text(MARGIN, self.y, Font::Bold, size, (0.0, 0.0, 0.0), &text);
+        self.y -= size * 0.8;
+    }
+
+    // Draws the table, repeating its header (under `heading`) on every page it continues on
+    fn table(&mut self, table: &Table, heading: &str) {
+        let widths = column_widths(table);
+
+        self.table_header(table, &widths);
+        for cells in table.rows().iter() {
+            if self.y - ROW_HEIGHT < BOTTOM {
+                self.new_page();
+                self.heading(&format!("{} (continued)", heading), 12.0);
+                self.table_header(table, &widths);
+            }
+
+            // Rows with something red in them (CRITICAL) get a red background
+            if cells.iter().any(|cell| cell.color() == Some(Color::Red)) {
+                self.rect(MARGIN, self.y - ROW_HEIGHT, widths.iter().sum(), ROW_HEIGHT, (1.0, 0.92, 0.92));
+            }
+
+            let mut x = MARGIN;
+            for ((column, width), cell) in table.columns().iter().zip(widths.iter()).zip(cells.iter()) {
+                let font = match cell.color() {
+                    Some(Color::Red) | Some(Color::Bold) => Font::Bold,
+                    _ => Font::Regular,
+                };
+                let color = cell.color().map(rgb).unwrap_or((0.0, 0.0, 0.0));
+                self.cell(x, *width, column.align(), font, color, cell.text());
+                x += width;
+            }
+
+            self.y -= ROW_HEIGHT;
+            self.line(MARGIN, self.y, MARGIN + widths.iter().sum::<f64>(), self.y);
+        }
+    }
+
+    fn table_header(&mut self, table: &Table, widths: &[f64]) {
+        self.y -= 6.0;
+        self.rect(MARGIN, self.y - ROW_HEIGHT, widths.iter().sum(), ROW_HEIGHT, (0.93, 0.93, 0.93));
+
+        let mut x = MARGIN;
+        for (column, width) in table.columns().iter().zip(widths.iter()) {
+            self.cell(x, *width, column.align(), Font::Bold, (0.0, 0.0, 0.0), column.header());
+            x += width;
+        }
+        self.y -= ROW_HEIGHT;
+    }
+
+    fn cell(&mut self, x: f64, width: f64, align: Align, font: Font, color: (f64, f64, f64), text: &str) {
+        let text = fit(text, width - 2.0 * CELL_PADDING, font, FONT_SIZE);
+        let x = match align {
+            Align::Left => x + CELL_PADDING,
+            Align::Right => x + width - CELL_PADDING - font.text_width(&text, FONT_SIZE),
+        };
+        // Baseline a little above the middle of the row
+        self.text(x, self.y - ROW_HEIGHT + 5.0, font, FONT_SIZE, color, &text);
+    }
+}
+
+// Widths fitting the longest value of each column, shrinking the widest truncatable
+// columns when the table is wider than the page
+fn column_widths(table: &Table) -> Vec<f64> {
+    let mut widths: Vec<f64> = table.columns().iter().enumerate().map(|(index, column)| {
+        let header = Font::Bold.text_width(column.header(), FONT_SIZE);
+        table.rows().iter()
+            .filter_map(|cells| cells.get(index))
+            .map(|cell| Font::Bold.text_width(cell.text(), FONT_SIZE))
+            // A point to spare, so that rounding doesn't cut the longest value
+            .fold(header, f64::max) + 2.0 * CELL_PADDING + 1.0
+    }).collect();
+
+    let available = PAGE_WIDTH - 2.0 * MARGIN;
+    while widths.iter().sum::<f64>() > available {
+        let widest = widths.iter().enumerate()
+            .filter(|(index, width)| table.columns()[*index].truncates() && **width > MIN_COLUMN_WIDTH)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index);
+
+        match widest {
+            Some(index) => { widths[index] = (widths[index] - 1.0).max(MIN_COLUMN_WIDTH); }
+            None => { break; }
+        }
+    }
+
+    widths
+}
+
+// Cuts the text to the width, marking the cut with an ellipsis
+fn fit(text: &str, width: f64, font: Font, size: f64) -> String {
+    if font.text_width(text, size) <= width {
+        return text.to_string();
+    }
+
+    let mut fitted = String::new();
+    for char in text.chars() {
+        let mut candidate = fitted.clone();
+        candidate.push(char);
+        candidate.push('…');
+        if font.text_width(&candidate, size) > width { break; }
+        fitted.push(char);
+    }
+    fitted.push('…');
+    fitted
+}
+
+// Text in the fonts' WinAnsiEncoding. Letters it doesn't have lose their accents (`ą` is
+// written as `a`), anything else becomes `?`.
+fn encode(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for char in text.chars() {
+        let byte = match char {
+            ' '..='~' => char as u8,
+            '\u{a0}'..='\u{ff}' => char as u32 as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '→' => { bytes.extend_from_slice(b"->"); continue; }
+            char => {
+                match char.nfd().next() {
+                    Some(base @ (' '..='~' | '\u{a0}'..='\u{ff}')) => base as u32 as u8,
+                    _ => b'?',
+                }
+            }
+        };
+        bytes.push(byte);
+    }
+    bytes
+}
+
+// Contents of a literal string, `(` `)` and `\` escaped and the rest of the file kept ASCII
+fn literal(text: &str) -> String {
+    let mut literal = String::new();
+    for byte in encode(text) {
+        match byte {
+            b'(' | b')' | b'\\' => { literal.push('\\'); literal.push(byte as char); }
+            32..=126 => { literal.push(byte as char); }
+            byte => { literal.push_str(&format!("\\{:03o}", byte)); }
+        }
+    }
+    literal
+}
+
+pub fn raport(title_page: &TitlePage, summary: &Table, sections: &[(String, Table)]) -> Vec<u8> {
+    let mut layout = Layout::new();
+
+    // Title page, with the summary below the title
+    layout.new_page();
+    layout.y = PAGE_HEIGHT - 220.0;
+    layout.heading(&title_page.title, 32.0);
+    layout.y -= 6.0;
+    layout.text(MARGIN, layout.y, Font::Regular, 14.0, (0.2, 0.2, 0.2), &fit(&title_page.subtitle, PAGE_WIDTH - 2.0 * MARGIN, Font::Regular, 14.0));
+    layout.y -= 24.0;
+    for line in title_page.lines.iter() {
+        layout.text(MARGIN, layout.y, Font::Regular, 10.0, (0.45, 0.45, 0.45), &fit(line, PAGE_WIDTH - 2.0 * MARGIN, Font::Regular, 10.0));
+        layout.y -= 14.0;
+    }
+    layout.y -= 40.0;
+    layout.heading("Summary", 14.0);
+    layout.table(summary, "Summary");
+
+    for (heading, table) in sections.iter() {
+        layout.new_page();
+        layout.heading(heading, 16.0);
+        layout.table(table, heading);
+    }
+
+    // Numbering needs the number of pages, so it comes last
+    let count = layout.pages.len();
+    for index in 0..count {
+        let number = format!("Page {} of {}", index + 1, count);
+        let x = (PAGE_WIDTH - Font::Regular.text_width(&number, 8.0)) / 2.0;
+        let operators = format!("BT /F1 8 Tf 0.45 0.45 0.45 rg {:.2} 30 Td ({}) Tj ET\n", x, literal(&number));
+        layout.pages[index].push_str(&operators);
+    }
+
+    write(&layout.pages, &title_page.title)
+}
+
+// Objects 1 to 5 are the catalog, the page tree, both fonts and the document information,
+// followed by each page and its content stream
+fn write(pages: &[String], title: &str) -> Vec<u8> {
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+
+    let kids: Vec<String> = (0..pages.len()).map(|index| format!("{} 0 R", 6 + 2 * index)).collect();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes());
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec());
+    objects.push(format!("<< /Title ({}) /Producer (deposito) >>", literal(title)).into_bytes());
+
+    for (index, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, 7 + 2 * index,
+        ).into_bytes());
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content).into_bytes());
+    }
+
+    // The binary comment tells transfer programs not to treat the file as text
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(format!("trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes());
+
+    pdf
+}
@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use crate::expr::{Context, Expr, ExprError, Severity};
+use crate::output::RaportRecord;
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+const CONSUMPTION_WINDOW_DAYS: f64 = 30.0;
+const FORECAST_LIMIT_DAYS: u32 = 365;
+
+pub struct Movement {
+    pub item: String,
+    pub delta: i64,
+    pub created_at: i64,
+}
+
+pub struct Rule {
+    pub gets_below_quantity: Option<i64>,
+    pub expression: Option<String>,
+    pub severity: Severity,
+}
+
+impl Rule {
+    // Short human readable form of the condition, used next to the status
+    pub fn describe(&self) -> String {
+        match (&self.expression, self.gets_below_quantity) {
+            (Some(expression), _) => format!("When {}", expression),
+            (None, Some(quantity)) => format!("Can't go below {}", quantity),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+pub enum Status {
+    Ok,
+    Triggered(Severity),
+    Invalid(ExprError),
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Triggered(severity) => severity.as_str(),
+            Status::Invalid(_) => "invalid",
+        }
+    }
+}
+
+// Files `deposito raport --output` can write, told apart by their extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RaportFile {
+    Xlsx,
+    Ods,
+    Pdf,
+}
+
+impl RaportFile {
+    pub fn from_path(path: &str) -> Result<Self, String> {
+        let extension = std::path::Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+        match extension.to_lowercase().as_str() {
+            "xlsx" => Ok(RaportFile::Xlsx),
+            "ods" => Ok(RaportFile::Ods),
+            "pdf" => Ok(RaportFile::Pdf),
+            _ => Err(format!("Can't write a raport to `{}`, the file has to end in .xlsx, .ods or .pdf.", path)),
+        }
+    }
+}
+
+pub struct Forecast {
+    pub daily_consumption: f64,
+    pub days_until_critical: Option<u32>,
+    pub days_until_empty: Option<f64>,
+}
+
+pub struct RaportEntry {
+    pub name: String,
+    pub quantity: i64,
+    pub description: Option<String>,
+    pub rule: Rule,
+    pub status: Status,
+    pub forecast: Forecast,
+}
+
+impl RaportEntry {
+    pub fn to_record(&self, warehouse_name: &str) -> RaportRecord {
+        RaportRecord {
+            warehouse: warehouse_name.to_string(),
+            name: self.name.clone(),
+            quantity: self.quantity,
+            description: self.description.clone(),
+            gets_below_quantity: self.rule.gets_below_quantity,
+            expression: self.rule.expression.clone(),
+            severity: self.rule.severity.to_string(),
+            status: self.status.as_str().to_string(),
+            is_critical: matches!(self.status, Status::Triggered(Severity::Critical)),
+            daily_consumption: self.forecast.daily_consumption,
+            days_until_critical: self.forecast.days_until_critical,
+            days_until_empty: self.forecast.days_until_empty,
+        }
+    }
+}
+
+// Snapshot of a single warehouse that rules are evaluated against. Movements are grouped
+// by item and consumption rates worked out once, forecasts look them up for every day.
+pub struct WarehouseState {
+    quantities: HashMap<String, i64>,
+    movements: HashMap<String, Vec<Movement>>,
+    daily_consumption: HashMap<String, f64>,
+    now: i64,
+}
+
+impl WarehouseState {
+    pub fn new(quantities: HashMap<String, i64>, movements: Vec<Movement>, now: i64) -> Self {
+        let mut by_item: HashMap<String, Vec<Movement>> = HashMap::new();
+        for movement in movements {
+            by_item.entry(movement.item.clone()).or_default().push(movement);
+        }
+
+        let daily_consumption = by_item.iter()
+            .map(|(item, movements)| (item.clone(), WarehouseState::consumption_rate(movements, now)))
+            .collect();
+        Self { quantities, movements: by_item, daily_consumption, now }
+    }
+
+    pub fn evaluate(&self, item: &str, rule: &Rule) -> Status {
+        let expr = match WarehouseState::parse_rule(rule) {
+            Ok(expr) => expr,
+            Err(err) => { return Status::Invalid(err); }
+        };
+
+        match self.is_triggered(item, rule, expr.as_ref(), 0.0) {
+            Ok(true) => Status::Triggered(rule.severity),
+            Ok(false) => Status::Ok,
+            Err(err) => Status::Invalid(err),
+        }
+    }
+
+    pub fn daily_consumption(&self, item: &str) -> f64 {
+        self.daily_consumption.get(item).copied().unwrap_or_default()
+    }
+
+    // Average daily outflow over the last `CONSUMPTION_WINDOW_DAYS` days (or since the first
+    // movement, if that is more recent)
+    fn consumption_rate(movements: &[Movement], now: i64) -> f64 {
+        let first = match movements.iter().map(|movement| movement.created_at).min() {
+            Some(first) => first,
+            None => { return 0.0; }
+        };
+
+        let window = ((now - first) as f64 / SECONDS_PER_DAY).clamp(1.0, CONSUMPTION_WINDOW_DAYS);
+        let since = now - (window * SECONDS_PER_DAY) as i64;
+        let outflow: i64 = movements.iter()
+            .filter(|movement| movement.created_at >= since && movement.delta < 0)
+            .map(|movement| -movement.delta)
+            .sum();
+
+        outflow as f64 / window
+    }
+
+    // Projects the item forward at its current consumption rate
+    pub fn forecast(&self, item: &str, rule: &Rule) -> Forecast {
+        let daily_consumption = self.daily_consumption(item);
+        let quantity = self.quantities.get(item).copied().unwrap_or_default();
+
+        let days_until_empty = if daily_consumption > 0.0 {
+            Some(quantity.max(0) as f64 / daily_consumption)
+        } else {
+            None
+        };
+
+        let mut days_until_critical = None;
+        if rule.severity == Severity::Critical {
+            if let Ok(expr) = WarehouseState::parse_rule(rule) {
+                let triggered = |day: u32| matches!(self.is_triggered(item, rule, expr.as_ref(), day as f64), Ok(true));
+                days_until_critical = match (&expr, rule.gets_below_quantity) {
+                    (None, Some(gets_below_quantity)) => {
+                        // The projection falls linearly until it stops at 0, so the day follows
+                        // from the rate. The days around it are checked for rounding.
+                        let day = if triggered(0) {
+                            Some(0)
+                        } else if gets_below_quantity <= 0 || daily_consumption <= 0.0 {
+                            None
+                        } else {
+                            let estimate = ((quantity - gets_below_quantity) as f64 / daily_consumption).floor().min(FORECAST_LIMIT_DAYS as f64) as u32 + 1;
+                            (estimate.saturating_sub(1)..=estimate + 1).find(|day| triggered(*day))
+                        };
+                        day.filter(|day| *day <= FORECAST_LIMIT_DAYS)
+                    }
+                    _ => { (0..=FORECAST_LIMIT_DAYS).find(|day| triggered(*day)) }
+                };
+            }
+        }
+
+        Forecast { daily_consumption, days_until_critical, days_until_empty }
+    }
+
+    fn parse_rule(rule: &Rule) -> Result<Option<Expr>, ExprError> {
+        match &rule.expression {
+            Some(expression) => Expr::parse(expression).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn is_triggered(&self, item: &str, rule: &Rule, expr: Option<&Expr>, days_ahead: f64) -> Result<bool, ExprError> {
+        let context = ItemContext { state: self, item, days_ahead };
+
+        match (expr, rule.gets_below_quantity) {
+            (Some(expr), _) => expr.evaluate(&context),
+            (None, Some(gets_below_quantity)) => Ok((gets_below_quantity as f64) > context.quantity()),
+            (None, None) => Ok(false),
+        }
+    }
+}
+
+struct ItemContext<'a> {
+    state: &'a WarehouseState,
+    item: &'a str,
+    days_ahead: f64,
+}
+
+impl<'a> ItemContext<'a> {
+    fn movements(&self) -> impl Iterator<Item = &'a Movement> + '_ {
+        self.state.movements.get(self.item).into_iter().flatten()
+    }
+}
+
+impl Context for ItemContext<'_> {
+    fn quantity(&self) -> f64 {
+        self.item_quantity(self.item).unwrap_or_default()
+    }
+
+    fn item_quantity(&self, name: &str) -> Option<f64> {
+        let quantity = *self.state.quantities.get(name)? as f64;
+        if self.days_ahead == 0.0 {
+            return Some(quantity);
+        }
+
+        let projected = quantity - self.state.daily_consumption(name) * self.days_ahead;
+        Some(projected.max(0.0))
+    }
+
+    fn consumption(&self, days: f64) -> f64 {
+        let since = self.state.now - (days * SECONDS_PER_DAY) as i64;
+        self.movements()
+            .filter(|movement| movement.created_at >= since && movement.delta < 0)
+            .map(|movement| -movement.delta as f64)
+            .sum()
+    }
+
+    fn days_idle(&self) -> f64 {
+        match self.movements().map(|movement| movement.created_at).max() {
+            Some(last) => (self.state.now - last) as f64 / SECONDS_PER_DAY + self.days_ahead,
+            None => f64::INFINITY,
+        }
+    }
+}
+
+pub fn unix_now() -> i64 {
+    match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(_) => 0,
+    }
+}
+
+// `2024-06-10 14:03 UTC`
+pub fn format_timestamp(timestamp: i64) -> String {
+    let (year, month, day, hour, minute, _) = civil(timestamp);
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, hour, minute)
+}
+
+// A Unix timestamp as UTC year, month, day, hour, minute and second
+pub fn civil(timestamp: i64) -> (i64, i64, i64, i64, i64, i64) {
+    let days = timestamp.div_euclid(86400);
+    let seconds = timestamp.rem_euclid(86400);
+
+    // Days since 1970-01-01 to a civil date, http://howardhinnant.github.io/date_algorithms.html
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day, seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+// Parses a forecast horizon such as `14d`, `2w` or plain `14` into days
+pub fn parse_horizon(value: &str) -> Result<u32, String> {
+    let (number, multiplier) = match value.chars().last() {
+        Some('d') => (&value[..value.len()-1], 1),
+        Some('w') => (&value[..value.len()-1], 7),
+        _ => (value, 1),
+    };
+
+    match number.parse::<u32>() {
+        Ok(days) if days <= FORECAST_LIMIT_DAYS / multiplier => Ok(days * multiplier),
+        Ok(_) => Err(format!("Horizon `{}` is too far, at most {} days are supported.", value, FORECAST_LIMIT_DAYS)),
+        Err(_) => Err(format!("Invalid horizon `{}` (expected e.g. `14d` or `2w`).", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_718_000_000;
+
+    // Movements as (item, delta, days ago)
+    fn state(quantities: &[(&str, i64)], movements: &[(&str, i64, f64)]) -> WarehouseState {
+        let quantities = quantities.iter().map(|(item, quantity)| (item.to_string(), *quantity)).collect();
+        let movements = movements.iter()
+            .map(|(item, delta, days_ago)| Movement { item: item.to_string(), delta: *delta, created_at: NOW - (days_ago * SECONDS_PER_DAY) as i64 })
+            .collect();
+        WarehouseState::new(quantities, movements, NOW)
+    }
+
+    fn threshold(gets_below_quantity: i64) -> Rule {
+        Rule { gets_below_quantity: Some(gets_below_quantity), expression: None, severity: Severity::Critical }
+    }
+
+    fn expression(expression: &str) -> Rule {
+        Rule { gets_below_quantity: None, expression: Some(expression.to_string()), severity: Severity::Critical }
+    }
+
+    #[test]
+    fn consumption_over_the_last_30_days() {
+        let state = state(&[("Egg", 6)], &[("Egg", 30, 45.0), ("Egg", -12, 40.0), ("Egg", -6, 10.0), ("Egg", -6, 2.0), ("Egg", 2, 1.0)]);
+        assert_eq!(state.daily_consumption("Egg"), 12.0 / 30.0);
+        assert_eq!(state.daily_consumption("Milk"), 0.0);
+    }
+
+    #[test]
+    fn consumption_window_starts_at_the_first_movement() {
+        let state = state(&[("Milk", 5), ("Jam", 2)], &[("Milk", 10, 5.0), ("Milk", -2, 4.0), ("Milk", -3, 1.0), ("Jam", -4, 0.1)]);
+        assert_eq!(state.daily_consumption("Milk"), 1.0);
+        // At least a day, so that a movement a moment ago isn't a huge rate
+        assert_eq!(state.daily_consumption("Jam"), 4.0);
+    }
+
+    #[test]
+    fn days_until_critical_and_empty() {
+        let state = state(&[("Milk", 5), ("Salt", 1000), ("Jam", 3)], &[("Milk", 10, 5.0), ("Milk", -5, 4.0), ("Salt", -30, 30.0)]);
+
+        let forecast = state.forecast("Milk", &threshold(2));
+        assert_eq!(forecast.days_until_critical, Some(4));
+        assert_eq!(forecast.days_until_empty, Some(5.0));
+        assert_eq!(state.forecast("Milk", &threshold(6)).days_until_critical, Some(0));
+        assert_eq!(state.forecast("Milk", &threshold(0)).days_until_critical, None);
+
+        // Beyond a year isn't forecast
+        assert_eq!(state.forecast("Salt", &threshold(10)).days_until_critical, None);
+        assert_eq!(state.forecast("Salt", &threshold(700)).days_until_critical, Some(301));
+
+        // Nothing is consumed
+        let forecast = state.forecast("Jam", &threshold(2));
+        assert_eq!(forecast.days_until_critical, None);
+        assert_eq!(forecast.days_until_empty, None);
+
+        let warn = Rule { severity: Severity::Warn, ..threshold(2) };
+        assert_eq!(state.forecast("Milk", &warn).days_until_critical, None);
+    }
+
+    #[test]
+    fn thresholds_agree_with_expressions() {
+        // Thresholds are worked out directly, expressions day by day
+        for (quantity, consumed) in [(5, 5), (7, 3), (100, 7), (3, 9), (0, 4), (-2, 1)] {
+            let state = state(&[("Egg", quantity)], &[("Egg", -consumed, 3.0)]);
+            for gets_below_quantity in -1..12 {
+                let expected = state.forecast("Egg", &expression(&format!("quantity < {}", gets_below_quantity))).days_until_critical;
+                assert_eq!(state.forecast("Egg", &threshold(gets_below_quantity)).days_until_critical, expected, "{} consumed by {} below {}", quantity, consumed, gets_below_quantity);
+            }
+        }
+    }
+
+    #[test]
+    fn expressions_see_projected_quantities() {
+        let state = state(&[("Egg", 10), ("Box", 4)], &[("Egg", -20, 10.0), ("Box", -1, 1.0)]);
+        let rule = expression("Egg + Box * 2 < 8");
+        assert!(matches!(state.evaluate("Egg", &rule), Status::Ok));
+        // 10 - 2d + (4 - d) * 2 < 8 from day 3 on
+        assert_eq!(state.forecast("Egg", &rule).days_until_critical, Some(3));
+        assert!(matches!(state.evaluate("Egg", &expression("days_idle > 5")), Status::Triggered(Severity::Critical)));
+        assert!(matches!(state.evaluate("Egg", &expression("Nope < 1")), Status::Invalid(_)));
+    }
+
+    #[test]
+    fn horizons() {
+        assert_eq!(parse_horizon("14d"), Ok(14));
+        assert_eq!(parse_horizon("2w"), Ok(14));
+        assert_eq!(parse_horizon("14"), Ok(14));
+        assert!(parse_horizon("53w").is_err());
+        assert!(parse_horizon("soon").is_err());
+    }
+}
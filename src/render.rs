@@ -0,0 +1,92 @@
+use crate::raport;
+use crate::table::Table;
+
+// Raports as a self-contained HTML page or as Markdown (`deposito raport --format`), made of
+// titled sections holding the same tables the text output prints.
+
+const CSS: &str = "\
+body { font-family: system-ui, -apple-system, \"Segoe UI\", sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.6em; }
+h2 { font-size: 1.2em; margin-top: 1.6em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #d0d0d0; padding: 0.3em 0.8em; text-align: left; }
+th { background: #f3f3f3; }
+td.right { text-align: right; }
+.red { color: #b00020; font-weight: bold; }
+.green { color: #1b7a1b; }
+.yellow { color: #9a6700; font-weight: bold; }
+.blue { color: #1a5fb4; }
+.bold { font-weight: bold; }
+.stamp { margin-top: 2em; color: #777; font-size: 0.9em; }
+";
+
+// When and where a raport was made, for `--stamp`
+pub struct Stamp {
+    pub generated_at: i64,
+    pub workspace: String,
+}
+
+impl Stamp {
+    pub fn describe(&self) -> (String, &str) {
+        (format!("Generated on {} in", raport::format_timestamp(self.generated_at)), &self.workspace)
+    }
+}
+
+pub fn html(title: &str, sections: &[(String, Table)], stamp: Option<&Stamp>) -> String {
+    let mut html = format!(concat!(
+        "<!DOCTYPE html>\n",
+        "<html lang=\"en\">\n",
+        "<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n",
+        "<body>\n<h1>{}</h1>\n",
+    ), escape(title), CSS, escape(title));
+
+    for (heading, table) in sections.iter() {
+        html.push_str(&format!("<h2>{}</h2>\n", escape(heading)));
+        html.push_str(&table.html());
+    }
+
+    if let Some(stamp) = stamp {
+        let (generated, workspace) = stamp.describe();
+        html.push_str(&format!("<p class=\"stamp\">{} <code>{}</code></p>\n", escape(&generated), escape(workspace)));
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+pub fn markdown(title: &str, sections: &[(String, Table)], stamp: Option<&Stamp>) -> String {
+    let mut markdown = format!("# {}\n", title);
+
+    for (heading, table) in sections.iter() {
+        markdown.push_str(&format!("\n## {}\n\n", heading));
+        for line in table.markdown() {
+            markdown.push_str(&line);
+            markdown.push('\n');
+        }
+    }
+
+    if let Some(stamp) = stamp {
+        let (generated, workspace) = stamp.describe();
+        markdown.push_str(&format!("\n_{} `{}`_\n", generated, workspace));
+    }
+
+    markdown
+}
+
+// Escapes text for HTML and XML
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Not allowed in XML at all
+            char if (char as u32) < 0x20 && !matches!(char, '\t' | '\n' | '\r') => {}
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,127 @@
+use crate::cli::{self, Arity, CommandSpec, Suggest};
+use crate::commands::COMMANDS;
+use crate::complete::{self, Completion};
+use crate::editor::Completions;
+
+// Helpers of `deposito shell`, the loop itself is `Deposito::shell`
+
+// Warehouse and item names for completion, reloaded after every command so that
+// completing doesn't have to wait for the database
+#[derive(Default)]
+pub struct Names {
+    pub warehouses: Vec<String>,
+    // (warehouse, item)
+    pub items: Vec<(String, String)>,
+}
+
+impl Names {
+    fn items_of(&self, warehouse_name: &str) -> Vec<String> {
+        self.items.iter()
+            .filter(|(warehouse, _)| warehouse == warehouse_name)
+            .map(|(_, item)| item.clone())
+            .collect()
+    }
+
+    fn is_warehouse(&self, name: &str) -> bool {
+        self.warehouses.iter().any(|warehouse| warehouse == name)
+    }
+}
+
+// Completes the word at the end of `line` (everything before the cursor)
+pub fn complete(line: &str, names: &Names, default_warehouse: Option<&str>) -> Completions {
+    // The current word starts after the last whitespace outside of quotes
+    let mut start = 0;
+    let mut quote: Option<char> = None;
+    for (index, char) in line.char_indices() {
+        match (quote, char) {
+            (Some(open), char) if char == open => { quote = None; }
+            (Some(_), _) => { }
+            (None, '\'' | '"') => { quote = Some(char); }
+            (None, char) if char.is_whitespace() => { start = index + char.len_utf8(); }
+            (None, _) => { }
+        }
+    }
+
+    let mut words = match shlex::split(&line[..start]) {
+        Some(words) => words,
+        None => { return Completions { start, candidates: Vec::new() }; }
+    };
+    let current: String = line[start..].chars().filter(|char| *char != '\'' && *char != '"').collect();
+
+    let candidates: Vec<String> = match words.as_slice() {
+        [] => {
+            let commands = COMMANDS.iter().map(|command| command.name).filter(|name| *name != "shell");
+            let shell = ["use", "help", "exit", "quit"];
+            commands.chain(shell).map(str::to_string).collect()
+        }
+        [command] if command == "use" => names.warehouses.clone(),
+        _ => {
+            let previous_words = words.len();
+            words.push(current.clone());
+            match complete::analyze(&words[..previous_words], &current) {
+                Completion::Words(words) => words,
+                Completion::Warehouses => {
+                    // The warehouse may be left out, so the word could be an item already
+                    let mut candidates = names.warehouses.clone();
+                    if let Some(default_warehouse) = default_warehouse {
+                        candidates.extend(names.items_of(default_warehouse));
+                    }
+                    candidates
+                }
+                Completion::Items(warehouse_name) if names.is_warehouse(&warehouse_name) => names.items_of(&warehouse_name),
+                Completion::Items(_) => {
+                    match default_warehouse {
+                        Some(default_warehouse) => names.items_of(default_warehouse),
+                        None => Vec::new(),
+                    }
+                }
+            }
+        }
+    };
+
+    let candidates = candidates.iter()
+        .filter(|candidate| candidate.starts_with(&current))
+        .map(|candidate| match shlex::try_quote(candidate) {
+            Ok(quoted) => quoted.into_owned(),
+            Err(_) => candidate.clone(),
+        })
+        .collect();
+
+    Completions { start, candidates }
+}
+
+// Puts the warehouse in use in front of the arguments when the command takes a warehouse
+// but wasn't given an existing one. Only done when that makes the line valid, so typos
+// still get reported against what was typed.
+pub fn with_default_warehouse(words: Vec<String>, default_warehouse: Option<&str>, names: &Names) -> Vec<String> {
+    let default_warehouse = match default_warehouse {
+        Some(default_warehouse) => default_warehouse,
+        None => { return words; }
+    };
+
+    let command = match words.first().and_then(|word| CommandSpec::find(word)) {
+        Some(command) => command,
+        None => { return words; }
+    };
+
+    let takes_warehouse = command.forms.iter().any(|form| {
+        form.args.first().is_some_and(|arg| arg.suggest == Suggest::Warehouses && arg.arity == Arity::Required)
+    });
+    if !takes_warehouse {
+        return words;
+    }
+
+    if let Ok(matches) = cli::parse(&words) {
+        match matches.arg("warehouse") {
+            Some(warehouse_name) if !names.is_warehouse(warehouse_name) => { }
+            _ => { return words; }
+        }
+    }
+
+    let mut with_default = words.clone();
+    with_default.insert(1, default_warehouse.to_string());
+    match cli::parse(&with_default) {
+        Ok(_) => with_default,
+        Err(_) => words,
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+// Comparing the items of two points in time for `deposito diff`, each a snapshot or the
+// current state of the workspace.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub gets_below_quantity: Option<i64>,
+    pub expression: Option<String>,
+    pub severity: String,
+}
+
+impl Rule {
+    // The condition as `rules -l` shows it, with the severity unless it's the default
+    pub fn describe(&self) -> String {
+        let condition = match (&self.expression, self.gets_below_quantity) {
+            (Some(expression), _) => expression.clone(),
+            (None, gets_below_quantity) => format!("can't get below {}", gets_below_quantity.unwrap_or_default()),
+        };
+
+        match self.severity.as_str() {
+            "critical" => condition,
+            severity => format!("{} ({})", condition, severity),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub warehouse: String,
+    pub name: String,
+    pub quantity: i64,
+    pub rule: Option<Rule>,
+}
+
+impl Item {
+    // From a row of items left joined with rules, or of `snapshot_items`
+    pub fn new(warehouse: String, name: String, quantity: i64, gets_below_quantity: Option<i64>, expression: Option<String>, severity: Option<String>) -> Self {
+        let rule = severity.map(|severity| Rule { gets_below_quantity, expression, severity });
+        Self { warehouse, name, quantity, rule }
+    }
+}
+
+pub enum Change<'a> {
+    Added(&'a Item),
+    Removed(&'a Item),
+    Changed { before: &'a Item, after: &'a Item },
+}
+
+impl Change<'_> {
+    pub fn name(&self) -> &str {
+        match self {
+            Change::Added(item) | Change::Removed(item) | Change::Changed { after: item, .. } => &item.name,
+        }
+    }
+}
+
+// What changed from `before` to `after`, per warehouse, both in name order. Items are
+// matched by warehouse and name, so an item moved to another warehouse is removed from
+// one and added to the other.
+pub fn diff<'a>(before: &'a [Item], after: &'a [Item]) -> Vec<(&'a str, Vec<Change<'a>>)> {
+    let key = |item: &'a Item| ((item.warehouse.as_str(), item.name.as_str()), item);
+    let before: BTreeMap<_, _> = before.iter().map(key).collect();
+    let after: BTreeMap<_, _> = after.iter().map(key).collect();
+    let keys: BTreeSet<_> = before.keys().chain(after.keys()).copied().collect();
+
+    let mut warehouses: Vec<(&str, Vec<Change>)> = Vec::new();
+    for (warehouse, name) in keys {
+        let change = match (before.get(&(warehouse, name)), after.get(&(warehouse, name))) {
+            (None, Some(item)) => Change::Added(item),
+            (Some(item), None) => Change::Removed(item),
+            (Some(before), Some(after)) if before.quantity != after.quantity || before.rule != after.rule => Change::Changed { before, after },
+            _ => continue,
+        };
+
+        match warehouses.last_mut() {
+            Some((last, changes)) if *last == warehouse => changes.push(change),
+            _ => warehouses.push((warehouse, vec![change])),
+        }
+    }
+    warehouses
+}
@@ -0,0 +1,325 @@
+use crate::render::escape;
+use crate::zip::ZipWriter;
+
+// Workbooks of plain values written as XLSX (Office Open XML) or ODS (OpenDocument), for
+// `deposito raport --output`. Only what raports use is supported: text and numbers, a bold
+// header row (and footer), column widths and rows highlighted by the value of a column.
+
+// Excel limits sheet names to 31 characters and doesn't allow these in them
+const MAX_SHEET_NAME: usize = 31;
+const SHEET_NAME_FORBIDDEN: &[char] = &['[', ']', ':', '*', '?', '/', '\\'];
+
+#[derive(Clone)]
+pub enum Value {
+    Empty,
+    Text(String),
+    Number(f64),
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::Text(text.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Value::Text(text)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(number: i64) -> Self {
+        Value::Number(number as f64)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(number: usize) -> Self {
+        Value::Number(number as f64)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Value::Empty)
+    }
+}
+
+impl Value {
+    fn width(&self) -> usize {
+        match self {
+            Value::Empty => 0,
+            Value::Text(text) => text.chars().count(),
+            Value::Number(number) => number.to_string().len(),
+        }
+    }
+}
+
+// Rows whose `column` holds `text` are highlighted in red, as conditional formatting so
+// that the highlight follows edits made in the spreadsheet
+pub struct Highlight {
+    pub column: usize,
+    pub text: &'static str,
+}
+
+pub struct Sheet {
+    pub name: String,
+    pub headers: Vec<&'static str>,
+    pub rows: Vec<Vec<Value>>,
+    pub footer: Option<Vec<Value>>,
+    pub highlight: Option<Highlight>,
+}
+
+impl Sheet {
+    // Column widths in characters, enough for the longest value
+    fn widths(&self) -> Vec<usize> {
+        (0..self.headers.len()).map(|column| {
+            let values = self.rows.iter().chain(self.footer.iter()).filter_map(|row| row.get(column)).map(Value::width);
+            values.chain(std::iter::once(self.headers[column].chars().count())).max().unwrap_or_default().max(8) + 2
+        }).collect()
+    }
+
+    // Header, rows and footer, with whether they are bold
+    fn all_rows(&self) -> Vec<(Vec<Value>, bool)> {
+        let mut rows = vec![(self.headers.iter().map(|header| Value::from(*header)).collect(), true)];
+        rows.extend(self.rows.iter().map(|row| (row.clone(), false)));
+        if let Some(footer) = self.footer.as_ref() {
+            rows.push((footer.clone(), true));
+        }
+        rows
+    }
+}
+
+#[derive(Default)]
+pub struct Workbook {
+    pub sheets: Vec<Sheet>,
+}
+
+impl Workbook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds a sheet, making its name valid and unique (`Fridge`, `Fridge (2)`, ...)
+    pub fn sheet(&mut self, name: &str, headers: Vec<&'static str>) -> &mut Sheet {
+        let base: String = name.chars().map(|char| if SHEET_NAME_FORBIDDEN.contains(&char) { '_' } else { char }).collect();
+        let base = match base.trim_matches('\'').trim() {
+            "" => "Sheet",
+            base => base,
+        };
+
+        let mut name: String = base.chars().take(MAX_SHEET_NAME).collect();
+        let mut count = 1;
+        while self.sheets.iter().any(|sheet| sheet.name.to_lowercase() == name.to_lowercase()) {
+            count += 1;
+            let suffix = format!(" ({})", count);
+            name = base.chars().take(MAX_SHEET_NAME - suffix.len()).collect::<String>() + &suffix;
+        }
+
+        self.sheets.push(Sheet { name, headers, rows: Vec::new(), footer: None, highlight: None });
+        self.sheets.last_mut().expect("a sheet was just added")
+    }
+
+    pub fn xlsx(&self) -> Vec<u8> {
+        let mut zip = ZipWriter::new();
+
+        let mut content_types = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+            r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+            r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+            r#"<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+            r#"<Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>"#,
+        ));
+        for index in 1..=self.sheets.len() {
+            content_types.push_str(&format!(r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#, index));
+        }
+        content_types.push_str("</Types>");
+        zip.file("[Content_Types].xml", content_types.as_bytes());
+
+        zip.file("_rels/.rels", concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+            r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>"#,
+            r#"</Relationships>"#,
+        ).as_bytes());
+
+        let mut workbook = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>"#,
+        ));
+        let mut relationships = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+        ));
+        for (index, sheet) in self.sheets.iter().enumerate() {
+            let id = index + 1;
+            workbook.push_str(&format!(r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#, escape(&sheet.name), id, id));
+            relationships.push_str(&format!(r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#, id, id));
+        }
+        workbook.push_str("</sheets></workbook>");
+        relationships.push_str(&format!(r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#, self.sheets.len() + 1));
+        relationships.push_str("</Relationships>");
+        zip.file("xl/workbook.xml", workbook.as_bytes());
+        zip.file("xl/_rels/workbook.xml.rels", relationships.as_bytes());
+
+        // Cell format 1 is bold, differential format 0 the highlight
+        zip.file("xl/styles.xml", concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+            r#"<fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts>"#,
+            r#"<fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills>"#,
+            r#"<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders>"#,
+            r#"<cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>"#,
+            r#"<cellXfs count="2"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/></cellXfs>"#,
+            r#"<cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles>"#,
+            r#"<dxfs count="1"><dxf><font><color rgb="FF9C0006"/></font><fill><patternFill><bgColor rgb="FFFFC7CE"/></patternFill></fill></dxf></dxfs>"#,
+            r#"</styleSheet>"#,
+        ).as_bytes());
+
+        for (index, sheet) in self.sheets.iter().enumerate() {
+            zip.file(&format!("xl/worksheets/sheet{}.xml", index + 1), xlsx_sheet(sheet).as_bytes());
+        }
+
+        zip.finish()
+    }
+
+    pub fn ods(&self) -> Vec<u8> {
+        let mut zip = ZipWriter::new();
+
+        // Has to come first, uncompressed, for the file to be recognised
+        zip.file("mimetype", b"application/vnd.oasis.opendocument.spreadsheet");
+        zip.file("META-INF/manifest.xml", concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">"#,
+            r#"<manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.spreadsheet"/>"#,
+            r#"<manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>"#,
+            r#"<manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/>"#,
+            r#"</manifest:manifest>"#,
+        ).as_bytes());
+
+        // Conditions can only apply common styles, so the highlight lives in styles.xml
+        zip.file("styles.xml", concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<office:document-styles xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" office:version="1.2">"#,
+            r#"<office:styles><style:style style:name="Highlight" style:family="table-cell">"#,
+            r##"<style:table-cell-properties fo:background-color="#ffc7ce"/><style:text-properties fo:color="#9c0006"/>"##,
+            r#"</style:style></office:styles>"#,
+            r#"</office:document-styles>"#,
+        ).as_bytes());
+
+        let mut styles = String::from(r#"<style:style style:name="bold" style:family="table-cell"><style:text-properties fo:font-weight="bold"/></style:style>"#);
+        let mut body = String::new();
+        for (index, sheet) in self.sheets.iter().enumerate() {
+            let widths = sheet.widths();
+            for (column, width) in widths.iter().enumerate() {
+                styles.push_str(&format!(r#"<style:style style:name="s{}c{}" style:family="table-column"><style:table-column-properties style:column-width="{:.2}cm"/></style:style>"#, index, column, *width as f64 * 0.22));
+            }
+
+            // Applied to every cell below the header, the condition's row is relative to A2
+            let highlight_style = sheet.highlight.as_ref().map(|highlight| {
+                let name = format!("s{}h", index);
+                styles.push_str(&format!(
+                    r#"<style:style style:name="{}" style:family="table-cell"><style:map style:condition="is-true-formula([.${}2]=&quot;{}&quot;)" style:apply-style-name="Highlight" style:base-cell-address="'{}'.A2"/></style:style>"#,
+                    name, column_letter(highlight.column), escape(highlight.text), escape(&sheet.name.replace('\'', "''")),
+                ));
+                name
+            });
+
+            body.push_str(&format!(r#"<table:table table:name="{}">"#, escape(&sheet.name)));
+            for column in 0..widths.len() {
+                body.push_str(&format!(r#"<table:table-column table:style-name="s{}c{}"/>"#, index, column));
+            }
+            for (row, bold) in sheet.all_rows() {
+                body.push_str("<table:table-row>");
+                let style = match (bold, highlight_style.as_ref()) {
+                    (true, _) => String::from(r#" table:style-name="bold""#),
+                    (false, Some(style)) => format!(r#" table:style-name="{}""#, style),
+                    (false, None) => String::new(),
+                };
+                for value in row.iter() {
+                    match value {
+                        Value::Empty => { body.push_str(&format!("<table:table-cell{}/>", style)); }
+                        Value::Text(text) => {
+                            body.push_str(&format!(r#"<table:table-cell{} office:value-type="string"><text:p>{}</text:p></table:table-cell>"#, style, escape(text)));
+                        }
+                        Value::Number(number) => {
+                            body.push_str(&format!(r#"<table:table-cell{} office:value-type="float" office:value="{}"><text:p>{}</text:p></table:table-cell>"#, style, number, number));
+                        }
+                    }
+                }
+                body.push_str("</table:table-row>");
+            }
+            body.push_str("</table:table>");
+        }
+
+        let content = format!(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" office:version="1.2">"#,
+            r#"<office:automatic-styles>{}</office:automatic-styles>"#,
+            r#"<office:body><office:spreadsheet>{}</office:spreadsheet></office:body>"#,
+            r#"</office:document-content>"#,
+        ), styles, body);
+        zip.file("content.xml", content.as_bytes());
+
+        zip.finish()
+    }
+}
+
+fn xlsx_sheet(sheet: &Sheet) -> String {
+    let mut xml = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+        // Keeps the header in view while scrolling
+        r#"<sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews>"#,
+        "<cols>",
+    ));
+    for (column, width) in sheet.widths().iter().enumerate() {
+        xml.push_str(&format!(r#"<col min="{}" max="{}" width="{}" customWidth="1"/>"#, column + 1, column + 1, width));
+    }
+    xml.push_str("</cols><sheetData>");
+
+    let rows = sheet.all_rows();
+    for (index, (row, bold)) in rows.iter().enumerate() {
+        let number = index + 1;
+        let style = if *bold { r#" s="1""# } else { "" };
+        xml.push_str(&format!(r#"<row r="{}">"#, number));
+        for (column, value) in row.iter().enumerate() {
+            let reference = format!("{}{}", column_letter(column), number);
+            match value {
+                Value::Empty => {}
+                Value::Text(text) => {
+                    xml.push_str(&format!(r#"<c r="{}" t="inlineStr"{}><is><t xml:space="preserve">{}</t></is></c>"#, reference, style, escape(text)));
+                }
+                Value::Number(number) => {
+                    xml.push_str(&format!(r#"<c r="{}"{}><v>{}</v></c>"#, reference, style, number));
+                }
+            }
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData>");
+
+    if let (Some(highlight), false) = (sheet.highlight.as_ref(), sheet.rows.is_empty()) {
+        let last = format!("{}{}", column_letter(sheet.headers.len() - 1), sheet.rows.len() + 1);
+        xml.push_str(&format!(
+            r#"<conditionalFormatting sqref="A2:{}"><cfRule type="expression" dxfId="0" priority="1"><formula>${}2="{}"</formula></cfRule></conditionalFormatting>"#,
+            last, column_letter(highlight.column), escape(highlight.text),
+        ));
+    }
+
+    xml.push_str("</worksheet>");
+    xml
+}
+
+// `A`, `B`, ..., `Z`, `AA`, ... for a zero based column
+fn column_letter(column: usize) -> String {
+    let mut letters = Vec::new();
+    let mut rest = column + 1;
+    while rest > 0 {
+        letters.push((b'A' + ((rest - 1) % 26) as u8) as char);
+        rest = (rest - 1) / 26;
+    }
+    letters.iter().rev().collect()
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::io::IsTerminal;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+// Whether escape codes are written at all. Decided once, either explicitly through
+// `init` (the `--color` option) or lazily on first use.
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColorChoice {
+    // Colors only when stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("Unknown color choice `{}` (expected auto, always or never).", value)),
+        }
+    }
+}
+
+impl ColorChoice {
+    fn resolve(&self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                // https://no-color.org: any non-empty value disables colors
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                !no_color && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+pub fn init(choice: ColorChoice) {
+    let _ = ENABLED.set(choice.resolve());
+}
+
+pub fn enabled() -> bool {
+    *ENABLED.get_or_init(|| ColorChoice::Auto.resolve())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Bold,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+}
+
+impl Color {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Color::Bold => "1",
+            Color::Red => "31",
+            Color::Green => "32",
+            Color::Yellow => "33",
+            Color::Blue => "34",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Color::Bold => "bold",
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Blue => "blue",
+        }
+    }
+}
+
+pub struct Styled<T> {
+    color: Color,
+    value: T,
+}
+
+impl<T: fmt::Display> fmt::Display for Styled<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if enabled() {
+            write!(f, "\x1b[{}m{}\x1b[0m", self.color.code(), self.value)
+        } else {
+            write!(f, "{}", self.value)
+        }
+    }
+}
+
+pub fn paint<T: fmt::Display>(color: Color, value: T) -> Styled<T> {
+    Styled { color, value }
+}
+
+pub fn bold<T: fmt::Display>(value: T) -> Styled<T> {
+    paint(Color::Bold, value)
+}
@@ -0,0 +1,314 @@
+use std::io::IsTerminal;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use crate::render;
+use crate::style::{self, Color};
+
+const INDENT: &str = "  ";
+const SEPARATOR: &str = "  ";
+const ELLIPSIS: char = '…';
+// Truncated columns never get narrower than this
+const MIN_TRUNCATED_WIDTH: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+pub struct Column {
+    header: String,
+    align: Align,
+    truncate: bool,
+}
+
+impl Column {
+    pub fn new(header: &str) -> Self {
+        Self { header: header.to_string(), align: Align::Left, truncate: false }
+    }
+
+    pub fn right(mut self) -> Self {
+        self.align = Align::Right;
+        self
+    }
+
+    // Allow the column to be shortened when the table doesn't fit the terminal
+    pub fn truncate(mut self) -> Self {
+        self.truncate = true;
+        self
+    }
+
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    pub fn align(&self) -> Align {
+        self.align
+    }
+
+    pub fn truncates(&self) -> bool {
+        self.truncate
+    }
+}
+
+pub struct Cell {
+    text: String,
+    color: Option<Color>,
+}
+
+impl Cell {
+    pub fn new(text: impl ToString) -> Self {
+        Self { text: text.to_string(), color: None }
+    }
+
+    pub fn colored(text: impl ToString, color: Color) -> Self {
+        Self { text: text.to_string(), color: Some(color) }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn color(&self) -> Option<Color> {
+        self.color
+    }
+}
+
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self { columns, rows: Vec::new() }
+    }
+
+    pub fn row(&mut self, cells: Vec<Cell>) {
+        self.rows.push(cells);
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.rows
+    }
+
+    pub fn print(&self) {
+        for line in self.render(terminal_width()) {
+            println!("{}", line);
+        }
+    }
+
+    pub fn render(&self, max_width: Option<usize>) -> Vec<String> {
+        let widths = self.widths(max_width);
+        let mut lines = Vec::with_capacity(self.rows.len() + 1);
+
+        let header: Vec<Cell> = self.columns.iter().map(|column| Cell::colored(&column.header, Color::Bold)).collect();
+        lines.push(self.render_row(&header, &widths));
+        for row in self.rows.iter() {
+            lines.push(self.render_row(row, &widths));
+        }
+
+        lines
+    }
+
+    // GitHub flavoured Markdown, colors are lost
+    pub fn markdown(&self) -> Vec<String> {
+        let escape = |text: &str| text.replace('|', "\\|");
+        let row = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+
+        let mut lines = vec![
+            row(self.columns.iter().map(|column| escape(&column.header)).collect()),
+            row(self.columns.iter().map(|column| String::from(if column.align == Align::Right { "---:" } else { "---" })).collect()),
+        ];
+        for cells in self.rows.iter() {
+            lines.push(row(self.columns.iter().enumerate().map(|(index, _)| cells.get(index).map(|cell| escape(&cell.text)).unwrap_or_default()).collect()));
+        }
+
+        lines
+    }
+
+    // Colored cells get the color's name as their class, see `render::html`
+    pub fn html(&self) -> String {
+        let mut html = String::from("<table>\n<thead><tr>");
+        for column in self.columns.iter() {
+            html.push_str(&format!("<th>{}</th>", render::escape(&column.header)));
+        }
+        html.push_str("</tr></thead>\n<tbody>\n");
+
+        for cells in self.rows.iter() {
+            html.push_str("<tr>");
+            for (index, column) in self.columns.iter().enumerate() {
+                let mut classes = Vec::new();
+                if column.align == Align::Right { classes.push("right"); }
+                let cell = cells.get(index);
+                if let Some(color) = cell.and_then(|cell| cell.color) { classes.push(color.name()); }
+
+                let class = match classes.is_empty() {
+                    true => String::new(),
+                    false => format!(" class=\"{}\"", classes.join(" ")),
+                };
+                html.push_str(&format!("<td{}>{}</td>", class, render::escape(cell.map(|cell| cell.text.as_str()).unwrap_or_default())));
+            }
+            html.push_str("</tr>\n");
+        }
+
+        html.push_str("</tbody>\n</table>\n");
+        html
+    }
+
+    fn widths(&self, max_width: Option<usize>) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.columns.iter().map(|column| column.header.width()).collect();
+        for row in self.rows.iter() {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.text.width());
+            }
+        }
+
+        let max_width = match max_width {
+            Some(max_width) => max_width,
+            None => { return widths; }
+        };
+
+        // Shrink the widest truncatable column until everything fits (or nothing can shrink)
+        let fixed = INDENT.width() + SEPARATOR.width() * widths.len().saturating_sub(1);
+        while fixed + widths.iter().sum::<usize>() > max_width {
+            let widest = widths.iter().enumerate()
+                .filter(|(index, width)| self.columns[*index].truncate && **width > MIN_TRUNCATED_WIDTH)
+                .max_by_key(|(_, width)| **width)
+                .map(|(index, _)| index);
+
+            match widest {
+                Some(index) => { widths[index] -= 1; }
+                None => { break; }
+            }
+        }
+
+        widths
+    }
+
+    fn render_row(&self, cells: &[Cell], widths: &[usize]) -> String {
+        let mut line = String::from(INDENT);
+        let last = widths.len().saturating_sub(1);
+
+        for (index, (column, width)) in self.columns.iter().zip(widths.iter()).enumerate() {
+            let text = match cells.get(index) {
+                Some(cell) => truncate(&cell.text, *width),
+                None => String::new(),
+            };
+
+            let padding = " ".repeat(width.saturating_sub(text.width()));
+            let painted = match cells.get(index).and_then(|cell| cell.color) {
+                Some(color) => style::paint(color, &text).to_string(),
+                None => text,
+            };
+
+            match column.align {
+                Align::Left if index == last => { line.push_str(&painted); }
+                Align::Left => { line.push_str(&painted); line.push_str(&padding); }
+                Align::Right => { line.push_str(&padding); line.push_str(&painted); }
+            }
+
+            if index != last {
+                line.push_str(SEPARATOR);
+            }
+        }
+
+        line.trim_end().to_string()
+    }
+}
+
+// Cuts the text to the given display width, marking the cut with an ellipsis
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for char in text.chars() {
+        let char_width = char.width().unwrap_or(0);
+        if used + char_width + 1 > width { break; }
+        truncated.push(char);
+        used += char_width;
+    }
+    truncated.push(ELLIPSIS);
+    truncated
+}
+
+// Width of the terminal stdout is connected to, `None` when it isn't a terminal
+// (in which case nothing gets truncated) unless `COLUMNS` says otherwise
+fn terminal_width() -> Option<usize> {
+    if std::io::stdout().is_terminal() {
+        if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+            return Some(width as usize);
+        }
+    }
+
+    std::env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::ColorChoice;
+
+    fn table(columns: Vec<Column>, rows: &[[&str; 2]]) -> Table {
+        style::init(ColorChoice::Never);
+        let mut table = Table::new(columns);
+        for [first, second] in rows {
+            table.row(vec![Cell::new(first), Cell::new(second)]);
+        }
+        table
+    }
+
+    #[test]
+    fn wide_and_combining_characters() {
+        // `ą` precomposed or as `a` with a combining ogonek is a single column, CJK takes two
+        let decomposed = "Pia\u{328}tnica";
+        assert_eq!("Piątnica".width(), 8);
+        assert_eq!(decomposed.width(), 8);
+        assert_eq!("日本酒".width(), 6);
+
+        let table = table(vec![Column::new("Item"), Column::new("Quantity").right()], &[["Piątnica", "2"], [decomposed, "3"], ["日本酒", "10"]]);
+        assert_eq!(table.render(None), vec![
+            "  Item      Quantity",
+            "  Piątnica         2",
+            "  Pia\u{328}tnica         3",
+            "  日本酒          10",
+        ]);
+    }
+
+    #[test]
+    fn truncation_counts_columns_not_characters() {
+        assert_eq!(truncate("Piątnica", 8), "Piątnica");
+        assert_eq!(truncate("Piątnica", 5), "Piąt…");
+        assert_eq!(truncate("Pia\u{328}tnica", 5), "Pia\u{328}t…");
+
+        // A wide character that doesn't fit whole is left out
+        assert_eq!(truncate("日本酒", 5), "日本…");
+        assert_eq!(truncate("日本酒", 4), "日…");
+        assert_eq!(truncate("日本酒", 4).width(), 3);
+    }
+
+    #[test]
+    fn descriptions_are_truncated_to_the_width() {
+        let description = "Twaróg półtłusty z Piątnicy, 250 g";
+        let table = table(vec![Column::new("Item"), Column::new("Description").truncate()], &[["Twaróg", description]]);
+
+        assert_eq!(table.render(None)[1], format!("  Twaróg  {}", description));
+        assert_eq!(table.render(Some(30)), vec![
+            "  Item    Description",
+            "  Twaróg  Twaróg półtłusty z …",
+        ]);
+        for line in table.render(Some(30)) {
+            assert!(line.width() <= 30, "`{}` is wider than 30", line);
+        }
+
+        // Never narrower than `MIN_TRUNCATED_WIDTH`, even if the table doesn't fit then
+        assert_eq!(table.render(Some(12))[1], "  Twaróg  Twaróg pó…");
+        assert_eq!(table.render(Some(12))[1].width(), 2 + 6 + 2 + MIN_TRUNCATED_WIDTH);
+    }
+}
@@ -0,0 +1,728 @@
+use std::io::{self, IsTerminal, Read, Write};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use crate::editor::{Key, KeyReader, RawMode, TerminalInput};
+use crate::expr::Severity;
+use crate::raport::Status;
+use crate::style::{self, Color};
+use crate::table;
+use crate::ItemEdit;
+
+// Full-screen interface of `deposito tui`. The `App` only knows the data it shows and turns
+// keys into `Action`s, `Deposito::tui` carries those out against the database and hands it
+// fresh data. Frames are drawn by a `Backend`: the terminal, or a headless one that reads the
+// keys from any input and prints the last frame as plain text (when stdin or stdout isn't a
+// terminal, e.g. in the tests).
+
+const DEFAULT_SIZE: (usize, usize) = (80, 24);
+const MIN_SIZE: (usize, usize) = (40, 8);
+const MIN_WAREHOUSES_WIDTH: usize = 12;
+const QUANTITY_WIDTH: usize = 8;
+const STATUS_WIDTH: usize = 8;
+const GAP: usize = 2;
+const KEY_HINTS: &str = "q quit  Tab pane  +/- quantity  e edit  r rename  d description  / search";
+
+pub struct WarehouseRow {
+    pub name: String,
+    // Items of the warehouse that are CRITICAL right now
+    pub critical: usize,
+}
+
+pub struct ItemRow {
+    pub name: String,
+    pub quantity: i64,
+    pub description: Option<String>,
+    // `None` for items without a rule
+    pub status: Option<Status>,
+}
+
+impl ItemRow {
+    fn is_critical(&self) -> bool {
+        matches!(self.status, Some(Status::Triggered(Severity::Critical)))
+    }
+
+    fn status_label(&self) -> (&'static str, Option<Color>) {
+        match &self.status {
+            None => ("-", None),
+            Some(Status::Ok) => ("OK", Some(Color::Green)),
+            Some(Status::Triggered(Severity::Critical)) => ("CRITICAL", Some(Color::Red)),
+            Some(Status::Triggered(Severity::Warn)) => ("WARN", Some(Color::Yellow)),
+            Some(Status::Triggered(Severity::Info)) => ("INFO", Some(Color::Blue)),
+            Some(Status::Invalid(_)) => ("INVALID", Some(Color::Red)),
+        }
+    }
+
+    fn matches(&self, search: &str) -> bool {
+        let search = search.to_lowercase();
+        self.name.to_lowercase().contains(&search)
+            || self.description.as_deref().unwrap_or_default().to_lowercase().contains(&search)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pane {
+    Warehouses,
+    Items,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Quantity,
+    Name,
+    Description,
+}
+
+impl Field {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Field::Quantity => "quantity",
+            Field::Name => "name",
+            Field::Description => "description",
+        }
+    }
+}
+
+enum Mode {
+    Browse,
+    Search,
+    // Until the cursor moves, typing replaces the current value instead of adding to it
+    Edit { field: Field, input: Vec<char>, cursor: usize, replace: bool },
+}
+
+pub enum Action {
+    None,
+    Quit,
+    // Another warehouse got selected, its items have to be loaded
+    SelectWarehouse(String),
+    Edit { warehouse: String, item: String, edit: ItemEdit },
+}
+
+pub struct App {
+    warehouses: Vec<WarehouseRow>,
+    items: Vec<ItemRow>,
+    warehouse: usize,
+    // Index into the items matching the search
+    item: usize,
+    // First rows shown when a pane doesn't fit on the screen
+    warehouse_offset: usize,
+    item_offset: usize,
+    pane: Pane,
+    search: String,
+    mode: Mode,
+    message: Option<(String, Option<Color>)>,
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            warehouses: Vec::new(),
+            items: Vec::new(),
+            warehouse: 0,
+            item: 0,
+            warehouse_offset: 0,
+            item_offset: 0,
+            pane: Pane::Warehouses,
+            search: String::new(),
+            mode: Mode::Browse,
+            message: None,
+        }
+    }
+
+    pub fn warehouse_name(&self) -> Option<&str> {
+        self.warehouses.get(self.warehouse).map(|warehouse| warehouse.name.as_str())
+    }
+
+    // Replaces the warehouses, staying on the selected one when it's still there
+    pub fn set_warehouses(&mut self, warehouses: Vec<WarehouseRow>) {
+        let selected = self.warehouse_name().map(str::to_string);
+        self.warehouses = warehouses;
+        self.warehouse = selected
+            .and_then(|name| self.warehouses.iter().position(|warehouse| warehouse.name == name))
+            .unwrap_or(0);
+    }
+
+    // Selects the warehouse and moves to its items
+    pub fn select_warehouse(&mut self, name: &str) {
+        if let Some(index) = self.warehouses.iter().position(|warehouse| warehouse.name == name) {
+            self.warehouse = index;
+            self.pane = Pane::Items;
+        }
+    }
+
+    // Replaces the items, staying on the selected one when it's still there (or on the same
+    // row, e.g. after it got renamed)
+    pub fn set_items(&mut self, items: Vec<ItemRow>) {
+        let selected = self.selected_item().map(|item| item.name.clone());
+        self.items = items;
+        let length = self.visible_items().len();
+        self.item = selected
+            .and_then(|name| self.visible_items().iter().position(|item| item.name == name))
+            .unwrap_or(self.item.min(length.saturating_sub(1)));
+    }
+
+    pub fn notice(&mut self, text: String) {
+        self.message = Some((text, None));
+    }
+
+    pub fn error(&mut self, text: String) {
+        self.message = Some((text, Some(Color::Red)));
+    }
+
+    fn visible_items(&self) -> Vec<&ItemRow> {
+        self.items.iter().filter(|item| item.matches(&self.search)).collect()
+    }
+
+    fn selected_item(&self) -> Option<&ItemRow> {
+        self.visible_items().get(self.item).copied()
+    }
+
+    pub fn handle_key(&mut self, key: Key) -> Action {
+        self.message = None;
+
+        match self.mode {
+            Mode::Browse => self.browse_key(key),
+            Mode::Search => self.search_key(key),
+            Mode::Edit { .. } => self.edit_key(key),
+        }
+    }
+
+    fn browse_key(&mut self, key: Key) -> Action {
+        match (self.pane, key) {
+            (_, Key::Char('q') | Key::Control('c')) => { return Action::Quit; }
+            (_, Key::Tab) => {
+                self.pane = match self.pane {
+                    Pane::Warehouses => Pane::Items,
+                    Pane::Items => Pane::Warehouses,
+                };
+            }
+            (_, Key::Left | Key::Char('h')) => { self.pane = Pane::Warehouses; }
+            (_, Key::Right | Key::Char('l')) | (Pane::Warehouses, Key::Enter) => { self.pane = Pane::Items; }
+            (_, Key::Char('/')) => {
+                self.pane = Pane::Items;
+                self.mode = Mode::Search;
+            }
+            (_, Key::Escape) => { self.set_search(String::new()); }
+            (Pane::Warehouses, key) => {
+                let selected = self.warehouse;
+                self.warehouse = moved(self.warehouse, self.warehouses.len(), &key);
+                if self.warehouse != selected {
+                    self.item = 0;
+                    self.item_offset = 0;
+                    return Action::SelectWarehouse(self.warehouses[self.warehouse].name.clone());
+                }
+            }
+            (Pane::Items, Key::Char('+' | '=')) => { return self.adjust_quantity(1); }
+            (Pane::Items, Key::Char('-')) => { return self.adjust_quantity(-1); }
+            (Pane::Items, Key::Enter | Key::Char('e')) => { self.start_edit(Field::Quantity); }
+            (Pane::Items, Key::Char('r')) => { self.start_edit(Field::Name); }
+            (Pane::Items, Key::Char('d')) => { self.start_edit(Field::Description); }
+            (Pane::Items, key) => { self.item = moved(self.item, self.visible_items().len(), &key); }
+        }
+        Action::None
+    }
+
+    fn search_key(&mut self, key: Key) -> Action {
+        let mut search = self.search.clone();
+        match key {
+            Key::Enter => { self.mode = Mode::Browse; }
+            Key::Escape | Key::Control('c') => {
+                self.mode = Mode::Browse;
+                search.clear();
+            }
+            Key::Char(char) => { search.push(char); }
+            Key::Backspace | Key::Control('h') => { search.pop(); }
+            Key::Control('u') => { search.clear(); }
+            key => { self.item = moved(self.item, self.visible_items().len(), &key); }
+        }
+
+        if search != self.search {
+            self.set_search(search);
+        }
+        Action::None
+    }
+
+    fn edit_key(&mut self, key: Key) -> Action {
+        let (field, input, cursor, replace) = match &mut self.mode {
+            Mode::Edit { field, input, cursor, replace } => (*field, input, cursor, replace),
+            _ => { return Action::None; }
+        };
+
+        if std::mem::take(replace) {
+            if let Key::Char(_) = key {
+                input.clear();
+                *cursor = 0;
+            }
+        }
+
+        match key {
+            Key::Escape | Key::Control('c') => { self.mode = Mode::Browse; }
+            Key::Enter => {
+                let text: String = input.iter().collect();
+                return self.finish_edit(field, text);
+            }
+            Key::Char(char) => {
+                input.insert(*cursor, char);
+                *cursor += 1;
+            }
+            Key::Backspace | Key::Control('h') if *cursor > 0 => {
+                *cursor -= 1;
+                input.remove(*cursor);
+            }
+            Key::Delete | Key::Control('d') if *cursor < input.len() => { input.remove(*cursor); }
+            Key::Left | Key::Control('b') => { *cursor = cursor.saturating_sub(1); }
+            Key::Right | Key::Control('f') => { *cursor = (*cursor + 1).min(input.len()); }
+            Key::Home | Key::Control('a') => { *cursor = 0; }
+            Key::End | Key::Control('e') => { *cursor = input.len(); }
+            Key::Control('u') => {
+                input.drain(..*cursor);
+                *cursor = 0;
+            }
+            _ => { }
+        }
+        Action::None
+    }
+
+    fn set_search(&mut self, search: String) {
+        self.search = search;
+        self.item = 0;
+        self.item_offset = 0;
+    }
+
+    fn adjust_quantity(&mut self, delta: i64) -> Action {
+        let (item, quantity) = match self.selected_item() {
+            Some(item) => (item.name.clone(), item.quantity),
+            None => { return Action::None; }
+        };
+
+        if quantity + delta < 0 {
+            self.error(format!("`{}` is already at {}.", item, quantity));
+            return Action::None;
+        }
+
+        self.edit_action(item, ItemEdit { quantity: Some(quantity + delta), ..Default::default() })
+    }
+
+    fn start_edit(&mut self, field: Field) {
+        let item = match self.selected_item() {
+            Some(item) => item,
+            None => { return; }
+        };
+
+        let value = match field {
+            Field::Quantity => item.quantity.to_string(),
+            Field::Name => item.name.clone(),
+            Field::Description => item.description.clone().unwrap_or_default(),
+        };
+        let input: Vec<char> = value.chars().collect();
+        self.mode = Mode::Edit { field, cursor: input.len(), input, replace: true };
+    }
+
+    fn finish_edit(&mut self, field: Field, text: String) -> Action {
+        let (item, quantity, description) = match self.selected_item() {
+            Some(item) => (item.name.clone(), item.quantity, item.description.clone()),
+            None => { self.mode = Mode::Browse; return Action::None; }
+        };
+
+        let edit = match field {
+            Field::Quantity => {
+                match text.trim().parse::<i64>() {
+                    Ok(new_quantity) if new_quantity == quantity => None,
+                    Ok(new_quantity) => Some(ItemEdit { quantity: Some(new_quantity), ..Default::default() }),
+                    Err(_) => { self.error(format!("`{}` isn't a quantity.", text.trim())); return Action::None; }
+                }
+            }
+            Field::Name => {
+                match text.trim() {
+                    "" => { self.error(String::from("The name can't be empty.")); return Action::None; }
+                    name if name == item => None,
+                    name => Some(ItemEdit { name: Some(name.to_string()), ..Default::default() }),
+                }
+            }
+            Field::Description if Some(&text) == description.as_ref() => None,
+            Field::Description => Some(ItemEdit { description: Some(text), ..Default::default() }),
+        };
+
+        self.mode = Mode::Browse;
+        match edit {
+            Some(edit) => self.edit_action(item, edit),
+            None => Action::None,
+        }
+    }
+
+    fn edit_action(&self, item: String, edit: ItemEdit) -> Action {
+        match self.warehouse_name() {
+            Some(warehouse) => Action::Edit { warehouse: warehouse.to_string(), item, edit },
+            None => Action::None,
+        }
+    }
+
+    pub fn render(&mut self, width: usize, height: usize) -> Frame {
+        let mut frame = Frame::new(width, height);
+        if width < MIN_SIZE.0 || height < MIN_SIZE.1 {
+            frame.print(0, 0, "The terminal is too small.", width, CellStyle::default());
+            return frame;
+        }
+
+        let reverse = CellStyle { reverse: true, ..Default::default() };
+        frame.fill(0, 0, width, reverse);
+        let title = match self.warehouse_name() {
+            Some(warehouse_name) => format!(" deposito · {}", warehouse_name),
+            None => String::from(" deposito"),
+        };
+        frame.print(0, 0, &table::truncate(&title, width), width, reverse);
+
+        let critical = self.items.iter().filter(|item| item.is_critical()).count();
+        if critical > 0 {
+            let count = format!("{} CRITICAL ", critical);
+            frame.print(width - count.width(), 0, &count, count.width(), CellStyle { color: Some(Color::Red), ..reverse });
+        }
+
+        // Panes take everything between the title and the two lines at the bottom
+        let body = 1..height - 2;
+        let longest = self.warehouses.iter().map(|warehouse| warehouse.name.width() + 4).max().unwrap_or(0);
+        let left_width = longest.clamp(MIN_WAREHOUSES_WIDTH, width / 3);
+        for y in body.clone() {
+            frame.print(left_width, y, "│", 1, CellStyle::default());
+        }
+
+        self.render_warehouses(&mut frame, left_width, body.clone());
+        self.render_items(&mut frame, left_width + 1 + GAP / 2, width - left_width - 1 - GAP / 2, body);
+        self.render_status(&mut frame, height - 2);
+
+        let hints = CellStyle { reverse: true, ..Default::default() };
+        frame.fill(0, height - 1, width, hints);
+        frame.print(1, height - 1, &table::truncate(KEY_HINTS, width - 1), width - 1, hints);
+        frame
+    }
+
+    fn render_warehouses(&mut self, frame: &mut Frame, width: usize, body: std::ops::Range<usize>) {
+        let header = CellStyle { bold: true, ..Default::default() };
+        frame.print(1, body.start, "Warehouses", width - 1, header);
+
+        let rows = body.len() - 1;
+        self.warehouse_offset = scrolled(self.warehouse_offset, self.warehouse, rows);
+        for (row, (index, warehouse)) in self.warehouses.iter().enumerate().skip(self.warehouse_offset).take(rows).enumerate() {
+            let y = body.start + 1 + row;
+            let style = match (index == self.warehouse, self.pane) {
+                (true, Pane::Warehouses) => CellStyle { reverse: true, ..Default::default() },
+                (true, Pane::Items) => CellStyle { bold: true, ..Default::default() },
+                (false, _) => CellStyle::default(),
+            };
+
+            frame.fill(0, y, width, style);
+            // Warehouses with something CRITICAL get marked
+            let marker = if warehouse.critical > 0 { "!" } else { " " };
+            frame.print(0, y, marker, 1, CellStyle { color: Some(Color::Red), ..style });
+            frame.print(1, y, &table::truncate(&warehouse.name, width - 2), width - 2, style);
+        }
+    }
+
+    fn render_items(&mut self, frame: &mut Frame, x: usize, width: usize, body: std::ops::Range<usize>) {
+        let items = self.visible_items();
+        let mut longest = items.iter().map(|item| item.name.width()).max().unwrap_or(0);
+        if let Mode::Edit { field: Field::Name, input, .. } = &self.mode {
+            longest = longest.max(input.iter().map(|char| char.width().unwrap_or(0)).sum::<usize>() + 1);
+        }
+        let fixed = QUANTITY_WIDTH + STATUS_WIDTH + 3 * GAP;
+        let name_width = longest.max("Item".len()).min(width.saturating_sub(fixed + MIN_WAREHOUSES_WIDTH).max("Item".len()));
+        let description_width = width.saturating_sub(name_width + fixed);
+
+        // Left edges of the name, quantity, status and description columns
+        let columns = [
+            x,
+            x + name_width + GAP,
+            x + name_width + GAP + QUANTITY_WIDTH + GAP,
+            x + name_width + GAP + QUANTITY_WIDTH + GAP + STATUS_WIDTH + GAP,
+        ];
+
+        let header = CellStyle { bold: true, ..Default::default() };
+        frame.print(columns[0], body.start, &table::truncate("Item", name_width), name_width, header);
+        frame.print(columns[1], body.start, "Quantity", QUANTITY_WIDTH, header);
+        frame.print(columns[2], body.start, "Status", STATUS_WIDTH, header);
+        frame.print(columns[3], body.start, &table::truncate("Description", description_width), description_width, header);
+
+        let empty = if self.warehouses.is_empty() {
+            Some(String::from("No warehouses. You can create one using `deposito create <name>`."))
+        } else if self.items.is_empty() {
+            Some(String::from("The warehouse contains no items."))
+        } else if items.is_empty() {
+            Some(format!("No items match `{}`.", self.search))
+        } else {
+            None
+        };
+        if let Some(empty) = empty {
+            frame.print(x, body.start + 1, &table::truncate(&empty, width), width, CellStyle::default());
+            return;
+        }
+
+        let rows = body.len() - 1;
+        let offset = scrolled(self.item_offset, self.item, rows);
+        for (row, (index, item)) in items.iter().enumerate().skip(offset).take(rows).enumerate() {
+            let y = body.start + 1 + row;
+            let selected = index == self.item;
+            let color = if item.is_critical() { Some(Color::Red) } else { None };
+            let style = CellStyle { color, reverse: selected && self.pane == Pane::Items, bold: selected };
+            frame.fill(x, y, width, style);
+
+            let quantity = item.quantity.to_string();
+            let quantity = format!("{:>width$}", quantity, width = QUANTITY_WIDTH);
+            let (status, status_color) = item.status_label();
+            let description = item.description.clone().unwrap_or_default();
+
+            frame.print(columns[0], y, &table::truncate(&item.name, name_width), name_width, style);
+            frame.print(columns[1], y, &quantity, QUANTITY_WIDTH, style);
+            frame.print(columns[2], y, status, STATUS_WIDTH, CellStyle { color: status_color.or(color), ..style });
+            frame.print(columns[3], y, &table::truncate(&description, description_width), description_width, style);
+
+            // The field being edited is replaced with the input, cursor included
+            if let (true, Mode::Edit { field, input, cursor, replace }) = (selected, &self.mode) {
+                let (column, field_width) = match field {
+                    Field::Quantity => (columns[1], QUANTITY_WIDTH),
+                    Field::Name => (columns[0], name_width),
+                    Field::Description => (columns[3], description_width),
+                };
+                let plain = CellStyle::default();
+                frame.fill(column, y, field_width, plain);
+                // The value about to be replaced is shown selected
+                let text_style = CellStyle { reverse: *replace, ..plain };
+
+                // Keep the cursor in view when the input is longer than the column
+                let start = (cursor + 1).saturating_sub(field_width);
+                let text: String = input[start..].iter().collect();
+                frame.print(column, y, &text, field_width, text_style);
+                let cursor_x = column + input[start..*cursor].iter().map(|char| char.width().unwrap_or(0)).sum::<usize>();
+                let under_cursor = input.get(*cursor).map(char::to_string).unwrap_or_else(|| String::from(" "));
+                frame.print(cursor_x, y, &under_cursor, field_width, CellStyle { reverse: true, ..plain });
+            }
+        }
+        self.item_offset = offset;
+    }
+
+    fn render_status(&self, frame: &mut Frame, y: usize) {
+        let width = frame.width - 1;
+        let (text, color) = match (&self.mode, &self.message) {
+            (Mode::Search, _) => (format!("/{}", self.search), None),
+            (Mode::Edit { field, .. }, None) => {
+                let item = self.selected_item().map(|item| item.name.as_str()).unwrap_or_default();
+                (format!("Editing the {} of `{}`, Enter saves and Esc cancels.", field.as_str(), item), None)
+            }
+            (_, Some((message, color))) => (message.clone(), *color),
+            (Mode::Browse, None) if !self.search.is_empty() => (format!("Items matching `{}` (Esc shows all)", self.search), None),
+            (Mode::Browse, None) => { return; }
+        };
+        frame.print(1, y, &table::truncate(&text, width), width, CellStyle { color, ..Default::default() });
+    }
+}
+
+// Selection after a movement key in a list of `length` rows
+fn moved(selected: usize, length: usize, key: &Key) -> usize {
+    let last = length.saturating_sub(1);
+    match key {
+        Key::Up | Key::Char('k') | Key::Control('p') => selected.saturating_sub(1),
+        Key::Down | Key::Char('j') | Key::Control('n') => (selected + 1).min(last),
+        Key::Home | Key::Char('g') => 0,
+        Key::End | Key::Char('G') => last,
+        _ => selected.min(last),
+    }
+}
+
+// First row to show so that the selected one is within the `rows` shown
+fn scrolled(offset: usize, selected: usize, rows: usize) -> usize {
+    if selected < offset {
+        selected
+    } else if selected >= offset + rows {
+        selected + 1 - rows
+    } else {
+        offset
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CellStyle {
+    color: Option<Color>,
+    bold: bool,
+    reverse: bool,
+}
+
+// Placeholder for the second column taken by a wide character
+const WIDE: char = '\0';
+
+// What's on the screen, cell by cell
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<(char, CellStyle)>,
+}
+
+impl Frame {
+    fn new(width: usize, height: usize) -> Self {
+        Self { width, height, cells: vec![(' ', CellStyle::default()); width * height] }
+    }
+
+    // Writes the text from `x`, cut off after `max_width` columns
+    fn print(&mut self, x: usize, y: usize, text: &str, max_width: usize, style: CellStyle) {
+        if y >= self.height {
+            return;
+        }
+
+        let limit = (x + max_width).min(self.width);
+        let mut column = x;
+        for char in text.chars() {
+            let char_width = match char.width() {
+                Some(char_width) if char_width > 0 => char_width,
+                _ => { continue; }
+            };
+            if column + char_width > limit { break; }
+
+            self.cells[y * self.width + column] = (char, style);
+            if char_width == 2 {
+                self.cells[y * self.width + column + 1] = (WIDE, style);
+            }
+            column += char_width;
+        }
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, style: CellStyle) {
+        let end = (x + width).min(self.width);
+        for column in x..end {
+            self.cells[y * self.width + column] = (' ', style);
+        }
+    }
+
+    // Text of every line, without styles and trailing spaces
+    pub fn lines(&self) -> Vec<String> {
+        self.cells.chunks(self.width)
+            .map(|line| line.iter().map(|(char, _)| *char).filter(|char| *char != WIDE).collect::<String>().trim_end().to_string())
+            .collect()
+    }
+
+    // Escape codes drawing the whole frame, colors only when `colors` is set
+    fn to_ansi(&self, colors: bool) -> String {
+        let mut output = String::new();
+        for (y, line) in self.cells.chunks(self.width).enumerate() {
+            output.push_str(&format!("\x1b[{};1H", y + 1));
+            let mut current = None;
+            for (char, style) in line.iter().filter(|(char, _)| *char != WIDE) {
+                if current != Some(*style) {
+                    output.push_str("\x1b[0");
+                    if style.bold { output.push_str(";1"); }
+                    if style.reverse { output.push_str(";7"); }
+                    if let (true, Some(color)) = (colors, style.color) { output.push_str(&format!(";{}", color.code())); }
+                    output.push('m');
+                    current = Some(*style);
+                }
+                output.push(*char);
+            }
+            output.push_str("\x1b[0m");
+        }
+        output
+    }
+}
+
+pub trait Backend {
+    fn size(&self) -> (usize, usize);
+    fn draw(&mut self, frame: &Frame) -> io::Result<()>;
+    // `None` once the input has ended
+    fn read_key(&mut self) -> io::Result<Option<Key>>;
+    // Called after the last frame
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// The terminal stdin and stdout are connected to, switched to its alternate screen for as
+// long as the backend is alive
+pub struct TerminalBackend {
+    keys: KeyReader<TerminalInput>,
+    _raw: RawMode,
+}
+
+impl TerminalBackend {
+    pub fn new() -> io::Result<Self> {
+        let raw = RawMode::enable()?;
+        let mut stdout = io::stdout();
+        write!(stdout, "\x1b[?1049h\x1b[?25l")?;
+        stdout.flush()?;
+        Ok(Self { keys: KeyReader::new(TerminalInput, true), _raw: raw })
+    }
+}
+
+impl Backend for TerminalBackend {
+    fn size(&self) -> (usize, usize) {
+        match terminal_size::terminal_size() {
+            Some((terminal_size::Width(width), terminal_size::Height(height))) => (width as usize, height as usize),
+            None => DEFAULT_SIZE,
+        }
+    }
+
+    fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        write!(stdout, "{}", frame.to_ansi(style::enabled()))?;
+        stdout.flush()
+    }
+
+    fn read_key(&mut self) -> io::Result<Option<Key>> {
+        self.keys.read_key().map(Some)
+    }
+}
+
+impl Drop for TerminalBackend {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = write!(stdout, "\x1b[?25h\x1b[?1049l");
+        let _ = stdout.flush();
+    }
+}
+
+// Reads keys from any input and prints the last frame when done
+pub struct HeadlessBackend<R> {
+    keys: KeyReader<R>,
+    size: (usize, usize),
+    lines: Vec<String>,
+}
+
+impl<R: Read> HeadlessBackend<R> {
+    pub fn new(input: R, size: (usize, usize)) -> Self {
+        Self { keys: KeyReader::new(input, false), size, lines: Vec::new() }
+    }
+}
+
+impl<R: Read> Backend for HeadlessBackend<R> {
+    fn size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        self.lines = frame.lines();
+        Ok(())
+    }
+
+    fn read_key(&mut self) -> io::Result<Option<Key>> {
+        match self.keys.read_key() {
+            Ok(key) => Ok(Some(key)),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        for line in self.lines.iter() {
+            writeln!(stdout, "{}", line)?;
+        }
+        stdout.flush()
+    }
+}
+
+// The terminal when there is one, otherwise the headless backend reading keys from stdin,
+// sized by `COLUMNS` and `LINES`
+pub fn backend() -> io::Result<Box<dyn Backend>> {
+    if cfg!(unix) && io::stdin().is_terminal() && io::stdout().is_terminal() {
+        return Ok(Box::new(TerminalBackend::new()?));
+    }
+
+    let dimension = |name: &str, default: usize| {
+        std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+    };
+    let size = (dimension("COLUMNS", DEFAULT_SIZE.0), dimension("LINES", DEFAULT_SIZE.1));
+    Ok(Box::new(HeadlessBackend::new(io::stdin(), size)))
+}
@@ -0,0 +1,102 @@
+// Just enough of the ZIP format to write XLSX and ODS files: entries are stored without
+// compression, which every reader accepts (and ODS requires of its `mimetype`).
+//
+// https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT
+
+const CRC32: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+// Entries are dated 1980-01-01 00:00 (the earliest DOS date), so that the same raport
+// always makes the same file
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+struct Entry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+#[derive(Default)]
+pub struct ZipWriter {
+    bytes: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file(&mut self, name: &str, contents: &[u8]) {
+        let entry = Entry {
+            name: name.to_string(),
+            crc: CRC32.checksum(contents),
+            size: contents.len() as u32,
+            offset: self.bytes.len() as u32,
+        };
+
+        // Local file header
+        self.u32(0x04034b50);
+        self.common_header(&entry);
+        self.u16(0);
+        self.bytes.extend_from_slice(entry.name.as_bytes());
+        self.bytes.extend_from_slice(contents);
+
+        self.entries.push(entry);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let directory_offset = self.bytes.len() as u32;
+        let entries = std::mem::take(&mut self.entries);
+        for entry in entries.iter() {
+            // Central directory header
+            self.u32(0x02014b50);
+            self.u16(20);
+            self.common_header(entry);
+            // Extra field, comment, disk, internal and external attributes
+            self.u16(0);
+            self.u16(0);
+            self.u16(0);
+            self.u16(0);
+            self.u32(0);
+            self.u32(entry.offset);
+            self.bytes.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_size = self.bytes.len() as u32 - directory_offset;
+
+        // End of central directory
+        self.u32(0x06054b50);
+        self.u16(0);
+        self.u16(0);
+        self.u16(entries.len() as u16);
+        self.u16(entries.len() as u16);
+        self.u32(directory_size);
+        self.u32(directory_offset);
+        self.u16(0);
+
+        self.bytes
+    }
+
+    // Version needed, flags (UTF-8 names), method (stored), date and time, CRC, sizes and
+    // the name length
+    fn common_header(&mut self, entry: &Entry) {
+        self.u16(20);
+        self.u16(1 << 11);
+        self.u16(0);
+        self.u16(DOS_TIME);
+        self.u16(DOS_DATE);
+        self.u32(entry.crc);
+        self.u32(entry.size);
+        self.u32(entry.size);
+        self.u16(entry.name.len() as u16);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+}
@@ -0,0 +1,833 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// End-to-end tests of the `deposito` binary: every command is run with missing and surplus
+// arguments to make sure none of them panics, and the features are checked through their
+// output. Each test gets its own workspace (created with `deposito init`) in a
+// temporary directory, since the database path is relative to the working directory.
+
+const BIN: &str = env!("CARGO_BIN_EXE_deposito");
+
+static WORKSPACES: AtomicUsize = AtomicUsize::new(0);
+
+struct Workspace {
+    path: PathBuf,
+}
+
+impl Workspace {
+    fn new() -> Self {
+        let id = WORKSPACES.fetch_add(1, Ordering::SeqCst);
+        let path = std::env::temp_dir().join(format!("deposito-cli-{}-{}", std::process::id(), id));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("unable to create the workspace directory");
+
+        let workspace = Self { path };
+        workspace.run(&["init"]);
+        workspace.run(&["create", "Fridge"]);
+        workspace.run(&["add", "Fridge", "Egg", "6"]);
+        workspace.run(&["add", "Fridge", "Milk", "2"]);
+        workspace.run(&["rules", "-c", "Fridge", "Egg", "4"]);
+        workspace
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        let output = Command::new(BIN)
+            .args(args)
+            .current_dir(&self.path)
+            .env("NO_COLOR", "1")
+            .output()
+            .expect("unable to run deposito");
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            !stderr.contains("panicked"),
+            "`deposito {}` panicked:\n{}", args.join(" "), stderr,
+        );
+        assert!(output.status.success(), "`deposito {}` exited with {}", args.join(" "), output.status);
+        output
+    }
+
+    fn run_with_input(&self, args: &[&str], input: &str) -> String {
+        use std::io::Write;
+        use std::process::Stdio;
+
+        let mut child = Command::new(BIN)
+            .args(args)
+            .current_dir(&self.path)
+            .env("NO_COLOR", "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("unable to run deposito");
+        child.stdin.take().expect("no stdin").write_all(input.as_bytes()).expect("unable to write the input");
+
+        let output = child.wait_with_output().expect("unable to run deposito");
+        assert!(!String::from_utf8_lossy(&output.stderr).contains("panicked"), "`deposito {}` panicked", args.join(" "));
+        assert!(output.status.success(), "`deposito {}` exited with {}", args.join(" "), output.status);
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    fn stdout(&self, args: &[&str]) -> String {
+        String::from_utf8_lossy(&self.run(args).stdout).into_owned()
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+// The schema of the first version of deposito, with no rule expressions, movements or
+// anything else added since
+const FIRST_SCHEMA: &str = "
+    CREATE TABLE warehouses (id INTEGER UNIQUE NOT NULL PRIMARY KEY, name TEXT UNIQUE NOT NULL);
+    CREATE TABLE items (
+        id INTEGER UNIQUE NOT NULL PRIMARY KEY, warehouse_id INTEGER NOT NULL, name TEXT UNIQUE NOT NULL, description TEXT, quantity INTEGER NOT NULL,
+        FOREIGN KEY (warehouse_id) REFERENCES warehouses(id)
+    );
+    CREATE TABLE rules (
+        id INTEGER UNIQUE NOT NULL PRIMARY KEY, item_id INTEGER UNIQUE NOT NULL, gets_below_quantity INTEGER NOT NULL,
+        FOREIGN KEY (item_id) REFERENCES items(id)
+    );
+    INSERT INTO warehouses VALUES (1, 'Fridge');
+    INSERT INTO items VALUES (1, 1, 'Egg', NULL, 6), (2, 1, 'Milk', NULL, 2);
+    INSERT INTO rules VALUES (1, 1, 4);
+";
+
+// Writes a database of the first version holding what `Workspace::new` puts in
+fn write_first_database(path: &Path) {
+    let _ = std::fs::remove_file(path);
+    let runtime = tokio::runtime::Runtime::new().expect("unable to start a runtime");
+    runtime.block_on(async {
+        let options = sqlx::sqlite::SqliteConnectOptions::new().filename(path).create_if_missing(true);
+        let pool = sqlx::SqlitePool::connect_with(options).await.expect("unable to create the database");
+        sqlx::raw_sql(FIRST_SCHEMA).execute(&pool).await.expect("unable to fill the database");
+        pool.close().await;
+    });
+}
+
+// Runs the command (with its mode options) with no arguments, one argument and far too many
+fn check_arities(workspace: &Workspace, command: &[&str]) {
+    let arities: [&[&str]; 3] = [&[], &["Fridge"], &["Fridge", "Egg", "Milk", "1", "2", "3", "4", "5"]];
+    for args in arities {
+        let args: Vec<&str> = command.iter().chain(args.iter()).copied().collect();
+        workspace.run(&args);
+    }
+}
+
+#[test]
+fn no_command_prints_help() {
+    let workspace = Workspace::new();
+    assert!(workspace.stdout(&[]).contains("Commands:"));
+}
+
+#[test]
+fn unknown_command() {
+    let workspace = Workspace::new();
+    assert!(workspace.stdout(&["bogus"]).contains("Unknown command"));
+}
+
+#[test]
+fn init() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["init"]);
+    assert!(workspace.stdout(&["init"]).contains("already exists"));
+}
+
+#[test]
+fn view() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["view"]);
+}
+
+#[test]
+fn create() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["create"]);
+    assert!(workspace.stdout(&["create"]).contains("missing the <warehouse> argument"));
+}
+
+#[test]
+fn list() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["list"]);
+    assert!(workspace.stdout(&["list", "Fridge", "extra"]).contains("too many arguments"));
+}
+
+#[test]
+fn add() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["add"]);
+    assert!(workspace.stdout(&["add", "Fridge"]).contains("missing the <item> argument"));
+    assert!(workspace.stdout(&["add", "Fridge", "Egg", "many"]).contains("expected a whole number"));
+}
+
+#[test]
+fn edit() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["edit"]);
+    check_arities(&workspace, &["edit", "-q", "3"]);
+    assert!(workspace.stdout(&["edit", "-q"]).contains("requires a value"));
+}
+
+#[test]
+fn remove() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["remove"]);
+    check_arities(&workspace, &["remove", "-i"]);
+    check_arities(&workspace, &["remove", "-ia"]);
+    check_arities(&workspace, &["remove", "-w"]);
+    assert!(workspace.stdout(&["remove", "-w"]).contains("missing the <warehouses> argument"));
+
+    // Without a terminal to ask on nothing is removed, unless `--yes` is given
+    let output = workspace.stdout(&["remove", "-w", "Fridge", "--purge"]);
+    assert!(output.contains("This removes the warehouse(s) `Fridge` with 2 item(s), 1 rule(s) and 2 movement(s)."));
+    assert!(output.contains("Run it with `--yes` to go ahead."));
+    let output = workspace.stdout(&["remove", "-ia", "Fridge", "--purge", "--dry-run"]);
+    assert!(output.lines().any(|line| line.contains("Egg") && line.contains("yes")));
+    assert!(output.contains("Dry run, nothing was removed."));
+    assert!(workspace.stdout(&["list", "Fridge"]).contains("Egg"));
+
+    // Only archived items go without `--purge`
+    assert!(workspace.stdout(&["remove", "-i", "Fridge", "Egg", "--yes"]).contains("Not removing the item(s) `Egg` from the `Fridge` warehouse, they aren't archived."));
+    assert!(workspace.stdout(&["remove", "-ia", "Fridge", "--dry-run"]).contains("has no archived items, nothing to remove"));
+    workspace.run(&["archive", "-i", "Fridge", "Egg"]);
+    assert!(workspace.stdout(&["remove", "-i", "Fridge", "Egg", "--yes"]).contains("Removed successfully."));
+    assert!(!workspace.stdout(&["list", "Fridge"]).contains("Egg"));
+    assert!(workspace.stdout(&["rules", "-l", "Fridge"]).contains("No rules"));
+}
+
+#[test]
+fn rename_and_merge() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["rename"]);
+    check_arities(&workspace, &["merge"]);
+
+    assert!(workspace.stdout(&["rename", "Fridge", "Kitchen"]).contains("Renamed the `Fridge` warehouse to `Kitchen`."));
+    assert!(workspace.stdout(&["list", "Kitchen"]).contains("Egg"));
+    workspace.run(&["create", "Pantry"]);
+    assert!(workspace.stdout(&["rename", "Pantry", "Kitchen"]).contains("Warehouse with the name `Kitchen` already exists."));
+
+    // Same-named items are combined, different rules are a conflict
+    workspace.run(&["add", "Pantry", "Egg", "4"]);
+    workspace.run(&["add", "Pantry", "Jam", "1"]);
+    workspace.run(&["rules", "-c", "Pantry", "Egg", "10"]);
+    let output = workspace.stdout(&["merge", "Pantry", "Kitchen"]);
+    assert!(output.contains("`Egg` has the rule `can't get below 10` in one warehouse and `can't get below 4` in the other."));
+    assert!(output.contains("Nothing was merged"));
+    let output = workspace.stdout(&["merge", "Pantry", "Kitchen", "--on-conflict=skip"]);
+    assert!(output.contains("Merged the `Pantry` warehouse into `Kitchen`: 1 item(s) moved, 1 combined."));
+    assert!(output.lines().any(|line| line.contains("Egg") && line.contains("6 + 4 = 10") && line.contains("can't get below 4")));
+    assert!(!workspace.stdout(&["view"]).contains("Pantry"));
+    let list = workspace.stdout(&["list", "Kitchen"]);
+    assert!(list.lines().any(|line| line.trim_start().starts_with("Egg") && line.trim_end().ends_with("10")));
+    assert!(list.contains("Jam"));
+
+    assert!(workspace.stdout(&["undo"]).contains("Undid `merge --on-conflict=skip Pantry Kitchen`."));
+    assert!(workspace.stdout(&["rules", "-l", "Pantry"]).contains("can't get below 10"));
+    assert!(workspace.stdout(&["list", "Pantry"]).contains("Jam"));
+}
+
+#[test]
+fn archive() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["archive"]);
+    check_arities(&workspace, &["archive", "-i"]);
+    check_arities(&workspace, &["unarchive", "-w"]);
+    assert!(workspace.stdout(&["archive", "-i", "Fridge"]).contains("missing the <items> argument"));
+
+    assert!(workspace.stdout(&["archive", "-i", "Fridge", "Egg"]).contains("Archived the item(s) `Egg` in the `Fridge` warehouse."));
+    assert!(!workspace.stdout(&["list", "Fridge"]).contains("Egg"));
+    assert!(!workspace.stdout(&["raport", "Fridge"]).contains("Egg"));
+    let csv = workspace.stdout(&["list", "Fridge", "--include-archived", "--format=csv"]);
+    assert!(csv.starts_with("name,quantity,description,unit,tags,archived_at\n"));
+    assert!(csv.lines().any(|line| line.starts_with("Egg,6,") && !line.ends_with(',')));
+    assert!(workspace.stdout(&["raport", "Fridge", "--include-archived"]).contains("Egg"));
+
+    workspace.run(&["archive", "-w", "Fridge"]);
+    assert!(!workspace.stdout(&["view"]).contains("Fridge"));
+    assert!(workspace.stdout(&["view", "--include-archived"]).contains("Fridge"));
+    assert!(workspace.stdout(&["list", "Fridge"]).contains("The `Fridge` warehouse is archived."));
+    assert!(!workspace.stdout(&["raport", "-a"]).contains("Fridge"));
+
+    assert!(workspace.stdout(&["undo"]).contains("Undid `archive -w Fridge`."));
+    assert!(workspace.stdout(&["view"]).contains("Fridge"));
+    assert!(workspace.stdout(&["unarchive", "-i", "Fridge", "Egg", "Milk"]).contains("1 of them was not archived."));
+    assert!(workspace.stdout(&["list", "Fridge"]).contains("Egg"));
+}
+
+#[test]
+fn raport() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["raport"]);
+    check_arities(&workspace, &["raport", "-a"]);
+    check_arities(&workspace, &["raport", "--horizon=2w"]);
+    assert!(workspace.stdout(&["raport"]).contains("missing the <warehouse> argument"));
+
+    // 1 taken since the first movement a moment ago is 1 a day, below 4 in 2 days
+    workspace.run(&["edit", "-q", "5", "Fridge", "Egg"]);
+    let egg = |args: &[&str]| workspace.stdout(args).lines().find(|line| line.contains("Egg")).map(str::to_string).unwrap_or_default();
+    let line = egg(&["raport", "Fridge"]);
+    assert!(line.contains("OK"));
+    assert!(line.contains("1.0/day, critical in 2 day(s), empty in 5 day(s)"));
+    assert!(egg(&["raport", "Fridge", "--horizon=1d"]).contains("OK"));
+    assert!(egg(&["raport", "Fridge", "--horizon=2d"]).contains("CRITICAL IN 2 DAY(S)"));
+}
+
+#[test]
+fn rules() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["rules"]);
+    check_arities(&workspace, &["rules", "-l"]);
+    check_arities(&workspace, &["rules", "-c"]);
+    check_arities(&workspace, &["rules", "-c", "--expr=quantity < 2"]);
+    check_arities(&workspace, &["rules", "-e"]);
+    check_arities(&workspace, &["rules", "-r"]);
+    check_arities(&workspace, &["rules", "-lc"]);
+    assert!(workspace.stdout(&["rules", "-e", "Fridge", "Egg"]).contains("missing the <quantity> argument"));
+    assert!(workspace.stdout(&["rules", "-r", "Fridge"]).contains("missing the <items> argument"));
+
+    let output = workspace.stdout(&["rules", "-r", "Fridge", "Egg", "Milk", "--dry-run"]);
+    assert!(output.contains("This removes 1 rule(s) from the `Fridge` warehouse."));
+    assert!(output.contains("Dry run, nothing was removed."));
+    assert!(workspace.stdout(&["rules", "-r", "Fridge", "Egg"]).contains("Run it with `--yes` to go ahead."));
+    assert!(workspace.stdout(&["rules", "-r", "Fridge", "Egg", "-y"]).contains("Successfully removed"));
+    assert!(workspace.stdout(&["rules", "-l", "Fridge", "--yes"]).contains("only applies to `rules -r`"));
+}
+
+#[test]
+fn rules_of_the_first_version() {
+    let workspace = Workspace::new();
+    write_first_database(&workspace.path.join("deposito").join("deposito.db"));
+
+    assert!(workspace.stdout(&["raport", "Fridge"]).lines().any(|line| line.contains("Egg") && line.contains("Can't go below 4")));
+    assert!(workspace.stdout(&["add", "Fridge", "Jam", "3"]).contains("New item `Jam` successfully added"));
+    assert!(workspace.stdout(&["rules", "-c", "--expr=quantity < 3", "Fridge", "Milk"]).contains("successfully created for `Milk`"));
+    let rules = workspace.stdout(&["rules", "-l", "Fridge"]);
+    assert!(rules.contains("can't get below 4"));
+    assert!(rules.contains("quantity < 3"));
+    assert!(workspace.stdout(&["raport", "Fridge"]).lines().any(|line| line.contains("Milk") && line.contains("CRITICAL")));
+}
+
+#[test]
+fn help_for_every_command() {
+    let workspace = Workspace::new();
+
+    // The commands as the general help lists them, `\t<command> - <summary>`
+    let help = workspace.stdout(&["-h"]);
+    let commands: Vec<&str> = help.lines()
+        .skip_while(|line| *line != "Commands:")
+        .filter_map(|line| line.strip_prefix('\t')?.split_once(" - ").map(|(command, _)| command))
+        .collect();
+    assert!(commands.len() > 20, "no commands in:\n{}", help);
+
+    for command in commands {
+        assert!(workspace.stdout(&[command, "-h"]).contains("Usage:"), "no help for `{}`", command);
+    }
+}
+
+#[test]
+fn completions() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["completions"]);
+    check_arities(&workspace, &["__complete"]);
+    check_arities(&workspace, &["__complete", "--"]);
+    assert!(workspace.stdout(&["completions", "bash"]).contains("complete -F _deposito deposito"));
+    assert_eq!(workspace.stdout(&["__complete", "--", "list", "Fr"]), "Fridge\n");
+    assert_eq!(workspace.stdout(&["__complete", "--", "remove", "-i", "Fridge", "M"]), "Milk\n");
+    assert_eq!(workspace.stdout(&["__complete", "--", "view", "--format=j"]), "--format=json\n");
+}
+
+#[test]
+fn man() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["man"]);
+    check_arities(&workspace, &["man", "--markdown"]);
+    assert!(workspace.stdout(&["man"]).starts_with(".TH DEPOSITO 1"));
+    assert!(workspace.stdout(&["man", "rules"]).contains(".SH EXPRESSIONS"));
+}
+
+#[test]
+fn markdown_reference_is_up_to_date() {
+    let workspace = Workspace::new();
+    let reference = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/docs/cli.md")).expect("unable to read docs/cli.md");
+    assert!(
+        workspace.stdout(&["man", "--markdown"]) == reference,
+        "docs/cli.md is out of date, regenerate it with `deposito man --markdown > docs/cli.md`",
+    );
+}
+
+#[test]
+fn suggestions() {
+    let workspace = Workspace::new();
+    assert!(workspace.stdout(&["lsit", "Fridge"]).contains("Did you mean `list`?"));
+    assert!(workspace.stdout(&["list", "--fromat=json", "Fridge"]).contains("Did you mean `--format`?"));
+    assert_eq!(workspace.stdout(&["list", "Fridg"]), "Warehouse `Fridg` not found. Did you mean `Fridge`?\n");
+    assert_eq!(workspace.stdout(&["edit", "-q", "1", "Fridge", "Eg"]), "Item `Eg` not found in the `Fridge` warehouse. Did you mean `Egg`?\n");
+    assert_eq!(workspace.stdout(&["list", "Xyzzy"]), "Warehouse `Xyzzy` not found.\n");
+}
+
+#[test]
+fn shell() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["shell"]);
+
+    let output = workspace.run_with_input(&["shell"], "use Fridg\nuse Fridge\nadd Egg 2\nlist\nuse -\nlist\nexit\nlist Fridge\n");
+    assert!(output.contains("Did you mean `Fridge`?"));
+    assert!(output.contains("Using the `Fridge` warehouse."));
+    assert!(output.contains("Successfully added `2` quantity to `Egg` in the `Fridge` warehouse."));
+    assert!(output.lines().any(|line| line.trim_start().starts_with("Egg") && line.trim_end().ends_with('8')));
+    assert!(output.contains("`list` is missing the <warehouse> argument."));
+    // Nothing after `exit` runs
+    assert_eq!(output.matches("Available items").count(), 1);
+}
+
+#[test]
+fn tui() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["tui"]);
+
+    // Without a terminal the keys come from stdin and the last screen gets printed
+    let screen = workspace.run_with_input(&["tui", "Fridge"], "---/mil\r+\x1bjrOat milk\rex\r");
+    let line = |item: &str| screen.lines().find(|line| line.contains(item)).unwrap_or_default().to_string();
+    assert!(line("deposito · Fridge").ends_with("1 CRITICAL"));
+    assert!(line("Egg").starts_with("!Fridge"));
+    assert!(line("Egg").contains("3  CRITICAL"));
+    // The renamed item stays selected, still being edited after the invalid quantity
+    assert!(line("Oat milk").contains("Oat milk  x"));
+    assert!(screen.contains("`x` isn't a quantity."));
+
+    let list = workspace.stdout(&["list", "Fridge"]);
+    assert!(list.lines().any(|line| line.trim_start().starts_with("Oat milk") && line.trim_end().ends_with('3')));
+    assert!(workspace.stdout(&["tui", "Fridg"]).contains("Did you mean `Fridge`?"));
+}
+
+#[test]
+fn batch() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["batch"]);
+
+    let file = workspace.path.join("delivery.txt");
+    std::fs::write(&file, "# Delivery\nadd Fridge Egg 2\n\ndeposito add Fridge \"Oat milk\" 3\nadd Fridg Butter 1\n").expect("unable to write the batch");
+    let output = workspace.stdout(&["batch", "delivery.txt"]);
+    assert!(output.contains("Line 2: add Fridge Egg 2"));
+    assert!(output.contains("Line 4: deposito add Fridge \"Oat milk\" 3"));
+    assert!(output.contains("1 of 3 command(s) failed, on line(s) 5."));
+
+    // Invalid lines stop an atomic batch before anything runs, failing ones roll it back
+    let output = workspace.run_with_input(&["batch", "--atomic"], "add Fridge Jam 1\nadd Fridge Tea x\n");
+    assert!(output.contains("Line 2: Invalid value `x`"));
+    assert!(output.contains("Nothing was run."));
+    let output = workspace.run_with_input(&["batch", "--atomic", "-"], "add Fridge Jam 1\nedit Fridge Egg -q 1\nedit Fridge Tea -q 1\n");
+    assert!(output.contains("Line 3 failed, none of the changes were saved."));
+    let output = workspace.run_with_input(&["batch", "--atomic"], "add Fridge Jam 1\nedit Fridge Egg -q 1\n");
+    assert!(output.contains("Ran 2 command(s) from stdin, all changes saved."));
+
+    let list = workspace.stdout(&["list", "Fridge"]);
+    let quantity = |item: &str| list.lines().find(|line| line.trim_start().starts_with(item)).and_then(|line| line.split_whitespace().last()).unwrap_or_default().to_string();
+    assert_eq!(quantity("Egg"), "1");
+    assert_eq!(quantity("Oat milk"), "3");
+    assert_eq!(quantity("Jam"), "1");
+
+    assert!(workspace.run_with_input(&["batch"], "shell\n").contains("`shell` can't be run in a batch."));
+
+    // Backups and restores can't run within the transaction of an atomic batch
+    let output = workspace.run_with_input(&["batch", "--atomic"], "add Fridge Egg 2\nbackup\n");
+    assert!(output.contains("Line 2: `backup` can't be run in an atomic batch."));
+    assert!(output.contains("Nothing was run."));
+    assert!(workspace.run_with_input(&["batch", "--atomic"], "restore before.db\n").contains("`restore` can't be run in an atomic batch."));
+    assert!(workspace.run_with_input(&["batch"], "backup before.db\n").contains("Backed up the database to `before.db`."));
+}
+
+#[test]
+fn import() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["import"]);
+
+    let file = workspace.path.join("delivery.csv");
+    std::fs::write(&file, "Item;Qty;Notes;Min;Unit;Tags\nJam;2;strawberry;1;jar;sweet|breakfast\nEgg;4;;;;\n").expect("unable to write the csv");
+    let output = workspace.stdout(&["import", "csv", "Fridge", "delivery.csv", "--dry-run"]);
+    assert!(output.lines().any(|line| line.contains("Egg") && line.contains("6 → 10")));
+    assert!(output.contains("Dry run, nothing was imported into the `Fridge` warehouse: 1 new, 1 updated, 0 removed, 0 unchanged."));
+    let output = workspace.stdout(&["import", "csv", "Fridge", "delivery.csv"]);
+    assert!(output.contains("Imported into the `Fridge` warehouse: 1 new, 1 updated, 0 removed, 0 unchanged."));
+
+    let list = workspace.stdout(&["list", "Fridge"]);
+    assert!(list.lines().any(|line| line.trim_start().starts_with("Egg") && line.trim_end().ends_with("10")));
+    assert!(list.contains("jar"));
+    assert!(list.contains("sweet, breakfast"));
+    assert!(workspace.stdout(&["rules", "-l", "Fridge"]).contains("can't get below 1"));
+
+    // Replacing keeps only the items of the file, headerless files can be mapped by `--columns`
+    std::fs::write(&file, "fresh,Egg,3\n,Jam,2\n").expect("unable to write the csv");
+    let output = workspace.stdout(&["import", "csv", "Fridge", "delivery.csv", "--mode=replace", "--columns=description,name,quantity"]);
+    assert!(output.lines().any(|line| line.contains("Milk") && line.trim_end().ends_with("removed")));
+    assert!(output.contains("0 new, 1 updated, 1 removed, 1 unchanged."));
+    let list = workspace.stdout(&["list", "Fridge"]);
+    assert!(!list.contains("Milk"));
+    assert!(list.contains("fresh"));
+
+    // A min doesn't replace an expression rule, the preview says it's skipped
+    workspace.run(&["add", "Fridge", "Tea", "1"]);
+    workspace.run(&["rules", "-c", "--expr=quantity < 1", "Fridge", "Tea"]);
+    std::fs::write(&file, "name,quantity,min\nTea,5,3\n").expect("unable to write the csv");
+    let output = workspace.stdout(&["import", "csv", "Fridge", "delivery.csv", "--mode=upsert", "--dry-run"]);
+    assert!(output.contains("Skipped the min of `Tea`, its rule `quantity < 1` is kept."));
+    assert!(workspace.stdout(&["import", "csv", "Fridge", "delivery.csv", "--mode=upsert"]).contains("0 new, 1 updated, 0 removed, 0 unchanged."));
+    let rules = workspace.stdout(&["rules", "-l", "Fridge"]);
+    assert!(rules.contains("quantity < 1"));
+    assert!(!rules.contains("can't get below 3"));
+
+    let output = workspace.run_with_input(&["import", "csv", "Fridge", "-"], "Tea,x\nEgg,1\nEgg,2\n");
+    assert!(output.contains("Line 1: invalid quantity `x`"));
+    assert!(output.contains("Line 3: `Egg` is already on line 2."));
+    assert!(output.contains("Nothing was imported."));
+}
+
+#[test]
+fn export_and_import_json() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["export"]);
+    workspace.run(&["edit", "Fridge", "Egg", "-q", "5"]);
+
+    let document: serde_json::Value = serde_json::from_str(&workspace.stdout(&["export", "--format=json"])).expect("the export isn't JSON");
+    assert_eq!(document["version"], 1);
+    let egg = &document["warehouses"][0]["items"][0];
+    assert_eq!(egg["name"], "Egg");
+    assert_eq!(egg["quantity"], 5);
+    assert_eq!(egg["rule"]["gets_below_quantity"], 4);
+    assert_eq!(egg["movements"][1]["delta"], -1);
+    assert!(workspace.stdout(&["export", "backup.json"]).contains("Exported 1 warehouse(s), 2 item(s), 1 rule(s) and 3 movement(s)"));
+
+    // The test data clashes with the items of the workspace
+    let data = concat!(env!("CARGO_MANIFEST_DIR"), "/deposito/deposito_data.json");
+    let output = workspace.stdout(&["import", "json", data]);
+    assert!(output.contains("`Egg` already exists in the `Fridge` warehouse."));
+    assert!(output.contains("Nothing was imported"));
+    let output = workspace.stdout(&["import", "json", data, "--on-conflict=skip", "--dry-run"]);
+    assert!(output.contains("Dry run, nothing was imported: 2 new warehouse(s), 4 new item(s), 0 replaced, 2 skipped"));
+    let output = workspace.stdout(&["import", "json", "Fridge", data, "--on-conflict=overwrite"]);
+    assert!(output.contains("Imported 0 new warehouse(s), 2 new item(s), 2 replaced, 0 skipped, 2 rule(s) and 0 movement(s)."));
+    assert!(workspace.stdout(&["list", "Fridge"]).contains("Ketchup"));
+    assert!(!workspace.stdout(&["view"]).contains("Garage"));
+
+    // Restoring the backup brings back the replaced items with their movements
+    workspace.stdout(&["import", "json", "backup.json", "--on-conflict=overwrite"]);
+    let list = workspace.stdout(&["list", "Fridge"]);
+    assert!(list.lines().any(|line| line.trim_start().starts_with("Egg") && line.trim_end().ends_with("5")));
+    let document: serde_json::Value = serde_json::from_str(&workspace.stdout(&["export"])).expect("the export isn't JSON");
+    let items = document["warehouses"][0]["items"].as_array().expect("no items");
+    assert_eq!(items.iter().find(|item| item["name"] == "Egg").map(|item| item["movements"].as_array().map(Vec::len)), Some(Some(2)));
+
+    assert!(workspace.run_with_input(&["import", "json", "-"], "{\"version\": 2}").contains("newer than this deposito supports"));
+    assert!(workspace.stdout(&["export", "--format=csv"]).contains("only `json`"));
+    assert!(workspace.stdout(&["import", "csv", "backup.json"]).contains("needs the <warehouse>"));
+}
+
+#[test]
+fn export_and_import_same_names() {
+    let workspace = Workspace::new();
+    workspace.run(&["create", "Pantry"]);
+    workspace.run(&["add", "Pantry", "Egg", "3"]);
+    workspace.run(&["edit", "Pantry", "Egg", "-q", "2"]);
+
+    // Each warehouse's `Egg` keeps its own movements
+    let movements = |document: &serde_json::Value, warehouse: usize| -> Vec<i64> {
+        let items = document["warehouses"][warehouse]["items"].as_array().expect("no items");
+        let egg = items.iter().find(|item| item["name"] == "Egg").expect("no `Egg`");
+        egg["movements"].as_array().expect("no movements").iter().map(|movement| movement["delta"].as_i64().unwrap_or_default()).collect()
+    };
+    let before: serde_json::Value = serde_json::from_str(&workspace.stdout(&["export"])).expect("the export isn't JSON");
+    assert_eq!(movements(&before, 0), vec![6]);
+    assert_eq!(movements(&before, 1), vec![3, -1]);
+
+    workspace.run(&["export", "both.json"]);
+    assert!(workspace.stdout(&["import", "json", "both.json", "--on-conflict=overwrite"]).contains("3 replaced"));
+    let after: serde_json::Value = serde_json::from_str(&workspace.stdout(&["export"])).expect("the export isn't JSON");
+    assert_eq!(before["warehouses"], after["warehouses"]);
+}
+
+#[test]
+fn backup_and_restore() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["backup"]);
+    check_arities(&workspace, &["restore"]);
+
+    let backups = workspace.path.join("deposito").join("backups");
+    let count = || std::fs::read_dir(&backups).expect("no backup directory").count();
+    for _ in 0..3 {
+        assert!(workspace.stdout(&["backup", "--keep=2"]).contains("Backed up the database to `deposito/backups/deposito-"));
+    }
+    assert_eq!(count(), 2);
+    assert!(workspace.stdout(&["backup", "before.db"]).contains("Backed up the database to `before.db`."));
+    assert!(workspace.stdout(&["backup", "before.db"]).contains("backups never overwrite files"));
+
+    workspace.run(&["remove", "-i", "Fridge", "Egg", "--yes", "--purge"]);
+    workspace.run(&["create", "Pantry"]);
+    let output = workspace.stdout(&["restore", "before.db"]);
+    assert!(output.contains("Restored the database from `before.db`."));
+    assert_eq!(count(), 3);
+    assert!(workspace.stdout(&["list", "Fridge"]).contains("Egg"));
+    assert!(!workspace.stdout(&["view"]).contains("Pantry"));
+    assert!(workspace.stdout(&["rules", "-l", "Fridge"]).contains("can't get below 4"));
+
+    // Backups of the first version are upgraded on the way in
+    write_first_database(&workspace.path.join("first.db"));
+    workspace.run(&["rules", "-r", "Fridge", "Egg", "--yes"]);
+    assert!(workspace.stdout(&["restore", "first.db"]).contains("Restored the database from `first.db`."));
+    assert!(workspace.stdout(&["rules", "-l", "Fridge"]).contains("can't get below 4"));
+    assert!(workspace.stdout(&["rules", "-c", "--expr=quantity < 3", "Fridge", "Milk"]).contains("successfully created for `Milk`"));
+    assert_eq!(count(), 4);
+
+    std::fs::write(workspace.path.join("notes.db"), "not a database").expect("unable to write the file");
+    assert!(workspace.stdout(&["restore", "notes.db"]).contains("Can't restore `notes.db`, it isn't a database"));
+
+    // A database of a newer schema is refused before anything changes
+    let newer = workspace.path.join("newer.db");
+    std::fs::copy(workspace.path.join("before.db"), &newer).expect("unable to copy the backup");
+    let mut header = std::fs::read(&newer).expect("unable to read the backup");
+    // `PRAGMA user_version` is a big-endian integer at offset 60 of the header
+    header[60..64].copy_from_slice(&99u32.to_be_bytes());
+    std::fs::write(&newer, header).expect("unable to write the backup");
+    assert!(workspace.stdout(&["restore", "newer.db"]).contains("made by a newer version of deposito (schema version 99"));
+    assert_eq!(count(), 4);
+}
+
+#[test]
+fn snapshots() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["snapshot"]);
+    check_arities(&workspace, &["diff"]);
+
+    assert!(workspace.stdout(&["snapshot", "create", "march"]).contains("Snapshot `march` of 2 item(s) taken."));
+    assert!(workspace.stdout(&["snapshot", "create", "march"]).contains("already exists"));
+    assert!(workspace.stdout(&["diff", "march"]).contains("No changes from `march` to now."));
+
+    workspace.run(&["edit", "Fridge", "Egg", "-q", "3"]);
+    workspace.run(&["remove", "-i", "Fridge", "Milk", "--yes", "--purge"]);
+    workspace.run(&["add", "Fridge", "Jam", "2"]);
+    workspace.run(&["rules", "-e", "Fridge", "Egg", "2"]);
+    let output = workspace.stdout(&["diff", "march"]);
+    assert!(output.lines().any(|line| line.contains("Egg") && line.contains("6 → 3 (-3)") && line.contains("can't get below 4 → can't get below 2")));
+    assert!(output.lines().any(|line| line.contains('+') && line.contains("Jam")));
+    assert!(output.lines().any(|line| line.contains('-') && line.contains("Milk")));
+    assert!(output.contains("1 added, 1 removed, 1 changed."));
+
+    workspace.run(&["snapshot", "create", "april"]);
+    let csv = workspace.stdout(&["diff", "april", "march", "--format=csv"]);
+    assert!(csv.starts_with("warehouse,name,change,quantity_before,quantity_after,delta,rule_before,rule_after\n"));
+    assert!(csv.contains("Fridge,Egg,changed,3,6,3,can't get below 2,can't get below 4\n"));
+    assert!(csv.contains("Fridge,Milk,added,,2,2,,\n"));
+
+    assert!(workspace.stdout(&["diff", "mrch"]).contains("Snapshot `mrch` not found. Did you mean `march`?"));
+    workspace.run(&["snapshot", "remove", "march"]);
+    let list = workspace.stdout(&["snapshot", "list"]);
+    assert!(list.contains("april"));
+    assert!(!list.contains("march"));
+}
+
+#[test]
+fn undo_and_redo() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["undo"]);
+    check_arities(&workspace, &["redo"]);
+
+    // Removing items with rules takes the rules along, and both come back
+    workspace.run(&["edit", "Fridge", "Egg", "-q", "3"]);
+    assert!(workspace.stdout(&["remove", "-ia", "Fridge", "--yes", "--purge"]).contains("Removed successfully."));
+    assert!(workspace.stdout(&["undo"]).contains("Undid `remove -i -a -y --purge Fridge`."));
+    let list = workspace.stdout(&["list", "Fridge"]);
+    assert!(list.lines().any(|line| line.trim_start().starts_with("Egg") && line.trim_end().ends_with("3")));
+    assert!(list.contains("Milk"));
+    assert!(workspace.stdout(&["rules", "-l", "Fridge"]).contains("can't get below 4"));
+
+    let output = workspace.stdout(&["undo", "2"]);
+    assert!(output.contains("Undid `edit --quantity=3 Fridge Egg`."));
+    assert!(output.contains("Undid `rules -c Fridge Egg 4`."));
+    assert!(workspace.stdout(&["rules", "-l", "Fridge"]).contains("No rules"));
+    let list = workspace.stdout(&["undo", "--list"]);
+    assert!(list.lines().any(|line| line.contains("undone") && line.contains("remove -i -a -y --purge Fridge")));
+    assert!(list.lines().any(|line| line.trim_start().starts_with("1 ") && line.contains("add Fridge Milk 2")));
+
+    assert!(workspace.stdout(&["redo"]).contains("Redid `rules -c Fridge Egg 4`."));
+    assert!(workspace.stdout(&["rules", "-l", "Fridge"]).contains("can't get below 4"));
+
+    // A new command drops what is left to redo
+    workspace.run(&["add", "Fridge", "Jam", "2"]);
+    assert!(workspace.stdout(&["redo"]).contains("Nothing to redo."));
+
+    // Changes from outside of the journal make it refuse
+    workspace.run_with_input(&["import", "csv", "Fridge", "-"], "Jam,5\n");
+    assert!(workspace.stdout(&["undo"]).contains("Can't undo `add Fridge Jam 2`"));
+    assert!(workspace.stdout(&["list", "Fridge"]).contains("Jam"));
+
+    let output = workspace.stdout(&["undo", "10"]);
+    assert!(output.contains("Can't undo"));
+    workspace.run(&["remove", "-w", "Fridge", "-y", "--purge"]);
+    assert!(workspace.stdout(&["undo"]).contains("Undid `remove -w -y --purge Fridge`."));
+    assert!(workspace.stdout(&["view"]).contains("Fridge"));
+}
+
+#[test]
+fn raport_spreadsheets() {
+    let workspace = Workspace::new();
+    workspace.run(&["edit", "Fridge", "Egg", "-q", "3"]);
+
+    // Entries are stored uncompressed, so their XML can be looked for in the file as is
+    let output = workspace.stdout(&["raport", "-a", "--output=raport.xlsx"]);
+    assert!(output.contains("Raport of 1 warehouse(s) written to `raport.xlsx`."));
+    let xlsx = String::from_utf8_lossy(&std::fs::read(workspace.path.join("raport.xlsx")).expect("no raport.xlsx")).to_string();
+    assert!(xlsx.starts_with("PK\u{3}\u{4}"));
+    assert!(xlsx.contains(r#"<sheet name="Summary" sheetId="1" r:id="rId1"/><sheet name="Fridge" sheetId="2" r:id="rId2"/>"#));
+    assert!(xlsx.contains(r#"<c r="A2" t="inlineStr"><is><t xml:space="preserve">Egg</t></is></c><c r="B2"><v>3</v></c><c r="C2"><v>4</v></c><c r="D2" t="inlineStr"><is><t xml:space="preserve">CRITICAL</t></is></c>"#));
+    assert!(xlsx.contains(r#"<formula>$D2="CRITICAL"</formula>"#));
+
+    workspace.stdout(&["raport", "Fridge", "--output=raport.ods"]);
+    let ods = std::fs::read(workspace.path.join("raport.ods")).expect("no raport.ods");
+    assert!(ods[30..].starts_with(b"mimetypeapplication/vnd.oasis.opendocument.spreadsheet"));
+    let ods = String::from_utf8_lossy(&ods);
+    assert!(ods.contains(r#"<table:table table:name="Fridge">"#));
+    assert!(ods.contains("is-true-formula([.$D2]=&quot;CRITICAL&quot;)"));
+
+    assert!(workspace.stdout(&["raport", "-a", "--output=raport.txt"]).contains("has to end in .xlsx, .ods or .pdf"));
+}
+
+#[test]
+fn raport_html_and_markdown() {
+    let workspace = Workspace::new();
+    workspace.run(&["edit", "Fridge", "Egg", "-q", "3"]);
+
+    let html = workspace.stdout(&["raport", "-a", "--format=html"]);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<style>"));
+    assert!(html.contains("<h2>Fridge</h2>"));
+    assert!(html.contains(r#"<tr><td>Egg</td><td class="right">3</td><td class="red">CRITICAL</td>"#));
+    assert!(!html.contains("Generated on"));
+
+    let markdown = workspace.stdout(&["raport", "Fridge", "--format=markdown", "--stamp"]);
+    assert!(markdown.starts_with("# Raport\n\n## Fridge\n\n| Item | Quantity | Status | Rule | Forecast |\n| --- | ---: | --- | --- | --- |\n| Egg | 3 | CRITICAL | Can't go below 4 |"));
+    let stamp = markdown.lines().last().unwrap_or_default();
+    assert!(stamp.starts_with("_Generated on ") && stamp.contains(" UTC in `") && stamp.contains("deposito-cli-"), "{}", stamp);
+
+    assert!(workspace.stdout(&["raport", "Fridge", "--stamp"]).contains("Generated on "));
+    assert!(workspace.stdout(&["raport", "Fridge", "--format=json", "--stamp"]).contains("`--stamp` only applies"));
+    assert!(workspace.stdout(&["list", "Fridge", "--format=markdown"]).contains("only applies to `raport`"));
+}
+
+// Follows the cross-reference table of a PDF to its objects, checking that every offset and
+// stream length is right, and returns the text shown on each page in order
+fn pdf_pages(pdf: &[u8]) -> Vec<Vec<String>> {
+    let text = String::from_utf8_lossy(pdf).to_string();
+    assert!(pdf.starts_with(b"%PDF-1.4\n"));
+    let startxref = text.rfind("startxref\n").expect("no startxref");
+    let xref: usize = text[startxref + 10..].lines().next().and_then(|line| line.parse().ok()).expect("no xref offset");
+    assert!(pdf[xref..].starts_with(b"xref\n0 "), "startxref doesn't point at the xref table");
+
+    let header = std::str::from_utf8(&pdf[xref..xref + 20]).expect("invalid xref");
+    let count: usize = header.lines().nth(1).and_then(|line| line.split(' ').nth(1)).and_then(|count| count.parse().ok()).expect("no object count");
+    let entries = xref + header.find('\n').expect("no xref header") + 1;
+    let entries = entries + std::str::from_utf8(&pdf[entries..]).unwrap_or_default().find('\n').unwrap_or_default() + 1;
+
+    let object = |number: usize| -> &[u8] {
+        let entry = std::str::from_utf8(&pdf[entries + 20 * number..entries + 20 * (number + 1)]).expect("invalid xref entry");
+        let offset: usize = entry[..10].parse().expect("invalid offset");
+        let start = format!("{} 0 obj\n", number);
+        assert!(pdf[offset..].starts_with(start.as_bytes()), "object {} isn't where the xref says", number);
+        let body = &pdf[offset + start.len()..];
+        let end = body.windows(8).position(|window| window == b"\nendobj\n").expect("unterminated object");
+        &body[..end]
+    };
+    let object_text = |number: usize| String::from_utf8_lossy(object(number)).to_string();
+    let reference = |dictionary: &str, key: &str| -> usize {
+        let start = dictionary.find(key).unwrap_or_else(|| panic!("no {} in {}", key, dictionary)) + key.len() + 1;
+        dictionary[start..].split(' ').next().and_then(|number| number.parse().ok()).expect("invalid reference")
+    };
+
+    let pages_object = object_text(reference(&object_text(1), "/Pages"));
+    let kids = &pages_object[pages_object.find("/Kids [").expect("no kids") + 7..];
+    let kids: Vec<usize> = kids[..kids.find(']').expect("unterminated kids")].split(" 0 R").filter_map(|kid| kid.trim().parse().ok()).collect();
+    assert!(pages_object.contains(&format!("/Count {} ", kids.len())));
+    assert!(kids.iter().all(|kid| *kid < count));
+
+    kids.iter().map(|kid| {
+        let contents = object(reference(&object_text(*kid), "/Contents"));
+        let dictionary = String::from_utf8_lossy(contents).to_string();
+        let length = reference(&dictionary, "/Length");
+        let stream = contents.windows(7).position(|window| window == b"stream\n").expect("no stream") + 7;
+        assert_eq!(&contents[stream + length..], b"endstream", "wrong stream length");
+
+        let stream = String::from_utf8_lossy(&contents[stream..stream + length]).to_string();
+        stream.lines().filter_map(|line| Some(line[line.find(" Td (")? + 5..line.rfind(") Tj")?].to_string())).collect()
+    }).collect()
+}
+
+#[test]
+fn raport_pdf() {
+    let workspace = Workspace::new();
+    workspace.run(&["edit", "Fridge", "Egg", "-q", "3"]);
+    workspace.run(&["create", "Workshop"]);
+    let parts: String = (1..=60).map(|part| format!("Part {},{},,2\n", part, part % 5)).collect();
+    workspace.run_with_input(&["import", "csv", "Workshop", "-"], &parts);
+
+    let output = workspace.stdout(&["raport", "-a", "--output=raport.pdf"]);
+    assert!(output.contains("Raport of 2 warehouse(s) written to `raport.pdf`."));
+    let pages = pdf_pages(&std::fs::read(workspace.path.join("raport.pdf")).expect("no raport.pdf"));
+
+    // Title page, Fridge, and Workshop continuing on a second page
+    assert_eq!(pages.len(), 4);
+    assert_eq!(&pages[0][..2], &["Raport", "All warehouses"]);
+    assert!(pages[0][2].starts_with("Generated on "));
+    assert!(pages[0].iter().any(|text| text == "Summary"));
+    assert_eq!(&pages[1][..7], &["Fridge", "Item", "Quantity", "Status", "Rule", "Forecast", "Egg"]);
+    assert_eq!(pages[1][8], "CRITICAL");
+    assert_eq!(pages[2][0], "Workshop");
+    assert_eq!(pages[3][0], "Workshop \\(continued\\)");
+    assert_eq!(pages[3][1], "Item");
+    for (index, page) in pages.iter().enumerate() {
+        assert_eq!(page.last(), Some(&format!("Page {} of 4", index + 1)));
+    }
+
+    // CRITICAL is red, on a red row
+    let pdf = String::from_utf8_lossy(&std::fs::read(workspace.path.join("raport.pdf")).expect("no raport.pdf")).to_string();
+    assert!(pdf.contains("0.69 0.00 0.13 rg"));
+    assert!(pdf.contains("1.00 0.92 0.92 rg"));
+}
+
+#[test]
+fn templates() {
+    let workspace = Workspace::new();
+    check_arities(&workspace, &["template"]);
+    check_arities(&workspace, &["template", "create"]);
+
+    // Archived items stay behind, `--zero` empties the rest
+    workspace.run(&["add", "Fridge", "Old", "1"]);
+    workspace.run(&["archive", "-i", "Fridge", "Old"]);
+    assert!(workspace.stdout(&["create", "--from=Fridge", "--zero", "Cellar"]).contains("Copied 2 item(s) and 1 rule(s) from the `Fridge` warehouse."));
+    let list = workspace.stdout(&["list", "Cellar"]);
+    assert!(list.lines().any(|line| line.trim_start().starts_with("Egg") && line.trim_end().ends_with('0')));
+    assert!(!list.contains("Old"));
+    assert!(workspace.stdout(&["rules", "-l", "Cellar"]).contains("can't get below 4"));
+    assert!(workspace.stdout(&["create", "--from=Nope", "Attic"]).contains("Warehouse `Nope` not found."));
+
+    assert!(workspace.stdout(&["template", "create", "site", "Fridge"]).contains("Template `site` of 2 item(s) made from the `Fridge` warehouse."));
+    assert!(workspace.stdout(&["template", "create", "site", "Fridge"]).contains("Template `site` already exists."));
+    assert!(workspace.stdout(&["template", "list"]).lines().any(|line| line.trim_start().starts_with("site") && line.split_whitespace().rev().take(2).eq(["1", "2"])));
+    assert!(workspace.stdout(&["create", "--template=sit", "Site"]).contains("Did you mean `site`?"));
+    workspace.run(&["create", "--template=site", "Site"]);
+    assert!(workspace.stdout(&["list", "Site"]).lines().any(|line| line.trim_start().starts_with("Egg") && line.trim_end().ends_with('6')));
+
+    // The `default` template goes into every new warehouse
+    workspace.run(&["template", "create", "default", "Cellar"]);
+    assert!(workspace.stdout(&["create", "Garage"]).contains("from the `default` template."));
+    assert!(workspace.stdout(&["list", "Garage"]).contains("Milk"));
+    assert!(workspace.stdout(&["create", "--empty", "Shed"]).contains("`Shed` successfully created!"));
+    assert!(workspace.stdout(&["list", "Shed"]).contains("contains no items"));
+    assert!(workspace.stdout(&["create", "--empty", "--from=Fridge", "Barn"]).contains("Only one of"));
+
+    assert!(workspace.stdout(&["undo"]).contains("Undid `create --empty Shed`."));
+    assert!(workspace.stdout(&["template", "remove", "site"]).contains("Template `site` removed."));
+    assert!(!workspace.stdout(&["template", "list"]).contains("site"));
+}